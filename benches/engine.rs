@@ -24,9 +24,18 @@
 //! - Multi-threaded concurrent transaction processing
 //! - Dispute lifecycle operations
 //! - Scaling with number of clients
+//! - Dense account table vs. the previous `DashMap` layout
+//! - `Decimal` vs. `FixedAmount` arithmetic and parsing
+//!
+//! Run `cargo bench --features fixed-point` to measure the engine benchmarks
+//! with fixed-point balances.
 
 use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
-use ledger_demo_rs::{ClientId, Engine, TransactionId, TransactionType};
+use dashmap::DashMap;
+use ledger_demo_rs::{
+    Account, ClientId, Engine, FixedAmount, TransactionError, TransactionId, TransactionQueue,
+    TransactionType,
+};
 use rayon::prelude::*;
 use rust_decimal::Decimal;
 use std::sync::Arc;
@@ -443,6 +452,229 @@ fn bench_contention(c: &mut Criterion) {
     group.finish();
 }
 
+// =============================================================================
+// Account Storage Comparison Benchmarks
+// =============================================================================
+
+/// The engine's previous account layout, kept here as a comparison baseline.
+///
+/// Every operation hashes into a `DashMap` shard lock and then takes the
+/// `Account`'s own mutex, i.e. two locks per transaction.
+struct DashMapEngine {
+    accounts: DashMap<ClientId, Account>,
+    transactions: TransactionQueue,
+}
+
+impl DashMapEngine {
+    fn new() -> Self {
+        Self {
+            accounts: DashMap::new(),
+            transactions: TransactionQueue::new(),
+        }
+    }
+
+    fn process(&self, transaction: TransactionType) -> Result<(), TransactionError> {
+        let client_id = transaction.client_id();
+        match transaction {
+            TransactionType::Deposit { .. } | TransactionType::Withdrawal { .. } => {
+                self.transactions.push(Arc::new(transaction))?;
+                self.accounts
+                    .entry(client_id)
+                    .or_insert_with(|| Account::new(client_id))
+                    .add_transaction(transaction)
+            }
+            _ => self
+                .accounts
+                .get_mut(&client_id)
+                .ok_or(TransactionError::TransactionNotFound)?
+                .add_transaction(transaction),
+        }
+    }
+}
+
+fn bench_contention_comparison(c: &mut Criterion) {
+    let mut group = c.benchmark_group("contention_comparison");
+    let total_ops = 10_000u32;
+
+    for num_clients in [1, 10, 100, 1_000, 10_000].iter() {
+        group.throughput(Throughput::Elements(total_ops as u64));
+        group.bench_with_input(
+            BenchmarkId::new("dense", num_clients),
+            num_clients,
+            |b, &num_clients| {
+                b.iter(|| {
+                    let engine = Arc::new(Engine::new());
+                    let tx_counter = AtomicU32::new(0);
+
+                    (0..total_ops).into_par_iter().for_each(|i| {
+                        let tx_id = tx_counter.fetch_add(1, Ordering::SeqCst);
+                        let client_id = (i % num_clients as u32) as u16 + 1;
+                        engine
+                            .process(make_deposit(client_id, tx_id, 10000))
+                            .unwrap();
+                    });
+
+                    black_box(&engine);
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("dashmap", num_clients),
+            num_clients,
+            |b, &num_clients| {
+                b.iter(|| {
+                    let engine = Arc::new(DashMapEngine::new());
+                    let tx_counter = AtomicU32::new(0);
+
+                    (0..total_ops).into_par_iter().for_each(|i| {
+                        let tx_id = tx_counter.fetch_add(1, Ordering::SeqCst);
+                        let client_id = (i % num_clients as u32) as u16 + 1;
+                        engine
+                            .process(make_deposit(client_id, tx_id, 10000))
+                            .unwrap();
+                    });
+
+                    black_box(&engine);
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_thread_scaling_comparison(c: &mut Criterion) {
+    let mut group = c.benchmark_group("thread_scaling_comparison");
+    let total_transactions = 100_000u32;
+
+    for num_threads in [1, 2, 4, 8].iter() {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(*num_threads)
+            .build()
+            .unwrap();
+
+        group.throughput(Throughput::Elements(total_transactions as u64));
+        group.bench_with_input(
+            BenchmarkId::new("dense", num_threads),
+            num_threads,
+            |b, _| {
+                b.iter(|| {
+                    let engine = Arc::new(Engine::new());
+                    let tx_counter = AtomicU32::new(0);
+
+                    pool.install(|| {
+                        (0..total_transactions).into_par_iter().for_each(|i| {
+                            let tx_id = tx_counter.fetch_add(1, Ordering::SeqCst);
+                            let client_id = (i % 1000) as u16 + 1;
+                            engine
+                                .process(make_deposit(client_id, tx_id, 10000))
+                                .unwrap();
+                        });
+                    });
+
+                    black_box(&engine);
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("dashmap", num_threads),
+            num_threads,
+            |b, _| {
+                b.iter(|| {
+                    let engine = Arc::new(DashMapEngine::new());
+                    let tx_counter = AtomicU32::new(0);
+
+                    pool.install(|| {
+                        (0..total_transactions).into_par_iter().for_each(|i| {
+                            let tx_id = tx_counter.fetch_add(1, Ordering::SeqCst);
+                            let client_id = (i % 1000) as u16 + 1;
+                            engine
+                                .process(make_deposit(client_id, tx_id, 10000))
+                                .unwrap();
+                        });
+                    });
+
+                    black_box(&engine);
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+// =============================================================================
+// Amount Representation Benchmarks
+// =============================================================================
+
+fn bench_amount_arithmetic(c: &mut Criterion) {
+    let mut group = c.benchmark_group("amount_arithmetic");
+    let count = 10_000i64;
+    let raws: Vec<i64> = (1..=count).map(|i| i * 7 + 1).collect();
+
+    group.throughput(Throughput::Elements(count as u64 * 2));
+    group.bench_function("decimal", |b| {
+        let amounts: Vec<Decimal> = raws.iter().map(|&r| Decimal::new(r, 4)).collect();
+        b.iter(|| {
+            let mut balance = Decimal::ZERO;
+            for amount in &amounts {
+                balance = balance.checked_add(*amount).unwrap();
+            }
+            for amount in &amounts {
+                balance = balance.checked_sub(*amount).unwrap();
+            }
+            black_box(balance)
+        })
+    });
+    group.bench_function("fixed", |b| {
+        let amounts: Vec<FixedAmount> = raws
+            .iter()
+            .map(|&r| FixedAmount::from_raw(i128::from(r)).unwrap())
+            .collect();
+        b.iter(|| {
+            let mut balance = FixedAmount::ZERO;
+            for amount in &amounts {
+                balance = balance.checked_add(*amount).unwrap();
+            }
+            for amount in &amounts {
+                balance = balance.checked_sub(*amount).unwrap();
+            }
+            black_box(balance)
+        })
+    });
+    group.finish();
+}
+
+fn bench_amount_parsing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("amount_parsing");
+    let inputs: Vec<String> = (1..=1_000i64)
+        .map(|i| Decimal::new(i * 12_345, 4).to_string())
+        .collect();
+
+    group.throughput(Throughput::Elements(inputs.len() as u64));
+    group.bench_function("decimal", |b| {
+        b.iter(|| {
+            for input in &inputs {
+                black_box(input.parse::<Decimal>().unwrap());
+            }
+        })
+    });
+    group.bench_function("fixed", |b| {
+        b.iter(|| {
+            for input in &inputs {
+                black_box(input.parse::<FixedAmount>().unwrap());
+            }
+        })
+    });
+    group.bench_function("decimal_to_fixed", |b| {
+        let decimals: Vec<Decimal> = inputs.iter().map(|i| i.parse().unwrap()).collect();
+        b.iter(|| {
+            for decimal in &decimals {
+                black_box(FixedAmount::from_decimal(*decimal).unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
 // =============================================================================
 // Memory/Allocation Benchmarks
 // =============================================================================
@@ -525,6 +757,14 @@ criterion_group!(
 
 criterion_group!(scaling, bench_thread_scaling, bench_contention,);
 
+criterion_group!(
+    storage,
+    bench_contention_comparison,
+    bench_thread_scaling_comparison,
+);
+
+criterion_group!(amounts, bench_amount_arithmetic, bench_amount_parsing,);
+
 criterion_group!(memory, bench_account_creation, bench_transaction_history,);
 
 criterion_main!(
@@ -533,5 +773,7 @@ criterion_main!(
     multi_client,
     multi_threaded,
     scaling,
+    storage,
+    amounts,
     memory
 );
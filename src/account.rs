@@ -29,10 +29,13 @@
 //! assert_eq!(account.available(), dec!(0.00));
 //! ```
 
+use crate::amount::{Balance, to_balance, to_decimal};
 use crate::base::{ClientId, TransactionId};
+use crate::config::{ChargebackPolicy, DisputePolicy, EngineConfig};
+use crate::history::{HistoryPage, HistoryQuery, StoredTransaction};
 use crate::transaction::TransactionStatus;
 use crate::{TransactionError, TransactionType};
-use parking_lot::Mutex;
+use parking_lot::{Mutex, MutexGuard};
 use rust_decimal::Decimal;
 use serde::ser::{SerializeStruct, Serializer};
 use serde::{Deserialize, Serialize};
@@ -58,6 +61,10 @@ pub struct AccountSnapshot {
     pub total: Decimal,
     /// Whether the account is frozen after a chargeback.
     pub locked: bool,
+    /// Number of transactions applied to the account; see
+    /// [`Account::version`]. Not part of the serialized report.
+    #[serde(default)]
+    pub version: u64,
 }
 
 impl Serialize for AccountSnapshot {
@@ -81,67 +88,287 @@ impl Serialize for AccountSnapshot {
     }
 }
 
-/// Tracks deposit amount and status for dispute resolution.
+/// Kind of a disputable transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RecordKind {
+    Deposit,
+    Withdrawal,
+}
+
+/// Tracks an accepted transaction's amount and status for dispute resolution
+/// and history queries.
 ///
 ///  Deposit (Applied) ──dispute──► Deposit (Inflight) ──resolve───► Deposit (Resolved)
 ///                                        │
 ///                                        └──chargeback──► Deposit (Voided) + Account Locked
+///
+/// Withdrawals follow the same state machine, but can only be disputed when
+/// [`DisputePolicy::DepositsAndWithdrawals`] is in effect.
 #[derive(Debug, Clone)]
-struct DepositRecord {
-    amount: Decimal,
+struct TransactionRecord {
+    kind: RecordKind,
+    amount: Balance,
     status: TransactionStatus,
 }
 
-#[derive(Debug)]
+/// Adds two balances, failing with [`TransactionError::Overflow`] instead of panicking.
+fn checked_add(lhs: Balance, rhs: Balance) -> Result<Balance, TransactionError> {
+    lhs.checked_add(rhs).ok_or(TransactionError::Overflow)
+}
+
+/// Subtracts two balances, failing with [`TransactionError::Overflow`] instead of panicking.
+fn checked_sub(lhs: Balance, rhs: Balance) -> Result<Balance, TransactionError> {
+    lhs.checked_sub(rhs).ok_or(TransactionError::Overflow)
+}
+
+#[derive(Debug, Clone)]
 struct AccountData {
     client_id: ClientId,
-    available: Decimal,
-    held: Decimal,
+    available: Balance,
+    held: Balance,
     locked: bool,
-    /// Deposits indexed by transaction ID for dispute lookup.
-    deposits: HashMap<TransactionId, DepositRecord>,
+    /// Accepted deposits and withdrawals indexed by transaction ID.
+    records: HashMap<TransactionId, TransactionRecord>,
+    /// Accepted deposits and withdrawals in the order they were applied.
+    history: Vec<TransactionId>,
+    /// Number of successfully applied transactions.
+    version: u64,
 }
 
 impl AccountData {
     fn new(client_id: ClientId) -> Self {
         Self {
             client_id,
-            available: Decimal::ZERO,
-            held: Decimal::ZERO,
+            available: Balance::ZERO,
+            held: Balance::ZERO,
             locked: false,
-            deposits: HashMap::new(),
+            records: HashMap::new(),
+            history: Vec::new(),
+            version: 0,
         }
     }
 
+    /// Returns `true` if nothing has been applied to the account yet.
+    fn is_pristine(&self) -> bool {
+        self.available == Balance::ZERO
+            && self.held == Balance::ZERO
+            && !self.locked
+            && self.history.is_empty()
+            && self.version == 0
+    }
+
     fn assert_invariants(&self) {
         debug_assert!(
-            self.available >= Decimal::ZERO,
+            self.available >= Balance::ZERO,
             "Invariant violated: available balance went negative: {}",
             self.available
         );
         debug_assert!(
-            self.held >= Decimal::ZERO,
+            self.held >= Balance::ZERO,
             "Invariant violated: held balance went negative: {}",
             self.held
         );
     }
 
+    /// Records an accepted deposit or withdrawal.
+    fn record(&mut self, transaction_id: TransactionId, kind: RecordKind, amount: Balance) {
+        self.records.insert(
+            transaction_id,
+            TransactionRecord {
+                kind,
+                amount,
+                status: TransactionStatus::Applied,
+            },
+        );
+        self.history.push(transaction_id);
+    }
+
+    /// Looks up the transaction a dispute, resolve or chargeback refers to.
+    ///
+    /// Withdrawals are not found unless `config` makes them disputable.
+    fn disputable(
+        &self,
+        transaction_id: TransactionId,
+        config: &EngineConfig,
+    ) -> Result<&TransactionRecord, TransactionError> {
+        self.records
+            .get(&transaction_id)
+            .filter(|record| {
+                record.kind == RecordKind::Deposit
+                    || config.disputable == DisputePolicy::DepositsAndWithdrawals
+            })
+            .ok_or(TransactionError::TransactionNotFound)
+    }
+
+    /// Returns a stored transaction as originally requested, with its status.
+    fn stored(&self, transaction_id: TransactionId) -> Option<StoredTransaction> {
+        let record = self.records.get(&transaction_id)?;
+        let (client_id, amount) = (self.client_id, to_decimal(record.amount));
+        let transaction = match record.kind {
+            RecordKind::Deposit => TransactionType::Deposit {
+                client_id,
+                transaction_id,
+                amount,
+            },
+            RecordKind::Withdrawal => TransactionType::Withdrawal {
+                client_id,
+                transaction_id,
+                amount,
+            },
+        };
+        Some(StoredTransaction {
+            transaction,
+            status: record.status,
+        })
+    }
+
+    /// Applies a transaction under the dispute and chargeback policies in
+    /// `config`, bumping the version on success and tracing the resulting
+    /// balances.
+    fn apply(
+        &mut self,
+        transaction: TransactionType,
+        config: &EngineConfig,
+    ) -> Result<(), TransactionError> {
+        let result = self.transition(transaction, config);
+        match &result {
+            Ok(()) => {
+                self.version += 1;
+                tracing::trace!(
+                    available = %self.available,
+                    held = %self.held,
+                    locked = self.locked,
+                    version = self.version,
+                    "balances updated"
+                )
+            }
+            Err(error) => tracing::trace!(error = error.code(), "account unchanged"),
+        }
+        result
+    }
+
+    /// Moves funds and dispute state for [`apply`](Self::apply).
+    fn transition(
+        &mut self,
+        transaction: TransactionType,
+        config: &EngineConfig,
+    ) -> Result<(), TransactionError> {
+        if transaction.client_id() != self.client_id {
+            return Err(TransactionError::ClientMismatch);
+        }
+
+        match transaction {
+            TransactionType::Deposit {
+                transaction_id,
+                amount,
+                ..
+            } => {
+                // Process deposit
+                let amount = to_balance(amount)?;
+                self.deposit(amount)?;
+
+                // Track deposit for future disputes and history
+                self.record(transaction_id, RecordKind::Deposit, amount);
+            }
+            TransactionType::Withdrawal {
+                transaction_id,
+                amount,
+                ..
+            } => {
+                // Process withdrawal
+                let amount = to_balance(amount)?;
+                self.withdraw(amount)?;
+
+                // Track withdrawal for history (and disputes, if the policy allows)
+                self.record(transaction_id, RecordKind::Withdrawal, amount);
+            }
+            TransactionType::Dispute { transaction_id, .. } => {
+                // Look up the referenced transaction
+                let record = self.disputable(transaction_id, config)?;
+
+                // Only Applied transactions can be disputed
+                if record.status != TransactionStatus::Applied {
+                    return Err(TransactionError::AlreadyDisputed);
+                }
+
+                let (kind, amount) = (record.kind, record.amount);
+
+                match kind {
+                    // Move funds from available to held
+                    RecordKind::Deposit => self.hold_funds(amount)?,
+                    // Hold the withdrawn amount pending investigation
+                    RecordKind::Withdrawal => self.hold_withdrawal(amount)?,
+                }
+
+                // Update status to Inflight
+                self.records.get_mut(&transaction_id).unwrap().status = TransactionStatus::Inflight;
+            }
+            TransactionType::Resolve { transaction_id, .. } => {
+                // Look up the referenced transaction
+                let record = self.disputable(transaction_id, config)?;
+
+                // Only Inflight transactions can be resolved
+                if record.status != TransactionStatus::Inflight {
+                    return Err(TransactionError::NotDisputed);
+                }
+
+                let (kind, amount) = (record.kind, record.amount);
+
+                match kind {
+                    // Move funds from held back to available
+                    RecordKind::Deposit => self.release_funds(amount)?,
+                    // Drop the hold; the withdrawal stands
+                    RecordKind::Withdrawal => self.release_withdrawal(amount)?,
+                }
+
+                // Update status to Resolved
+                self.records.get_mut(&transaction_id).unwrap().status = TransactionStatus::Resolved;
+            }
+            TransactionType::Chargeback { transaction_id, .. } => {
+                // Look up the referenced transaction
+                let record = self.disputable(transaction_id, config)?;
+
+                // Only Inflight transactions can be charged back
+                if record.status != TransactionStatus::Inflight {
+                    return Err(TransactionError::NotDisputed);
+                }
+
+                let (kind, amount) = (record.kind, record.amount);
+
+                match kind {
+                    // Remove funds from held and, per policy, lock account
+                    RecordKind::Deposit => self.chargeback(amount, config.chargeback)?,
+                    // Return held funds to available and, per policy, lock account
+                    RecordKind::Withdrawal => self.reverse_withdrawal(amount, config.chargeback)?,
+                }
+
+                // Update status to Voided
+                self.records.get_mut(&transaction_id).unwrap().status = TransactionStatus::Voided;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Increases available balance.
-    fn deposit(&mut self, amount: Decimal) -> Result<(), TransactionError> {
-        if amount <= Decimal::ZERO {
+    fn deposit(&mut self, amount: Balance) -> Result<(), TransactionError> {
+        if amount <= Balance::ZERO {
             return Err(TransactionError::InvalidAmount);
         }
         if self.locked {
             return Err(TransactionError::AccountLocked);
         }
-        self.available += amount;
+        let available = checked_add(self.available, amount)?;
+        // Keep `available + held` representable so totals can always be reported.
+        checked_add(available, self.held)?;
+        self.available = available;
         self.assert_invariants();
         Ok(())
     }
 
     /// Decreases available balance.
-    fn withdraw(&mut self, amount: Decimal) -> Result<(), TransactionError> {
-        if amount <= Decimal::ZERO {
+    fn withdraw(&mut self, amount: Balance) -> Result<(), TransactionError> {
+        if amount <= Balance::ZERO {
             return Err(TransactionError::InvalidAmount);
         }
         if self.locked {
@@ -150,14 +377,14 @@ impl AccountData {
         if self.available < amount {
             return Err(TransactionError::InsufficientFunds);
         }
-        self.available -= amount;
+        self.available = checked_sub(self.available, amount)?;
         self.assert_invariants();
         Ok(())
     }
 
     /// Moves funds from available to held (dispute).
-    fn hold_funds(&mut self, amount: Decimal) -> Result<(), TransactionError> {
-        if amount <= Decimal::ZERO {
+    fn hold_funds(&mut self, amount: Balance) -> Result<(), TransactionError> {
+        if amount <= Balance::ZERO {
             return Err(TransactionError::InvalidAmount);
         }
         if self.locked {
@@ -166,15 +393,17 @@ impl AccountData {
         if self.available < amount {
             return Err(TransactionError::InsufficientFunds);
         }
-        self.available -= amount;
-        self.held += amount;
+        let available = checked_sub(self.available, amount)?;
+        let held = checked_add(self.held, amount)?;
+        self.available = available;
+        self.held = held;
         self.assert_invariants();
         Ok(())
     }
 
     /// Moves funds from held to available (resolve).
-    fn release_funds(&mut self, amount: Decimal) -> Result<(), TransactionError> {
-        if amount <= Decimal::ZERO {
+    fn release_funds(&mut self, amount: Balance) -> Result<(), TransactionError> {
+        if amount <= Balance::ZERO {
             return Err(TransactionError::InvalidAmount);
         }
         if self.locked {
@@ -183,15 +412,21 @@ impl AccountData {
         if self.held < amount {
             return Err(TransactionError::InsufficientFunds);
         }
-        self.held -= amount;
-        self.available += amount;
+        let held = checked_sub(self.held, amount)?;
+        let available = checked_add(self.available, amount)?;
+        self.held = held;
+        self.available = available;
         self.assert_invariants();
         Ok(())
     }
 
-    /// Removes held funds and locks the account (chargeback).
-    fn chargeback(&mut self, amount: Decimal) -> Result<(), TransactionError> {
-        if amount <= Decimal::ZERO {
+    /// Removes held funds and, per `policy`, locks the account (deposit chargeback).
+    fn chargeback(
+        &mut self,
+        amount: Balance,
+        policy: ChargebackPolicy,
+    ) -> Result<(), TransactionError> {
+        if amount <= Balance::ZERO {
             return Err(TransactionError::InvalidAmount);
         }
         if self.locked {
@@ -200,11 +435,55 @@ impl AccountData {
         if self.held < amount {
             return Err(TransactionError::InsufficientFunds);
         }
-        self.held -= amount;
-        self.locked = true;
+        self.held = checked_sub(self.held, amount)?;
+        self.locked = policy == ChargebackPolicy::LockAccount;
         self.assert_invariants();
         Ok(())
     }
+
+    /// Holds a disputed withdrawal's amount without touching available funds.
+    fn hold_withdrawal(&mut self, amount: Balance) -> Result<(), TransactionError> {
+        if amount <= Balance::ZERO {
+            return Err(TransactionError::InvalidAmount);
+        }
+        if self.locked {
+            return Err(TransactionError::AccountLocked);
+        }
+        let held = checked_add(self.held, amount)?;
+        // Keep `available + held` representable so totals can always be reported.
+        checked_add(self.available, held)?;
+        self.held = held;
+        self.assert_invariants();
+        Ok(())
+    }
+
+    /// Drops the hold on a disputed withdrawal, letting the withdrawal stand.
+    fn release_withdrawal(&mut self, amount: Balance) -> Result<(), TransactionError> {
+        if amount <= Balance::ZERO {
+            return Err(TransactionError::InvalidAmount);
+        }
+        if self.locked {
+            return Err(TransactionError::AccountLocked);
+        }
+        if self.held < amount {
+            return Err(TransactionError::InsufficientFunds);
+        }
+        self.held = checked_sub(self.held, amount)?;
+        self.assert_invariants();
+        Ok(())
+    }
+
+    /// Reverses a disputed withdrawal, returning held funds to available and,
+    /// per `policy`, locking the account.
+    fn reverse_withdrawal(
+        &mut self,
+        amount: Balance,
+        policy: ChargebackPolicy,
+    ) -> Result<(), TransactionError> {
+        self.release_funds(amount)?;
+        self.locked = policy == ChargebackPolicy::LockAccount;
+        Ok(())
+    }
 }
 
 /// Ledger account.
@@ -214,7 +493,8 @@ pub struct Account {
 }
 
 impl Account {
-    const DECIMAL_PRECISION: u32 = 4;
+    /// Number of decimal places balances are reported with.
+    pub const DECIMAL_PRECISION: u32 = 4;
 
     pub fn new(client_id: ClientId) -> Self {
         Self {
@@ -223,134 +503,183 @@ impl Account {
     }
 
     pub fn available(&self) -> Decimal {
-        self.inner.lock().available
+        to_decimal(self.inner.lock().available)
     }
 
     pub fn held(&self) -> Decimal {
-        self.inner.lock().held
+        to_decimal(self.inner.lock().held)
     }
 
     /// Returns `available + held`.
     pub fn total(&self) -> Decimal {
         let data = self.inner.lock();
-        data.available + data.held
+        to_decimal(data.available) + to_decimal(data.held)
     }
 
     pub fn locked(&self) -> bool {
         self.inner.lock().locked
     }
 
+    /// Returns the number of transactions applied to the account.
+    ///
+    /// Starts at zero and increases by one with every successful transaction,
+    /// so two reads returning the same version saw the same state.
+    pub fn version(&self) -> u64 {
+        self.inner.lock().version
+    }
+
     /// Creates a point-in-time snapshot of the account state.
     ///
     /// The returned snapshot is an owned value that doesn't hold any locks,
     /// making it safe to use across [`Engine::process()`](crate::Engine::process) calls.
     pub fn snapshot(&self) -> AccountSnapshot {
         let data = self.inner.lock();
+        let available = to_decimal(data.available);
+        let held = to_decimal(data.held);
         AccountSnapshot {
             client_id: data.client_id,
-            available: data.available.round_dp(Self::DECIMAL_PRECISION),
-            held: data.held.round_dp(Self::DECIMAL_PRECISION),
-            total: (data.available + data.held).round_dp(Self::DECIMAL_PRECISION),
+            available: available.round_dp(Self::DECIMAL_PRECISION),
+            held: held.round_dp(Self::DECIMAL_PRECISION),
+            total: (available + held).round_dp(Self::DECIMAL_PRECISION),
             locked: data.locked,
+            version: data.version,
         }
     }
 
+    /// Returns an accepted deposit or withdrawal of this account by ID.
+    pub fn transaction(&self, transaction_id: TransactionId) -> Option<StoredTransaction> {
+        self.inner.lock().stored(transaction_id)
+    }
+
+    /// Returns a page of the account's accepted deposits and withdrawals, in
+    /// the order they were applied.
+    pub fn history(&self, query: &HistoryQuery) -> HistoryPage {
+        let data = self.inner.lock();
+        let start = usize::try_from(query.cursor).unwrap_or(usize::MAX);
+        let mut transactions = Vec::new();
+        let mut next_cursor = None;
+        for (position, id) in data.history.iter().enumerate().skip(start) {
+            if transactions.len() == query.limit.max(1) {
+                next_cursor = Some(position as u64);
+                break;
+            }
+            let stored = data.stored(*id).expect("history entries have records");
+            if query.matches(&stored) {
+                transactions.push(stored);
+            }
+        }
+        HistoryPage {
+            transactions,
+            next_cursor,
+        }
+    }
+
+    /// Applies a transaction using the default [`EngineConfig`] policies.
     pub fn add_transaction(
         &mut self,
         transaction: TransactionType,
+    ) -> Result<(), TransactionError> {
+        let _span = tracing::debug_span!(
+            "add_transaction",
+            client = transaction.client_id().0,
+            tx = transaction.id().0,
+            "type" = transaction.kind(),
+        )
+        .entered();
+        self.apply(transaction, &EngineConfig::default(), None)
+    }
+
+    /// Applies a transaction through the account's internal lock.
+    ///
+    /// Shared-reference counterpart of [`add_transaction`](Self::add_transaction),
+    /// used by the engine so that the account mutex is the only lock taken.
+    /// Dispute and chargeback behavior follows the policies in `config`.
+    ///
+    /// With an `expected_version`, the transaction is rejected with
+    /// [`TransactionError::VersionConflict`] unless the account is at exactly
+    /// that version when the lock is taken.
+    pub(crate) fn apply(
+        &self,
+        transaction: TransactionType,
+        config: &EngineConfig,
+        expected_version: Option<u64>,
     ) -> Result<(), TransactionError> {
         let mut data = self.inner.lock();
-        if transaction.client_id() != data.client_id {
-            return Err(TransactionError::ClientMismatch);
+        if expected_version.is_some_and(|version| version != data.version) {
+            return Err(TransactionError::VersionConflict);
         }
+        data.apply(transaction, config)
+    }
 
-        match transaction {
-            TransactionType::Deposit {
-                transaction_id,
-                amount,
-                ..
-            } => {
-                // Process deposit
-                data.deposit(amount)?;
-
-                // Track deposit for future disputes
-                data.deposits.insert(
-                    transaction_id,
-                    DepositRecord {
-                        amount,
-                        status: TransactionStatus::Applied,
-                    },
-                );
-            }
-            TransactionType::Withdrawal { amount, .. } => {
-                // Process withdrawal (withdrawals cannot be disputed)
-                data.withdraw(amount)?;
-            }
-            TransactionType::Dispute { transaction_id, .. } => {
-                // Look up the referenced deposit
-                let deposit = data
-                    .deposits
-                    .get(&transaction_id)
-                    .ok_or(TransactionError::TransactionNotFound)?;
-
-                // Only Applied deposits can be disputed
-                if deposit.status != TransactionStatus::Applied {
-                    return Err(TransactionError::AlreadyDisputed);
-                }
-
-                let amount = deposit.amount;
-
-                // Move funds from available to held
-                data.hold_funds(amount)?;
-
-                // Update deposit status to Inflight
-                data.deposits.get_mut(&transaction_id).unwrap().status =
-                    TransactionStatus::Inflight;
-            }
-            TransactionType::Resolve { transaction_id, .. } => {
-                // Look up the referenced deposit
-                let deposit = data
-                    .deposits
-                    .get(&transaction_id)
-                    .ok_or(TransactionError::TransactionNotFound)?;
-
-                // Only Inflight deposits can be resolved
-                if deposit.status != TransactionStatus::Inflight {
-                    return Err(TransactionError::NotDisputed);
-                }
-
-                let amount = deposit.amount;
+    /// Locks the account and returns a private copy of it to apply
+    /// transactions to; see [`StagedAccount`].
+    pub(crate) fn stage(&self) -> StagedAccount<'_> {
+        let live = self.inner.lock();
+        StagedAccount {
+            copy: live.clone(),
+            live: Some(live),
+        }
+    }
+}
 
-                // Move funds from held back to available
-                data.release_funds(amount)?;
+/// A copy of an account that transactions are applied to tentatively.
+///
+/// The live account stays locked while staged, so the copy cannot go stale;
+/// [`commit`](Self::commit) writes the copy back and dropping it discards the
+/// changes. A client without an account is staged from an empty account and
+/// must be [`attach`](Self::attach)ed to one before committing.
+#[derive(Debug)]
+pub(crate) struct StagedAccount<'a> {
+    live: Option<MutexGuard<'a, AccountData>>,
+    copy: AccountData,
+}
 
-                // Update deposit status to Resolved
-                data.deposits.get_mut(&transaction_id).unwrap().status =
-                    TransactionStatus::Resolved;
-            }
-            TransactionType::Chargeback { transaction_id, .. } => {
-                // Look up the referenced deposit
-                let deposit = data
-                    .deposits
-                    .get(&transaction_id)
-                    .ok_or(TransactionError::TransactionNotFound)?;
-
-                // Only Inflight deposits can be charged back
-                if deposit.status != TransactionStatus::Inflight {
-                    return Err(TransactionError::NotDisputed);
-                }
+impl<'a> StagedAccount<'a> {
+    /// Stages a client that has no account yet.
+    pub(crate) fn new(client_id: ClientId) -> Self {
+        Self {
+            live: None,
+            copy: AccountData::new(client_id),
+        }
+    }
 
-                let amount = deposit.amount;
+    pub(crate) fn client_id(&self) -> ClientId {
+        self.copy.client_id
+    }
 
-                // Remove funds from held and lock account
-                data.chargeback(amount)?;
+    /// Applies a transaction to the copy.
+    pub(crate) fn apply(
+        &mut self,
+        transaction: TransactionType,
+        config: &EngineConfig,
+    ) -> Result<(), TransactionError> {
+        self.copy.apply(transaction, config)
+    }
 
-                // Update deposit status to Voided
-                data.deposits.get_mut(&transaction_id).unwrap().status = TransactionStatus::Voided;
+    /// Locks `account` as the live account of a client staged with
+    /// [`new`](Self::new).
+    ///
+    /// Returns `false` if something was applied to the account since staging
+    /// began, in which case the staged changes are based on a stale state.
+    pub(crate) fn attach(&mut self, account: &'a Account) -> bool {
+        if self.live.is_none() {
+            let live = account.inner.lock();
+            if !live.is_pristine() {
+                return false;
             }
+            self.live = Some(live);
         }
+        true
+    }
 
-        Ok(())
+    /// Writes the staged changes to the live account.
+    ///
+    /// # Panics
+    ///
+    /// If the client was staged without an account and never attached.
+    pub(crate) fn commit(self) {
+        let mut live = self.live.expect("staged account is attached");
+        *live = self.copy;
     }
 }
 
@@ -359,88 +688,160 @@ mod tests {
     use super::*;
     use rust_decimal_macros::dec;
 
+    /// Converts a test amount into the active balance representation.
+    fn bal(amount: Decimal) -> Balance {
+        to_balance(amount).unwrap()
+    }
+
     // === AccountData Internal Tests ===
     // These test the private AccountData methods directly.
 
     #[test]
     fn account_data_hold_funds() {
         let mut data = AccountData::new(ClientId(1));
-        data.deposit(dec!(100.00)).unwrap();
-        data.hold_funds(dec!(30.00)).unwrap();
-        assert_eq!(data.available, dec!(70.00));
-        assert_eq!(data.held, dec!(30.00));
+        data.deposit(bal(dec!(100.00))).unwrap();
+        data.hold_funds(bal(dec!(30.00))).unwrap();
+        assert_eq!(data.available, bal(dec!(70.00)));
+        assert_eq!(data.held, bal(dec!(30.00)));
     }
 
     #[test]
     fn account_data_release_funds() {
         let mut data = AccountData::new(ClientId(1));
-        data.deposit(dec!(100.00)).unwrap();
-        data.hold_funds(dec!(30.00)).unwrap();
-        data.release_funds(dec!(30.00)).unwrap();
-        assert_eq!(data.available, dec!(100.00));
-        assert_eq!(data.held, Decimal::ZERO);
+        data.deposit(bal(dec!(100.00))).unwrap();
+        data.hold_funds(bal(dec!(30.00))).unwrap();
+        data.release_funds(bal(dec!(30.00))).unwrap();
+        assert_eq!(data.available, bal(dec!(100.00)));
+        assert_eq!(data.held, Balance::ZERO);
     }
 
     #[test]
     fn account_data_chargeback_locks_account() {
         let mut data = AccountData::new(ClientId(1));
-        data.deposit(dec!(100.00)).unwrap();
-        data.hold_funds(dec!(50.00)).unwrap();
-        data.chargeback(dec!(50.00)).unwrap();
+        data.deposit(bal(dec!(100.00))).unwrap();
+        data.hold_funds(bal(dec!(50.00))).unwrap();
+        data.chargeback(bal(dec!(50.00)), ChargebackPolicy::LockAccount)
+            .unwrap();
         assert!(data.locked);
-        assert_eq!(data.available, dec!(50.00));
-        assert_eq!(data.held, Decimal::ZERO);
+        assert_eq!(data.available, bal(dec!(50.00)));
+        assert_eq!(data.held, Balance::ZERO);
+    }
+
+    #[test]
+    fn version_counts_successful_transactions() {
+        let config = EngineConfig::default();
+        let account = Account::new(ClientId(1));
+        let deposit = |id| TransactionType::Deposit {
+            client_id: ClientId(1),
+            transaction_id: TransactionId(id),
+            amount: dec!(5),
+        };
+        let withdrawal = TransactionType::Withdrawal {
+            client_id: ClientId(1),
+            transaction_id: TransactionId(3),
+            amount: dec!(100),
+        };
+        account.apply(deposit(1), &config, None).unwrap();
+        assert_eq!(
+            account.apply(withdrawal, &config, None),
+            Err(TransactionError::InsufficientFunds)
+        );
+        assert_eq!(
+            account.apply(deposit(2), &config, Some(0)),
+            Err(TransactionError::VersionConflict)
+        );
+        account.apply(deposit(2), &config, Some(1)).unwrap();
+        assert_eq!(account.version(), 2);
+        assert_eq!(account.snapshot().version, 2);
     }
 
     #[test]
     fn locked_account_rejects_deposit() {
         let mut data = AccountData::new(ClientId(1));
-        data.deposit(dec!(100.00)).unwrap();
-        data.hold_funds(dec!(50.00)).unwrap();
-        data.chargeback(dec!(50.00)).unwrap();
+        data.deposit(bal(dec!(100.00))).unwrap();
+        data.hold_funds(bal(dec!(50.00))).unwrap();
+        data.chargeback(bal(dec!(50.00)), ChargebackPolicy::LockAccount)
+            .unwrap();
 
-        let result = data.deposit(dec!(10.00));
+        let result = data.deposit(bal(dec!(10.00)));
         assert_eq!(result, Err(TransactionError::AccountLocked));
     }
 
     #[test]
     fn locked_account_rejects_withdrawal() {
         let mut data = AccountData::new(ClientId(1));
-        data.deposit(dec!(100.00)).unwrap();
-        data.hold_funds(dec!(50.00)).unwrap();
-        data.chargeback(dec!(50.00)).unwrap();
+        data.deposit(bal(dec!(100.00))).unwrap();
+        data.hold_funds(bal(dec!(50.00))).unwrap();
+        data.chargeback(bal(dec!(50.00)), ChargebackPolicy::LockAccount)
+            .unwrap();
 
-        let result = data.withdraw(dec!(10.00));
+        let result = data.withdraw(bal(dec!(10.00)));
         assert_eq!(result, Err(TransactionError::AccountLocked));
     }
 
     #[test]
     fn hold_funds_insufficient_returns_error() {
         let mut data = AccountData::new(ClientId(1));
-        data.deposit(dec!(50.00)).unwrap();
-        let result = data.hold_funds(dec!(100.00));
+        data.deposit(bal(dec!(50.00))).unwrap();
+        let result = data.hold_funds(bal(dec!(100.00)));
         assert_eq!(result, Err(TransactionError::InsufficientFunds));
     }
 
     #[test]
     fn release_funds_insufficient_returns_error() {
         let mut data = AccountData::new(ClientId(1));
-        data.deposit(dec!(100.00)).unwrap();
-        data.hold_funds(dec!(30.00)).unwrap();
-        let result = data.release_funds(dec!(50.00));
+        data.deposit(bal(dec!(100.00))).unwrap();
+        data.hold_funds(bal(dec!(30.00))).unwrap();
+        let result = data.release_funds(bal(dec!(50.00)));
         assert_eq!(result, Err(TransactionError::InsufficientFunds));
     }
 
     #[test]
     fn chargeback_insufficient_returns_error() {
         let mut data = AccountData::new(ClientId(1));
-        data.deposit(dec!(100.00)).unwrap();
-        data.hold_funds(dec!(30.00)).unwrap();
-        let result = data.chargeback(dec!(50.00));
+        data.deposit(bal(dec!(100.00))).unwrap();
+        data.hold_funds(bal(dec!(30.00))).unwrap();
+        let result = data.chargeback(bal(dec!(50.00)), ChargebackPolicy::LockAccount);
         assert_eq!(result, Err(TransactionError::InsufficientFunds));
         assert!(!data.locked); // Should not be locked
     }
 
+    #[test]
+    fn deposit_overflow_leaves_account_unchanged() {
+        let mut data = AccountData::new(ClientId(1));
+        let large = bal(Decimal::from_i128_with_scale(1 << 92, 4));
+        while data.deposit(large).is_ok() {}
+
+        let before = (data.available, data.held);
+        assert_eq!(data.deposit(large), Err(TransactionError::Overflow));
+        assert_eq!((data.available, data.held), before);
+    }
+
+    #[test]
+    fn deposit_rejected_when_total_would_overflow() {
+        let mut data = AccountData::new(ClientId(1));
+        let large = bal(Decimal::from_i128_with_scale(1 << 92, 4));
+        while data.deposit(large).is_ok() {}
+        data.hold_funds(data.available).unwrap();
+
+        // Available is empty, but available + held would no longer fit.
+        assert_eq!(data.deposit(large), Err(TransactionError::Overflow));
+        assert_eq!(data.available, Balance::ZERO);
+    }
+
+    #[cfg(feature = "fixed-point")]
+    #[test]
+    fn fixed_point_rejects_excess_precision() {
+        let mut account = Account::new(ClientId(1));
+        let result = account.add_transaction(TransactionType::Deposit {
+            client_id: ClientId(1),
+            transaction_id: TransactionId(1),
+            amount: dec!(1.00001),
+        });
+        assert_eq!(result, Err(TransactionError::InvalidAmount));
+        assert_eq!(account.available(), Decimal::ZERO);
+    }
+
     // === Serialization Tests ===
     // These tests verify AccountSnapshot serialization behavior.
 
@@ -455,6 +856,7 @@ mod tests {
             held: dec!(0.000001),        // Should round to 0.0000
             total: dec!(123.456790),     // Will be recalculated during serialization
             locked: false,
+            version: 0,
         };
 
         let json = serde_json::to_string(&snapshot).unwrap();
@@ -486,6 +888,7 @@ mod tests {
             held: dec!(50.5678),
             total: dec!(150.6912),
             locked: false,
+            version: 0,
         };
 
         let json = serde_json::to_string(&snapshot).unwrap();
@@ -508,6 +911,7 @@ mod tests {
             held: dec!(500),
             total: dec!(1500),
             locked: false,
+            version: 0,
         };
 
         let json = serde_json::to_string(&snapshot).unwrap();
@@ -532,6 +936,7 @@ mod tests {
             held: dec!(0.00005),
             total: Decimal::ZERO,
             locked: false,
+            version: 0,
         };
 
         let json = serde_json::to_string(&snapshot).unwrap();
@@ -0,0 +1,126 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Dense, lazily initialized account storage.
+//!
+//! [`ClientId`] is a `u16`, so the full key space is only 65,536 entries. Instead
+//! of hashing into a sharded map, accounts live in a fixed two-level table indexed
+//! directly by client ID. Pages of [`PAGE_SIZE`] slots are allocated on first use,
+//! so an empty table costs a few kilobytes rather than the full key space.
+//!
+//! Slots are [`OnceLock`]s: once an account is created it is never moved or
+//! removed, so lookups return plain shared references and the only lock taken
+//! on the hot path is the [`Account`]'s own mutex.
+
+use crate::account::Account;
+use crate::base::ClientId;
+use std::sync::OnceLock;
+
+/// Number of account slots per page.
+const PAGE_SIZE: usize = 256;
+
+/// Number of pages needed to cover every possible [`ClientId`].
+const PAGE_COUNT: usize = (u16::MAX as usize + 1) / PAGE_SIZE;
+
+type Page = Box<[OnceLock<Account>]>;
+
+/// Fixed-size account table indexed by client ID.
+#[derive(Debug)]
+pub(crate) struct AccountTable {
+    pages: Box<[OnceLock<Page>]>,
+}
+
+impl AccountTable {
+    /// Creates an empty table. No pages are allocated until first insert.
+    pub(crate) fn new() -> Self {
+        Self {
+            pages: (0..PAGE_COUNT).map(|_| OnceLock::new()).collect(),
+        }
+    }
+
+    /// Splits a client ID into its page and slot indices.
+    fn index(client_id: ClientId) -> (usize, usize) {
+        let id = client_id.0 as usize;
+        (id / PAGE_SIZE, id % PAGE_SIZE)
+    }
+
+    /// Returns the account for `client_id`, if one has been created.
+    pub(crate) fn get(&self, client_id: ClientId) -> Option<&Account> {
+        let (page, slot) = Self::index(client_id);
+        self.pages[page].get()?[slot].get()
+    }
+
+    /// Returns the account for `client_id`, creating an empty one if needed.
+    pub(crate) fn get_or_create(&self, client_id: ClientId) -> &Account {
+        let (page, slot) = Self::index(client_id);
+        let page =
+            self.pages[page].get_or_init(|| (0..PAGE_SIZE).map(|_| OnceLock::new()).collect());
+        page[slot].get_or_init(|| Account::new(client_id))
+    }
+
+    /// Iterates over all created accounts in ascending client ID order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Account> {
+        self.pages
+            .iter()
+            .filter_map(OnceLock::get)
+            .flat_map(|page| page.iter().filter_map(OnceLock::get))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_table_has_no_accounts() {
+        let table = AccountTable::new();
+        assert!(table.get(ClientId(1)).is_none());
+        assert_eq!(table.iter().count(), 0);
+    }
+
+    #[test]
+    fn get_or_create_is_idempotent() {
+        let table = AccountTable::new();
+        let first = table.get_or_create(ClientId(7)) as *const Account;
+        let second = table.get_or_create(ClientId(7)) as *const Account;
+        assert_eq!(first, second);
+        assert_eq!(table.iter().count(), 1);
+    }
+
+    #[test]
+    fn covers_full_client_id_range() {
+        let table = AccountTable::new();
+        for id in [0, 255, 256, u16::MAX] {
+            table.get_or_create(ClientId(id));
+            assert_eq!(
+                table.get(ClientId(id)).unwrap().snapshot().client_id,
+                ClientId(id)
+            );
+        }
+        assert!(table.get(ClientId(1)).is_none());
+    }
+
+    #[test]
+    fn iterates_in_client_id_order() {
+        let table = AccountTable::new();
+        for id in [900, 3, 65_000, 256, 42] {
+            table.get_or_create(ClientId(id));
+        }
+        let ids: Vec<u16> = table.iter().map(|a| a.snapshot().client_id.0).collect();
+        assert_eq!(ids, vec![3, 42, 256, 900, 65_000]);
+    }
+}
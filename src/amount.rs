@@ -0,0 +1,306 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Monetary amount representations.
+//!
+//! Balances are stored as [`Decimal`] by default. With the `fixed-point` cargo
+//! feature enabled, accounts instead store balances as [`FixedAmount`], a scaled
+//! integer with exactly four decimal places. Transactions still carry `Decimal`
+//! amounts; they are converted exactly at ingestion, and amounts with more than
+//! four fractional digits are rejected rather than rounded.
+//!
+//! # Example
+//!
+//! ```
+//! use ledger_demo_rs::FixedAmount;
+//! use rust_decimal_macros::dec;
+//!
+//! let amount: FixedAmount = "12.5".parse().unwrap();
+//! assert_eq!(amount.raw(), 125_000);
+//! assert_eq!(amount.to_decimal(), dec!(12.5000));
+//! assert!("0.00001".parse::<FixedAmount>().is_err());
+//! ```
+
+use crate::error::{AmountError, TransactionError};
+use rust_decimal::Decimal;
+use std::fmt;
+use std::str::FromStr;
+
+/// Fixed-point amount with four decimal places, stored as a scaled `i128`.
+///
+/// The raw value is the amount multiplied by `10^4`, so `1.5` is stored as
+/// `15000`. The range is limited to what a [`Decimal`] mantissa can hold
+/// (±(2^96 - 1) raw units), which keeps conversion back to `Decimal` lossless.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FixedAmount(i128);
+
+impl FixedAmount {
+    /// Number of fractional decimal digits.
+    pub const SCALE: u32 = 4;
+
+    /// Raw units per whole unit (`10^SCALE`).
+    const FACTOR: i128 = 10_i128.pow(Self::SCALE);
+
+    /// Zero amount.
+    pub const ZERO: Self = Self(0);
+
+    /// Largest representable amount.
+    pub const MAX: Self = Self((1 << 96) - 1);
+
+    /// Smallest representable amount.
+    pub const MIN: Self = Self(-Self::MAX.0);
+
+    /// Creates an amount from raw scaled units, or `None` if out of range.
+    pub const fn from_raw(raw: i128) -> Option<Self> {
+        if raw > Self::MAX.0 || raw < Self::MIN.0 {
+            None
+        } else {
+            Some(Self(raw))
+        }
+    }
+
+    /// Returns the raw scaled value (amount × 10^4).
+    pub const fn raw(self) -> i128 {
+        self.0
+    }
+
+    /// Adds two amounts, returning `None` on overflow.
+    pub const fn checked_add(self, rhs: Self) -> Option<Self> {
+        match self.0.checked_add(rhs.0) {
+            Some(raw) => Self::from_raw(raw),
+            None => None,
+        }
+    }
+
+    /// Subtracts two amounts, returning `None` on overflow.
+    pub const fn checked_sub(self, rhs: Self) -> Option<Self> {
+        match self.0.checked_sub(rhs.0) {
+            Some(raw) => Self::from_raw(raw),
+            None => None,
+        }
+    }
+
+    /// Converts a `Decimal` exactly.
+    ///
+    /// # Errors
+    ///
+    /// - [`AmountError::TooPrecise`] - More than four significant fractional digits.
+    /// - [`AmountError::OutOfRange`] - Value does not fit the fixed-point range.
+    pub fn from_decimal(value: Decimal) -> Result<Self, AmountError> {
+        let value = value.normalize();
+        let scale = value.scale();
+        if scale > Self::SCALE {
+            return Err(AmountError::TooPrecise);
+        }
+        value
+            .mantissa()
+            .checked_mul(10_i128.pow(Self::SCALE - scale))
+            .and_then(Self::from_raw)
+            .ok_or(AmountError::OutOfRange)
+    }
+
+    /// Converts to a `Decimal` with a scale of four.
+    pub fn to_decimal(self) -> Decimal {
+        Decimal::from_i128_with_scale(self.0, Self::SCALE)
+    }
+}
+
+impl From<FixedAmount> for Decimal {
+    fn from(value: FixedAmount) -> Self {
+        value.to_decimal()
+    }
+}
+
+impl TryFrom<Decimal> for FixedAmount {
+    type Error = AmountError;
+
+    fn try_from(value: Decimal) -> Result<Self, Self::Error> {
+        Self::from_decimal(value)
+    }
+}
+
+impl FromStr for FixedAmount {
+    type Err = AmountError;
+
+    /// Parses a plain decimal string (e.g. `-12.3400`) without rounding.
+    ///
+    /// Trailing fractional zeros beyond the fourth digit are accepted; any other
+    /// fifth-or-later fractional digit is rejected.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, digits) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+        let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+
+        let all_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if (int_part.is_empty() && frac_part.is_empty())
+            || !all_digits(int_part)
+            || !all_digits(frac_part)
+        {
+            return Err(AmountError::Invalid);
+        }
+
+        let frac_part = frac_part.trim_end_matches('0');
+        if frac_part.len() > Self::SCALE as usize {
+            return Err(AmountError::TooPrecise);
+        }
+
+        let mut raw: i128 = 0;
+        for b in int_part.bytes() {
+            raw = raw
+                .checked_mul(10)
+                .and_then(|r| r.checked_add(i128::from(b - b'0')))
+                .ok_or(AmountError::OutOfRange)?;
+        }
+        raw = raw
+            .checked_mul(Self::FACTOR)
+            .ok_or(AmountError::OutOfRange)?;
+
+        let mut frac: i128 = 0;
+        for b in frac_part.bytes() {
+            frac = frac * 10 + i128::from(b - b'0');
+        }
+        frac *= 10_i128.pow(Self::SCALE - frac_part.len() as u32);
+
+        let raw = raw.checked_add(frac).ok_or(AmountError::OutOfRange)?;
+        Self::from_raw(if negative { -raw } else { raw }).ok_or(AmountError::OutOfRange)
+    }
+}
+
+impl fmt::Display for FixedAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_decimal())
+    }
+}
+
+/// Internal balance representation selected by the `fixed-point` feature.
+#[cfg(not(feature = "fixed-point"))]
+pub(crate) type Balance = Decimal;
+
+/// Internal balance representation selected by the `fixed-point` feature.
+#[cfg(feature = "fixed-point")]
+pub(crate) type Balance = FixedAmount;
+
+/// Converts a transaction amount into the internal balance representation.
+#[cfg(not(feature = "fixed-point"))]
+#[inline]
+pub(crate) fn to_balance(amount: Decimal) -> Result<Balance, TransactionError> {
+    Ok(amount)
+}
+
+/// Converts a transaction amount into the internal balance representation.
+///
+/// Amounts with excess precision are rejected as invalid; amounts beyond the
+/// fixed-point range are rejected as an overflow.
+#[cfg(feature = "fixed-point")]
+#[inline]
+pub(crate) fn to_balance(amount: Decimal) -> Result<Balance, TransactionError> {
+    FixedAmount::from_decimal(amount).map_err(|err| match err {
+        AmountError::OutOfRange => TransactionError::Overflow,
+        AmountError::Invalid | AmountError::TooPrecise => TransactionError::InvalidAmount,
+    })
+}
+
+/// Converts an internal balance back into a `Decimal`.
+#[cfg(not(feature = "fixed-point"))]
+#[inline]
+pub(crate) fn to_decimal(balance: Balance) -> Decimal {
+    balance
+}
+
+/// Converts an internal balance back into a `Decimal`.
+#[cfg(feature = "fixed-point")]
+#[inline]
+pub(crate) fn to_decimal(balance: Balance) -> Decimal {
+    balance.to_decimal()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn parse_exact_values() {
+        assert_eq!("0".parse::<FixedAmount>().unwrap(), FixedAmount::ZERO);
+        assert_eq!("1".parse::<FixedAmount>().unwrap().raw(), 10_000);
+        assert_eq!("1.5".parse::<FixedAmount>().unwrap().raw(), 15_000);
+        assert_eq!(".25".parse::<FixedAmount>().unwrap().raw(), 2_500);
+        assert_eq!("-0.0001".parse::<FixedAmount>().unwrap().raw(), -1);
+        assert_eq!("2.50000".parse::<FixedAmount>().unwrap().raw(), 25_000);
+    }
+
+    #[test]
+    fn parse_rejects_excess_precision() {
+        assert_eq!(
+            "0.00001".parse::<FixedAmount>(),
+            Err(AmountError::TooPrecise)
+        );
+        assert_eq!(
+            "1.23456".parse::<FixedAmount>(),
+            Err(AmountError::TooPrecise)
+        );
+    }
+
+    #[test]
+    fn parse_rejects_malformed_input() {
+        for input in ["", "-", ".", "abc", "1.2.3", "1e5", " 1", "--1"] {
+            assert_eq!(
+                input.parse::<FixedAmount>(),
+                Err(AmountError::Invalid),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_rejects_out_of_range() {
+        let too_big = "9".repeat(40);
+        assert_eq!(too_big.parse::<FixedAmount>(), Err(AmountError::OutOfRange));
+    }
+
+    #[test]
+    fn decimal_round_trip() {
+        let amount = FixedAmount::from_decimal(dec!(123.4567)).unwrap();
+        assert_eq!(amount.to_decimal(), dec!(123.4567));
+        assert_eq!(
+            FixedAmount::from_decimal(dec!(1.00000)).unwrap().raw(),
+            10_000
+        );
+        assert_eq!(
+            FixedAmount::from_decimal(dec!(1.00001)),
+            Err(AmountError::TooPrecise)
+        );
+        assert_eq!(
+            FixedAmount::MAX.to_decimal().mantissa(),
+            FixedAmount::MAX.raw()
+        );
+    }
+
+    #[test]
+    fn checked_arithmetic_detects_overflow() {
+        let one = FixedAmount::from_raw(1).unwrap();
+        assert_eq!(FixedAmount::MAX.checked_add(one), None);
+        assert_eq!(FixedAmount::MIN.checked_sub(one), None);
+        assert_eq!(
+            FixedAmount::MAX.checked_sub(one).unwrap().raw(),
+            FixedAmount::MAX.raw() - 1
+        );
+    }
+}
@@ -0,0 +1,55 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Batch transaction processing.
+//!
+//! [`Engine::process_batch`](crate::Engine::process_batch) applies a sequence
+//! of transactions in order, so each client's transactions keep their relative
+//! order. In [`BatchMode::Independent`] every transaction stands on its own, as
+//! if passed to [`Engine::process`](crate::Engine::process) one by one. In
+//! [`BatchMode::Atomic`] the batch is applied all-or-nothing: if any
+//! transaction is rejected, none of them take effect.
+
+use crate::error::TransactionError;
+
+/// How a batch treats rejected transactions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BatchMode {
+    /// Apply every transaction that succeeds and report the rest (default).
+    #[default]
+    Independent,
+    /// Apply the batch only if every transaction succeeds.
+    Atomic,
+}
+
+/// Outcome of a batch, one result per transaction in submission order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchReport {
+    /// Result of each transaction. In a rolled-back atomic batch, `Ok` marks
+    /// transactions that would have been applied.
+    pub results: Vec<Result<(), TransactionError>>,
+    /// Whether the successful transactions took effect. Always `true` in
+    /// [`BatchMode::Independent`]; `false` for a rejected atomic batch.
+    pub committed: bool,
+}
+
+impl BatchReport {
+    /// Number of rejected transactions.
+    pub fn rejected(&self) -> usize {
+        self.results.iter().filter(|result| result.is_err()).count()
+    }
+}
@@ -0,0 +1,211 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Row failure classification and process exit codes.
+//!
+//! In `--strict` mode and in the `validate` subcommand, a failing row is
+//! reported with its line number and a [`FailureClass`]. Each class maps to a
+//! distinct process exit code so feed validation scripts can branch on it.
+
+use ledger_demo_rs::TransactionError;
+use std::{fmt, io};
+
+/// Exit code for I/O, configuration and output errors.
+pub const EXIT_IO: i32 = 1;
+
+/// Help text listing every exit code.
+pub const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0  success
+  1  I/O, configuration or output error
+  2  invalid command-line usage
+  3  schema error (missing header column, too many fields)
+  4  invalid row (bad field type, unknown type, missing or invalid amount)
+  5  duplicate transaction ID
+  6  reference to an unknown transaction
+  7  client mismatch on a dispute, resolve or chargeback
+  8  transaction rejected by the engine (--strict only)";
+
+/// Category of a failing row, each with its own exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureClass {
+    /// Missing header columns, or a row with more fields than the header.
+    Schema,
+    /// A field has the wrong type, or the transaction type or amount is invalid.
+    InvalidRow,
+    /// A deposit or withdrawal reuses a transaction ID.
+    DuplicateId,
+    /// A dispute, resolve or chargeback references an unknown transaction.
+    UnknownReference,
+    /// A dispute, resolve or chargeback names a different client than the
+    /// transaction it references.
+    ClientMismatch,
+    /// The engine rejected the transaction for another reason.
+    Rejected,
+}
+
+impl FailureClass {
+    /// Process exit code for this class.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Self::Schema => 3,
+            Self::InvalidRow => 4,
+            Self::DuplicateId => 5,
+            Self::UnknownReference => 6,
+            Self::ClientMismatch => 7,
+            Self::Rejected => 8,
+        }
+    }
+}
+
+impl From<&TransactionError> for FailureClass {
+    fn from(err: &TransactionError) -> Self {
+        match err {
+            TransactionError::MissingAmount
+            | TransactionError::InvalidAmount
+            | TransactionError::ExcessPrecision => Self::InvalidRow,
+            TransactionError::DuplicateTransaction => Self::DuplicateId,
+            TransactionError::TransactionNotFound => Self::UnknownReference,
+            TransactionError::ClientMismatch => Self::ClientMismatch,
+            TransactionError::InsufficientFunds
+            | TransactionError::AlreadyDisputed
+            | TransactionError::NotDisputed
+            | TransactionError::NotDisputable
+            | TransactionError::AccountLocked
+            | TransactionError::Overflow
+            | TransactionError::VersionConflict => Self::Rejected,
+        }
+    }
+}
+
+/// A row that failed parsing, validation or (in strict mode) processing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowFailure {
+    /// 1-based line number in the input.
+    pub line: u64,
+    /// Failure category.
+    pub class: FailureClass,
+    /// Machine-readable code, e.g. `MALFORMED_ROW` or `INSUFFICIENT_FUNDS`.
+    pub code: &'static str,
+    /// Human-readable explanation.
+    pub reason: String,
+}
+
+impl RowFailure {
+    /// Creates a failure for a row the engine (or validator) rejected.
+    pub fn from_error(line: u64, err: &TransactionError) -> Self {
+        Self {
+            line,
+            class: err.into(),
+            code: err.code(),
+            reason: err.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for RowFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {} [{}]", self.line, self.reason, self.code)
+    }
+}
+
+/// Error that stops processing an input.
+#[derive(Debug)]
+pub enum ProcessError {
+    /// The reader failed or the CSV could not be read.
+    Csv(csv::Error),
+    /// Reading a JSON Lines input failed.
+    Io(io::Error),
+    /// A row failed in strict mode.
+    Row(RowFailure),
+}
+
+impl ProcessError {
+    /// Process exit code for this error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Csv(_) | Self::Io(_) => EXIT_IO,
+            Self::Row(failure) => failure.class.exit_code(),
+        }
+    }
+}
+
+impl From<csv::Error> for ProcessError {
+    fn from(err: csv::Error) -> Self {
+        Self::Csv(err)
+    }
+}
+
+impl From<io::Error> for ProcessError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<RowFailure> for ProcessError {
+    fn from(failure: RowFailure) -> Self {
+        Self::Row(failure)
+    }
+}
+
+impl fmt::Display for ProcessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Csv(err) => err.fmt(f),
+            Self::Io(err) => err.fmt(f),
+            Self::Row(failure) => failure.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ProcessError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_codes_are_distinct() {
+        let classes = [
+            FailureClass::Schema,
+            FailureClass::InvalidRow,
+            FailureClass::DuplicateId,
+            FailureClass::UnknownReference,
+            FailureClass::ClientMismatch,
+            FailureClass::Rejected,
+        ];
+        let mut codes: Vec<i32> = classes.iter().map(|c| c.exit_code()).collect();
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), classes.len());
+        assert!(!codes.contains(&EXIT_IO) && !codes.contains(&2));
+    }
+
+    #[test]
+    fn engine_errors_map_to_classes() {
+        let failure = RowFailure::from_error(7, &TransactionError::InsufficientFunds);
+        assert_eq!(failure.class, FailureClass::Rejected);
+        assert_eq!(
+            failure.to_string(),
+            "line 7: insufficient available funds [INSUFFICIENT_FUNDS]"
+        );
+        assert_eq!(
+            FailureClass::from(&TransactionError::DuplicateTransaction),
+            FailureClass::DuplicateId
+        );
+    }
+}
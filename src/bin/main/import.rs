@@ -0,0 +1,207 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The `import` subcommand: convert bank statements to transaction CSV.
+//!
+//! Each camt.053 or MT940 statement is parsed, its entries are matched to
+//! clients through a lookup table, and the resulting deposits and withdrawals
+//! are written to stdout as `type,client,tx,amount` rows ready to be processed.
+//! Entries whose references match no client are reported on stderr.
+
+use crate::failure::EXIT_IO;
+use ledger_demo_rs::{
+    ClientLookup, Direction, StatementEntry, StatementFormat, StatementImporter, TransactionType,
+};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Runs the `import` subcommand, returning the process exit code.
+///
+/// The format of each input is `format` if given, else guessed from its
+/// extension. Transaction IDs are numbered from `first_tx` across all inputs.
+pub fn run_import(
+    inputs: &[PathBuf],
+    format: Option<StatementFormat>,
+    clients: &Path,
+    first_tx: u32,
+) -> i32 {
+    let lookup = match File::open(clients)
+        .map_err(Into::into)
+        .and_then(ClientLookup::from_csv)
+    {
+        Ok(lookup) => lookup,
+        Err(e) => {
+            eprintln!("Error loading client table '{}': {}", clients.display(), e);
+            return EXIT_IO;
+        }
+    };
+
+    let mut importer = StatementImporter::new(lookup, first_tx);
+    let mut writer = csv::Writer::from_writer(io::stdout());
+    if let Err(e) = writer.write_record(HEADER) {
+        eprintln!("Error writing output: {}", e);
+        return EXIT_IO;
+    }
+    let (mut imported, mut unmatched) = (0, 0);
+
+    for input in inputs {
+        let Some(format) = format.or_else(|| StatementFormat::from_path(input)) else {
+            eprintln!(
+                "Error: cannot tell the statement format of '{}'; pass --format",
+                input.display()
+            );
+            return EXIT_IO;
+        };
+        let entries = match read_to_string(input)
+            .map_err(Into::into)
+            .and_then(|text| format.parse(&text))
+        {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("Error reading statement '{}': {}", input.display(), e);
+                return EXIT_IO;
+            }
+        };
+
+        let import = importer.import(entries);
+        for entry in &import.unmatched {
+            eprintln!("{}: {}", input.display(), describe_unmatched(entry));
+        }
+        imported += import.transactions.len();
+        unmatched += import.unmatched.len();
+        if let Err(e) = write_transactions(&mut writer, &import.transactions) {
+            eprintln!("Error writing output: {}", e);
+            return EXIT_IO;
+        }
+    }
+
+    if let Err(e) = writer.flush() {
+        eprintln!("Error writing output: {}", e);
+        return EXIT_IO;
+    }
+    eprintln!("{imported} transaction(s) imported, {unmatched} unmatched");
+    0
+}
+
+/// Reads a whole input, with `-` meaning stdin.
+fn read_to_string(path: &Path) -> io::Result<String> {
+    let mut text = String::new();
+    if path.as_os_str() == "-" {
+        io::stdin().lock().read_to_string(&mut text)?;
+    } else {
+        File::open(path)?.read_to_string(&mut text)?;
+    }
+    Ok(text)
+}
+
+/// Header of the transaction CSV written by `import`.
+const HEADER: [&str; 4] = ["type", "client", "tx", "amount"];
+
+/// Writes transactions as `type,client,tx,amount` rows (without header).
+pub fn write_transactions<W: Write>(
+    writer: &mut csv::Writer<W>,
+    transactions: &[TransactionType],
+) -> csv::Result<()> {
+    for tx in transactions {
+        let amount = match tx {
+            TransactionType::Deposit { amount, .. }
+            | TransactionType::Withdrawal { amount, .. } => amount.to_string(),
+            _ => String::new(),
+        };
+        writer.write_record([
+            tx.kind(),
+            &tx.client_id().0.to_string(),
+            &tx.id().0.to_string(),
+            &amount,
+        ])?;
+    }
+    Ok(())
+}
+
+/// Describes an unmatched entry as `line N: ...`.
+fn describe_unmatched(entry: &StatementEntry) -> String {
+    let direction = match entry.direction {
+        Direction::Credit => "credit",
+        Direction::Debit => "debit",
+    };
+    let currency = entry
+        .currency
+        .as_deref()
+        .map(|c| format!(" {c}"))
+        .unwrap_or_default();
+    let references = if entry.references.is_empty() {
+        "no reference".to_string()
+    } else {
+        format!("references {:?}", entry.references)
+    };
+    format!(
+        "line {}: no client matches {} of {}{} ({}) [UNMATCHED_REFERENCE]",
+        entry.line, direction, entry.amount, currency, references
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ledger_demo_rs::{ClientId, TransactionId};
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn transactions_round_trip_as_input_csv() {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record(HEADER).unwrap();
+        write_transactions(
+            &mut writer,
+            &[
+                TransactionType::Deposit {
+                    client_id: ClientId(1),
+                    transaction_id: TransactionId(7),
+                    amount: dec!(250.00),
+                },
+                TransactionType::Withdrawal {
+                    client_id: ClientId(2),
+                    transaction_id: TransactionId(8),
+                    amount: dec!(75.50),
+                },
+            ],
+        )
+        .unwrap();
+        let csv = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        assert_eq!(
+            csv,
+            "type,client,tx,amount\ndeposit,1,7,250.00\nwithdrawal,2,8,75.50\n"
+        );
+    }
+
+    #[test]
+    fn unmatched_entries_name_line_amount_and_references() {
+        let entry = StatementEntry {
+            line: 11,
+            direction: Direction::Credit,
+            amount: dec!(10.00),
+            currency: Some("EUR".to_string()),
+            booking_date: None,
+            references: vec!["Unknown sender".to_string()],
+        };
+        assert_eq!(
+            describe_unmatched(&entry),
+            "line 11: no client matches credit of 10.00 EUR (references [\"Unknown sender\"]) \
+             [UNMATCHED_REFERENCE]"
+        );
+    }
+}
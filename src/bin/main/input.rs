@@ -0,0 +1,498 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Input decoding and row parsing.
+//!
+//! Inputs are files or stdin, optionally gzip or zstd compressed, in CSV or
+//! JSON Lines format. Each CSV row is parsed into a [`TransactionType`]
+//! according to a [`CsvSchema`] and each JSON line from a
+//! [`TransactionRequest`](ledger_demo_rs::TransactionRequest); rows that cannot
+//! be are reported as a [`RowFailure`] carrying the line number and reason.
+
+use crate::failure::{FailureClass, ProcessError, RowFailure};
+use csv::StringRecord;
+use flate2::read::MultiGzDecoder;
+use ledger_demo_rs::{
+    ClientId, CsvSchema, Field, InputFormat, JsonLine, ResolvedSchema, TransactionId,
+    TransactionType,
+};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::Value;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+/// Gzip member header magic bytes.
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+/// Zstandard frame magic bytes.
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Opens an input path for reading, with `-` meaning stdin.
+///
+/// See [`decompress`] for compression handling.
+pub fn open_input(path: &Path) -> io::Result<Box<dyn Read>> {
+    if path.as_os_str() == "-" {
+        decompress(io::stdin().lock())
+    } else {
+        decompress(File::open(path)?)
+    }
+}
+
+/// Wraps a reader in a decoder chosen by its leading magic bytes.
+///
+/// Gzip (including multi-member files) and zstd streams are decompressed;
+/// anything else is passed through unchanged.
+pub fn decompress<R: Read + 'static>(mut reader: R) -> io::Result<Box<dyn Read>> {
+    // A pipe may return fewer bytes per read than the longest magic number,
+    // so read until it is complete or the input ends.
+    let mut head = [0; ZSTD_MAGIC.len()];
+    let mut len = 0;
+    while len < head.len() {
+        match reader.read(&mut head[len..]) {
+            Ok(0) => break,
+            Ok(read) => len += read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    let (is_gzip, is_zstd) = (
+        head[..len].starts_with(GZIP_MAGIC),
+        head[..len].starts_with(ZSTD_MAGIC),
+    );
+    let reader = BufReader::new(io::Cursor::new(head).take(len as u64).chain(reader));
+
+    if is_gzip {
+        Ok(Box::new(MultiGzDecoder::new(reader)))
+    } else if is_zstd {
+        Ok(Box::new(zstd::Decoder::with_buffer(reader)?))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/// Per-run input settings shared by processing and validation.
+#[derive(Debug, Clone, Default)]
+pub struct InputOptions {
+    /// Column layout of CSV inputs.
+    pub schema: CsvSchema,
+    /// Stop at the first failing row and reject unexpected columns.
+    pub strict: bool,
+    /// Format of every input; when unset it is chosen per input by extension.
+    pub format: Option<InputFormat>,
+}
+
+impl InputOptions {
+    /// Returns the format to read `path` with: the forced format, else the
+    /// one implied by its extension, else CSV (which includes stdin).
+    pub fn format_for(&self, path: &Path) -> InputFormat {
+        self.format
+            .or_else(|| InputFormat::from_path(path))
+            .unwrap_or_default()
+    }
+}
+
+/// Returns the 1-based line number of a record, or 0 if unknown.
+pub fn line_of(record: &StringRecord) -> u64 {
+    record.position().map_or(0, |p| p.line())
+}
+
+/// Matches the schema against an input's header row.
+///
+/// Required fields without a column are always an error. Header columns that
+/// are neither mapped nor ignored are an error only when `strict` is set.
+pub fn resolve_schema<R: Read>(
+    schema: &CsvSchema,
+    rdr: &mut csv::Reader<R>,
+    strict: bool,
+) -> Result<ResolvedSchema, ProcessError> {
+    let headers = if schema.has_headers {
+        Some(rdr.headers()?.clone())
+    } else {
+        None
+    };
+
+    let schema_failure = |code, reason| RowFailure {
+        line: 1,
+        class: FailureClass::Schema,
+        code,
+        reason,
+    };
+    let resolved = schema
+        .resolve(headers.as_ref())
+        .map_err(|e| schema_failure(e.code(), e.to_string()))?;
+
+    if strict && !resolved.unexpected_columns().is_empty() {
+        return Err(schema_failure(
+            "UNEXPECTED_COLUMN",
+            format!(
+                "header has unmapped column(s) not listed in the schema's ignore list: {}",
+                resolved.unexpected_columns().join(", ")
+            ),
+        )
+        .into());
+    }
+    Ok(resolved)
+}
+
+/// Parses one CSV row into a transaction.
+///
+/// `header_len` is the number of header columns, if the input has a header;
+/// rows with more fields than that are a schema error. Deposits and
+/// withdrawals without a valid amount are rejected. Amounts are normalized by
+/// the engine's `AmountPolicy` when processed.
+pub fn parse_row(
+    record: &StringRecord,
+    schema: &ResolvedSchema,
+    header_len: Option<usize>,
+) -> Result<TransactionType, RowFailure> {
+    let line = line_of(record);
+    let failure = |class, code, reason| RowFailure {
+        line,
+        class,
+        code,
+        reason,
+    };
+
+    if let Some(expected) = header_len.filter(|&expected| record.len() > expected) {
+        return Err(failure(
+            FailureClass::Schema,
+            "TOO_MANY_FIELDS",
+            format!(
+                "expected at most {} fields, found {}",
+                expected,
+                record.len()
+            ),
+        ));
+    }
+
+    let required = |field: Field| {
+        schema
+            .get(record, field)
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| {
+                failure(
+                    FailureClass::InvalidRow,
+                    "MALFORMED_ROW",
+                    format!("missing field '{}'", field.name()),
+                )
+            })
+    };
+    let invalid = |field: Field, value: &str| {
+        failure(
+            FailureClass::InvalidRow,
+            "MALFORMED_ROW",
+            format!("invalid {} '{}'", field.name(), value),
+        )
+    };
+
+    let tx_type = schema.transaction_type(required(Field::Type)?);
+    let client = required(Field::Client)?;
+    let client_id = ClientId(client.parse().map_err(|_| invalid(Field::Client, client))?);
+    let tx = required(Field::Tx)?;
+    let transaction_id = TransactionId(tx.parse().map_err(|_| invalid(Field::Tx, tx))?);
+    let amount = || {
+        schema
+            .get(record, Field::Amount)
+            .and_then(|value| value.parse::<Decimal>().ok())
+            .ok_or_else(|| {
+                failure(
+                    FailureClass::InvalidRow,
+                    "MISSING_AMOUNT",
+                    format!("{tx_type} without a valid amount"),
+                )
+            })
+    };
+
+    match tx_type.as_str() {
+        "deposit" => Ok(TransactionType::Deposit {
+            client_id,
+            transaction_id,
+            amount: amount()?,
+        }),
+        "withdrawal" => Ok(TransactionType::Withdrawal {
+            client_id,
+            transaction_id,
+            amount: amount()?,
+        }),
+        "dispute" => Ok(TransactionType::Dispute {
+            client_id,
+            transaction_id,
+        }),
+        "resolve" => Ok(TransactionType::Resolve {
+            client_id,
+            transaction_id,
+        }),
+        "chargeback" => Ok(TransactionType::Chargeback {
+            client_id,
+            transaction_id,
+        }),
+        other => Err(failure(
+            FailureClass::InvalidRow,
+            "UNKNOWN_TYPE",
+            format!("unknown transaction type '{other}'"),
+        )),
+    }
+}
+
+/// Converts one JSON Lines record into a transaction.
+///
+/// Invalid JSON is a schema error; well-formed JSON that is not a valid
+/// transaction is an invalid row, classified like the equivalent CSV problem.
+pub fn parse_json_line(json_line: JsonLine) -> Result<(u64, TransactionType), RowFailure> {
+    let line = json_line.line;
+    let err = match json_line.request {
+        Ok(request) => return Ok((line, request.into())),
+        Err(err) => err,
+    };
+
+    let (class, code) = match &json_line.json {
+        Some(json) => (FailureClass::InvalidRow, invalid_json_code(json)),
+        None => (FailureClass::Schema, "MALFORMED_ROW"),
+    };
+    Err(RowFailure {
+        line,
+        class,
+        code,
+        reason: err.to_string(),
+    })
+}
+
+/// Names what is wrong with well-formed JSON that is not a valid transaction,
+/// using the code of the equivalent CSV problem.
+fn invalid_json_code(json: &Value) -> &'static str {
+    match json.get("type").and_then(Value::as_str) {
+        Some("deposit" | "withdrawal") => match json.get("amount") {
+            Some(amount) if <Decimal as Deserialize>::deserialize(amount).is_ok() => {
+                "MALFORMED_ROW"
+            }
+            _ => "MISSING_AMOUNT",
+        },
+        Some("dispute" | "resolve" | "chargeback") | None => "MALFORMED_ROW",
+        Some(_) => "UNKNOWN_TYPE",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ledger_demo_rs::JsonLines;
+    use std::io::{Cursor, Write};
+
+    fn rows_with(schema: &CsvSchema, csv: &str) -> Vec<Result<TransactionType, RowFailure>> {
+        let mut rdr = schema.reader_builder().from_reader(csv.as_bytes());
+        let resolved = resolve_schema(schema, &mut rdr, false).unwrap();
+        let header_len = schema.has_headers.then(|| rdr.headers().unwrap().len());
+        rdr.records()
+            .map(|record| parse_row(&record.unwrap(), &resolved, header_len))
+            .collect()
+    }
+
+    fn rows(csv: &str) -> Vec<Result<TransactionType, RowFailure>> {
+        rows_with(&CsvSchema::default(), csv)
+    }
+
+    #[test]
+    fn detects_gzip_and_zstd_inputs() {
+        let csv = b"type,client,tx,amount\ndeposit,1,1,2.5\n";
+
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        gz.write_all(csv).unwrap();
+        let gz = gz.finish().unwrap();
+        let zst = zstd::encode_all(&csv[..], 0).unwrap();
+
+        for input in [csv.to_vec(), gz, zst] {
+            let mut decoded = String::new();
+            decompress(Cursor::new(input))
+                .unwrap()
+                .read_to_string(&mut decoded)
+                .unwrap();
+            assert_eq!(decoded.as_bytes(), csv);
+        }
+    }
+
+    /// A reader returning one byte per read, like a slow pipe.
+    struct Trickle(Cursor<Vec<u8>>);
+
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let end = buf.len().min(1);
+            self.0.read(&mut buf[..end])
+        }
+    }
+
+    #[test]
+    fn detects_compression_from_short_reads() {
+        let csv = b"type,client,tx,amount\ndeposit,1,1,2.5\n";
+        let zst = zstd::encode_all(&csv[..], 0).unwrap();
+
+        for input in [zst, csv.to_vec(), b"ty".to_vec(), Vec::new()] {
+            let expected = if input.starts_with(ZSTD_MAGIC) {
+                csv.to_vec()
+            } else {
+                input.clone()
+            };
+            let mut decoded = Vec::new();
+            decompress(Trickle(Cursor::new(input)))
+                .unwrap()
+                .read_to_end(&mut decoded)
+                .unwrap();
+            assert_eq!(decoded, expected);
+        }
+    }
+
+    #[test]
+    fn rows_report_line_and_reason() {
+        let parsed = rows(
+            "type,client,tx,amount\n\
+             deposit,1,1,1.0\n\
+             deposit,x,2,1.0\n\
+             refund,1,3,1.0\n\
+             withdrawal,1,4\n\
+             deposit,1,5,1.0,extra\n",
+        );
+
+        assert!(parsed[0].is_ok());
+        let failures: Vec<(u64, FailureClass, &str)> = parsed[1..]
+            .iter()
+            .map(|r| {
+                let f = r.as_ref().unwrap_err();
+                (f.line, f.class, f.code)
+            })
+            .collect();
+        assert_eq!(
+            failures,
+            vec![
+                (3, FailureClass::InvalidRow, "MALFORMED_ROW"),
+                (4, FailureClass::InvalidRow, "UNKNOWN_TYPE"),
+                (5, FailureClass::InvalidRow, "MISSING_AMOUNT"),
+                (6, FailureClass::Schema, "TOO_MANY_FIELDS"),
+            ]
+        );
+    }
+
+    #[test]
+    fn header_must_name_required_columns() {
+        let schema = CsvSchema::default();
+        let mut rdr = schema
+            .reader_builder()
+            .from_reader("kind,client,amount\n".as_bytes());
+        let ProcessError::Row(failure) = resolve_schema(&schema, &mut rdr, false).unwrap_err()
+        else {
+            panic!("expected schema failure");
+        };
+        assert_eq!(failure.class, FailureClass::Schema);
+        assert_eq!(
+            failure.reason,
+            "required field 'type' is not mapped: the header has no column 'type'"
+        );
+    }
+
+    #[test]
+    fn strict_rejects_unexpected_columns() {
+        let schema = CsvSchema::default();
+        let csv = "type,client,tx,amount,note\n";
+        let mut rdr = schema.reader_builder().from_reader(csv.as_bytes());
+        assert!(resolve_schema(&schema, &mut rdr, false).is_ok());
+
+        let mut rdr = schema.reader_builder().from_reader(csv.as_bytes());
+        let err = resolve_schema(&schema, &mut rdr, true).unwrap_err();
+        assert!(err.to_string().contains("note"), "{err}");
+
+        let schema = CsvSchema {
+            ignore: vec!["note".to_string()],
+            ..CsvSchema::default()
+        };
+        let mut rdr = schema.reader_builder().from_reader(csv.as_bytes());
+        assert!(resolve_schema(&schema, &mut rdr, true).is_ok());
+    }
+
+    #[test]
+    fn json_lines_are_classified_like_csv_rows() {
+        let input = "{\"type\":\"deposit\",\"client_id\":1,\"transaction_id\":1,\"amount\":\"1.5\"}\n\
+                     {\"type\":\"refund\",\"client_id\":1,\"transaction_id\":2}\n\
+                     {\"type\":\"withdrawal\",\"client_id\":1,\"transaction_id\":3}\n\
+                     {\"type\":\"deposit\",\"client_id\":1,\"transaction_id\":3,\"amount\":\"x\"}\n\
+                     {\"type\":\"deposit\",\"client_id\":-1,\"transaction_id\":3,\"amount\":\"1\"}\n\
+                     {\"type\":\"dispute\",\"client_id\":\"one\",\"transaction_id\":1}\n\
+                     {\"type\":\"dispute\"\n";
+        let parsed: Vec<_> = JsonLines::new(input.as_bytes())
+            .map(|line| parse_json_line(line.unwrap()).map_err(|f| (f.line, f.class, f.code)))
+            .collect();
+        assert_eq!(
+            parsed,
+            vec![
+                Ok((
+                    1,
+                    TransactionType::Deposit {
+                        client_id: ClientId(1),
+                        transaction_id: TransactionId(1),
+                        amount: Decimal::new(15, 1),
+                    }
+                )),
+                Err((2, FailureClass::InvalidRow, "UNKNOWN_TYPE")),
+                Err((3, FailureClass::InvalidRow, "MISSING_AMOUNT")),
+                Err((4, FailureClass::InvalidRow, "MISSING_AMOUNT")),
+                Err((5, FailureClass::InvalidRow, "MALFORMED_ROW")),
+                Err((6, FailureClass::InvalidRow, "MALFORMED_ROW")),
+                Err((7, FailureClass::Schema, "MALFORMED_ROW")),
+            ]
+        );
+    }
+
+    #[test]
+    fn format_follows_flag_then_extension() {
+        let auto = InputOptions::default();
+        assert_eq!(
+            auto.format_for(Path::new("feed.ndjson.gz")),
+            InputFormat::Jsonl
+        );
+        assert_eq!(auto.format_for(Path::new("-")), InputFormat::Csv);
+
+        let forced = InputOptions {
+            format: Some(InputFormat::Jsonl),
+            ..InputOptions::default()
+        };
+        assert_eq!(forced.format_for(Path::new("-")), InputFormat::Jsonl);
+    }
+
+    #[test]
+    fn custom_schema_parses_partner_layout() {
+        let schema = CsvSchema::from_toml_str(
+            "delimiter = \";\"\nhas_headers = false\n\
+             [columns]\ntype = 1\nclient = 0\ntx = 2\namount = 3\n\
+             [type_aliases]\nDEP = \"deposit\"\n",
+        )
+        .unwrap();
+        let parsed = rows_with(&schema, "7;DEP;1;2.5\n7;chargeback;1\n");
+        assert_eq!(
+            parsed,
+            vec![
+                Ok(TransactionType::Deposit {
+                    client_id: ClientId(7),
+                    transaction_id: TransactionId(1),
+                    amount: Decimal::new(25, 1),
+                }),
+                Ok(TransactionType::Chargeback {
+                    client_id: ClientId(7),
+                    transaction_id: TransactionId(1),
+                }),
+            ]
+        );
+    }
+}
@@ -0,0 +1,125 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Beancount and ledger-cli journal export of a run.
+//!
+//! Accepted transactions are recorded while processing (see
+//! [`RunLog`](crate::RunLog)) and written with the final balances as
+//! assertions once all inputs are done.
+
+use crate::failure::EXIT_IO;
+use clap::Args as ClapArgs;
+use ledger_demo_rs::{AccountSnapshot, JournalFormat, JournalOptions, JournalRecorder};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::process;
+
+/// Journal export options.
+#[derive(ClapArgs, Debug)]
+pub struct JournalArgs {
+    /// Write accepted transactions and closing balance assertions to PATH
+    #[arg(long, value_name = "PATH")]
+    pub journal: Option<PathBuf>,
+
+    /// Journal format: beancount or ledger [default: by extension]
+    ///
+    /// .beancount and .bean are Beancount; .ledger, .journal and .dat are
+    /// ledger-cli.
+    #[arg(long, value_name = "FORMAT", requires = "journal")]
+    pub journal_format: Option<JournalFormat>,
+
+    /// Date of the journal entries, YYYY-MM-DD [default: today (UTC)]
+    #[arg(long, value_name = "DATE", requires = "journal")]
+    pub journal_date: Option<String>,
+
+    /// Commodity of journal amounts [default: USD]
+    #[arg(long, value_name = "CODE", requires = "journal")]
+    pub journal_currency: Option<String>,
+}
+
+/// Where and how to write the journal.
+#[derive(Debug)]
+pub struct JournalPlan {
+    path: PathBuf,
+    format: JournalFormat,
+    options: JournalOptions,
+}
+
+impl JournalArgs {
+    /// Resolves the journal plan if `--journal` was given, exiting with
+    /// [`EXIT_IO`] if the format is unknown or an option is invalid.
+    pub fn plan_or_exit(&self) -> Option<JournalPlan> {
+        let path = self.journal.clone()?;
+        let Some(format) = self
+            .journal_format
+            .or_else(|| JournalFormat::from_path(&path))
+        else {
+            eprintln!(
+                "Error: cannot tell the journal format of '{}'; pass --journal-format",
+                path.display()
+            );
+            process::exit(EXIT_IO);
+        };
+        let mut options = match &self.journal_date {
+            Some(date) => JournalOptions::new(date.clone()),
+            None => JournalOptions::default(),
+        };
+        if let Some(currency) = &self.journal_currency {
+            options.currency = currency.clone();
+        }
+        if let Err(e) = options.validate() {
+            eprintln!("Error: {}", e);
+            process::exit(EXIT_IO);
+        }
+        Some(JournalPlan {
+            path,
+            format,
+            options,
+        })
+    }
+}
+
+impl JournalPlan {
+    /// Writes the recorded entries and balance assertions for `accounts`.
+    pub fn write(
+        &self,
+        recorder: &JournalRecorder,
+        accounts: &[AccountSnapshot],
+    ) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(&self.path)?);
+        recorder.write(self.format, &self.options, accounts, &mut writer)?;
+        writer.flush()
+    }
+
+    /// Like [`write`](Self::write), reporting the outcome on stderr and
+    /// exiting with [`EXIT_IO`] on failure.
+    pub fn write_or_exit(&self, recorder: &JournalRecorder, accounts: &[AccountSnapshot]) {
+        match self.write(recorder, accounts) {
+            Ok(()) => eprintln!(
+                "{} journal entries written to '{}' ({})",
+                recorder.entries().len(),
+                self.path.display(),
+                self.format
+            ),
+            Err(e) => {
+                eprintln!("Error writing journal '{}': {}", self.path.display(), e);
+                process::exit(EXIT_IO);
+            }
+        }
+    }
+}
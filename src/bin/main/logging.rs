@@ -0,0 +1,230 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! `--log-format` and `--log-level`: a `tracing-subscriber` formatter writing
+//! the engine's, server's and CLI's events to stderr.
+//!
+//! Each event is one line, as text (`pretty`) or as a JSON object (`json`)
+//! holding the fields of the event and listing the spans it happened in with
+//! theirs, e.g. `client`, `tx` and `type` on the `process` span and `outcome`
+//! and `error` on the event for a rejected transaction. With `--log-level off`
+//! no subscriber is installed, and callsites below the level are disabled once
+//! and then skipped, so disabled logging costs nothing per transaction.
+
+use clap::Args as ClapArgs;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use tracing::Subscriber;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Logging options, accepted before or after a subcommand.
+#[derive(ClapArgs, Debug)]
+pub struct LogArgs {
+    /// Log line format: pretty or json
+    #[arg(long, value_name = "FORMAT", global = true, default_value_t = LogFormat::default())]
+    pub log_format: LogFormat,
+
+    /// Most verbose events to log: off, error, warn, info, debug or trace
+    ///
+    /// `info` adds skipped rows, rejected transactions and HTTP requests;
+    /// `debug` every transaction; `trace` the balances after each.
+    #[arg(long, value_name = "LEVEL", global = true, default_value_t = LevelFilter::WARN)]
+    pub log_level: LevelFilter,
+}
+
+impl LogArgs {
+    /// Installs the subscriber for the whole process, unless logging is off.
+    pub fn install(&self) {
+        if self.log_level == LevelFilter::OFF {
+            return;
+        }
+        let subscriber = subscriber(self.log_level, self.log_format, io::stderr);
+        // Only fails if a subscriber is already installed, which keeps it.
+        let _ = tracing::subscriber::set_global_default(subscriber);
+    }
+}
+
+/// Log line format.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// `2026-01-31T12:00:00.000000Z  INFO process{client=1 tx=7 type="withdrawal"}: message key="value"`
+    #[default]
+    Pretty,
+    /// One JSON object per line.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pretty" => Ok(Self::Pretty),
+            "json" => Ok(Self::Json),
+            _ => Err(format!(
+                "unknown log format '{s}' (expected pretty or json)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Pretty => "pretty",
+            Self::Json => "json",
+        })
+    }
+}
+
+/// Builds a subscriber writing events at or above `level` as lines to
+/// `writer`.
+fn subscriber<W>(
+    level: LevelFilter,
+    format: LogFormat,
+    writer: W,
+) -> Box<dyn Subscriber + Send + Sync>
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let builder = tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(writer);
+    match format {
+        LogFormat::Pretty => Box::new(builder.with_target(false).finish()),
+        LogFormat::Json => Box::new(
+            builder
+                .json()
+                .flatten_event(true)
+                .with_current_span(false)
+                .with_span_list(true)
+                .finish(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ledger_demo_rs::{ClientId, Engine, TransactionId, TransactionType};
+    use parking_lot::Mutex;
+    use rust_decimal_macros::dec;
+    use serde_json::Value;
+    use std::io::Write;
+    use std::sync::Arc;
+
+    /// A writer whose output the test can read back.
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Runs a deposit and an overdrawing withdrawal under a subscriber,
+    /// returning its output lines.
+    fn log(level: LevelFilter, format: LogFormat) -> Vec<String> {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = subscriber(level, format, move || writer.clone());
+        let engine = Engine::new();
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = tracing::info_span!("input", path = "day1.csv").entered();
+            for (id, tx) in [
+                TransactionType::Deposit {
+                    client_id: ClientId(1),
+                    transaction_id: TransactionId(1),
+                    amount: dec!(10),
+                },
+                TransactionType::Withdrawal {
+                    client_id: ClientId(1),
+                    transaction_id: TransactionId(2),
+                    amount: dec!(50),
+                },
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                let _ = tracing::info_span!("row", line = id + 2).in_scope(|| engine.process(tx));
+            }
+        });
+        let text = String::from_utf8(buffer.0.lock().clone()).unwrap();
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn json_events_carry_span_fields() {
+        let lines = log(LevelFilter::INFO, LogFormat::Json);
+        assert_eq!(lines.len(), 1, "{lines:?}");
+        let event: Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(event["level"], "INFO");
+        assert_eq!(event["target"], "ledger_demo_rs::engine");
+        assert_eq!(event["outcome"], "rejected");
+        assert_eq!(event["error"], "INSUFFICIENT_FUNDS");
+        assert_eq!(
+            event["spans"],
+            serde_json::json!([
+                {"name": "input", "path": "day1.csv"},
+                {"name": "row", "line": 3},
+                {"name": "process", "client": 1, "tx": 2, "type": "withdrawal"},
+            ])
+        );
+    }
+
+    #[test]
+    fn pretty_lines_nest_spans() {
+        let lines = log(LevelFilter::DEBUG, LogFormat::Pretty);
+        assert_eq!(lines.len(), 2, "{lines:?}");
+        assert!(lines[0].ends_with(
+            "DEBUG input{path=\"day1.csv\"}:row{line=2}:process{client=1 tx=1 type=\"deposit\"}: \
+             transaction applied outcome=\"applied\""
+        ), "{lines:?}");
+        assert!(lines[1].contains(" INFO input{path=\"day1.csv\"}:row{line=3}:"));
+        assert!(
+            lines[1].ends_with(
+                "transaction rejected: insufficient available funds \
+             outcome=\"rejected\" error=\"INSUFFICIENT_FUNDS\""
+            ),
+            "{lines:?}"
+        );
+    }
+
+    #[test]
+    fn level_filters_events() {
+        assert!(log(LevelFilter::WARN, LogFormat::Pretty).is_empty());
+        let lines = log(LevelFilter::TRACE, LogFormat::Pretty);
+        assert_eq!(lines.len(), 4, "{lines:?}");
+        // Balances print at the engine's scale, e.g. `10.0000` with fixed-point.
+        assert!(lines[0].contains("balances updated available=10"));
+        assert!(lines[0].ends_with("locked=false version=1"));
+    }
+
+    #[test]
+    fn parses_log_formats() {
+        assert_eq!("JSON".parse(), Ok(LogFormat::Json));
+        assert_eq!(LogFormat::Pretty.to_string(), "pretty");
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+}
@@ -0,0 +1,1454 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+mod failure;
+mod import;
+mod input;
+mod journal;
+mod logging;
+mod payout;
+#[cfg(feature = "http")]
+mod serve;
+mod validate;
+
+use clap::{Args as ClapArgs, Parser, Subcommand};
+use failure::{EXIT_CODES_HELP, EXIT_IO, ProcessError, RowFailure};
+use input::{InputOptions, line_of, open_input, parse_json_line, parse_row, resolve_schema};
+use journal::JournalArgs;
+use ledger_demo_rs::{
+    AccountFilter, AmountPolicy, ColumnRef, ConfigError, CsvSchema, Engine, EngineConfig, Field,
+    InputFormat, JournalRecorder, JsonLines, OutputFormat, PayoutCollector, PrecisionMode,
+    ReportError, RunStats, SortKey, SortOrder, StatementFormat, TransactionError, TransactionType,
+    sort_accounts, write_report,
+};
+use logging::LogArgs;
+use payout::PayoutArgs;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+#[cfg(feature = "http")]
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Instant;
+use validate::Validator;
+
+/// Payment Engine - Process transaction CSV files
+///
+/// Reads transactions from one or more CSV files (or stdin) and outputs
+/// account states to stdout.
+/// Supports deposits, withdrawals, disputes, resolves, and chargebacks.
+#[derive(Parser, Debug)]
+#[command(name = "ledger-demo-rs")]
+#[command(about = "A payment engine that processes transaction CSVs", long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+#[command(after_help = EXIT_CODES_HELP)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// CSV or JSON Lines files with transactions, processed in order (`-`
+    /// reads stdin)
+    ///
+    /// Expected CSV format: type,client,tx,amount
+    /// Gzip and zstd compressed inputs are detected automatically.
+    /// Example: cargo run -- day1.csv.gz day2.csv.zst > accounts.csv
+    #[arg(value_name = "FILE", required_unless_present = "print_config")]
+    inputs: Vec<PathBuf>,
+
+    #[command(flatten)]
+    policy: PolicyArgs,
+
+    #[command(flatten)]
+    schema: SchemaArgs,
+
+    #[command(flatten)]
+    payout: PayoutArgs,
+
+    #[command(flatten)]
+    journal: JournalArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
+
+    /// Print the effective configuration as TOML and exit
+    #[arg(long)]
+    print_config: bool,
+
+    /// Stop at the first malformed row or rejected transaction
+    ///
+    /// The line number and reason are printed to stderr and the process exits
+    /// with the code for the failure class. No accounts are written.
+    #[arg(long)]
+    strict: bool,
+
+    /// Output format: csv, json, jsonl, tsv or table
+    #[arg(long, value_name = "FORMAT", default_value_t = OutputFormat::default())]
+    format: OutputFormat,
+
+    /// Column to sort accounts by: client, available, held or total
+    ///
+    /// Ties are broken by ascending client ID.
+    #[arg(long, value_name = "KEY", default_value_t = SortKey::default())]
+    sort_by: SortKey,
+
+    /// Sort direction: asc or desc
+    #[arg(long, value_name = "ORDER", default_value_t = SortOrder::default())]
+    order: SortOrder,
+
+    /// Only report matching accounts: locked, held>0 or client=<min>..<max>
+    ///
+    /// May be repeated; an account must match every filter.
+    #[arg(long, value_name = "FILTER")]
+    filter: Vec<AccountFilter>,
+
+    /// Print a run summary to stderr, or with `--stats=PATH` write it as JSON
+    #[arg(long, value_name = "PATH", num_args = 0..=1, require_equals = true)]
+    stats: Option<Option<PathBuf>>,
+
+    /// Write engine metrics in the Prometheus text format to PATH at the end
+    ///
+    /// Suitable for node_exporter's textfile collector: the file is replaced
+    /// atomically, so a scrape never sees a partial dump.
+    #[arg(long, value_name = "PATH")]
+    metrics: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Lint input files without computing balances
+    ///
+    /// Checks the header, field types, amounts, duplicate transaction IDs,
+    /// references to unknown transactions and client mismatches. Every problem
+    /// is printed to stdout; the exit code is that of the first one.
+    #[command(after_help = EXIT_CODES_HELP)]
+    Validate {
+        /// CSV or JSON Lines files to check, in processing order (`-` reads stdin)
+        #[arg(value_name = "FILE", required = true)]
+        inputs: Vec<PathBuf>,
+
+        #[command(flatten)]
+        policy: PolicyArgs,
+
+        #[command(flatten)]
+        schema: SchemaArgs,
+    },
+
+    /// Convert camt.053 or MT940 bank statements to transaction CSV
+    ///
+    /// Booked credits become deposits and debits withdrawals. Each entry is
+    /// mapped to a client by matching its reference fields against the
+    /// --clients table; unmatched entries are reported on stderr. The CSV is
+    /// written to stdout.
+    Import {
+        /// Statement files, in order (`-` reads stdin)
+        #[arg(value_name = "FILE", required = true)]
+        inputs: Vec<PathBuf>,
+
+        /// Statement format: camt053 or mt940 [default: by extension]
+        ///
+        /// .xml is camt.053; .sta, .mt940 and .940 are MT940.
+        #[arg(long, value_name = "FORMAT")]
+        format: Option<StatementFormat>,
+
+        /// CSV table with `reference,client` columns mapping statement
+        /// references to client IDs
+        #[arg(long, value_name = "PATH")]
+        clients: PathBuf,
+
+        /// Transaction ID of the first imported entry; later entries count up
+        ///
+        /// Choose a range that does not collide with other feeds.
+        #[arg(long, value_name = "ID", default_value_t = 1)]
+        first_tx: u32,
+    },
+
+    /// Serve the HTTP API over an empty engine
+    ///
+    /// The listening address is printed to stdout once the server accepts
+    /// connections. Stop it with Ctrl-C.
+    #[cfg(feature = "http")]
+    Serve {
+        /// Address to listen on; port 0 picks a free port
+        #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:3000")]
+        bind: SocketAddr,
+
+        /// API keys file (.toml or .json); every request must then present
+        /// one of its keys
+        ///
+        /// Without it the API is open to anyone who can reach it.
+        #[arg(long, value_name = "PATH")]
+        keys: Option<PathBuf>,
+
+        /// Rate limits file (.toml or .json) for transaction submission
+        ///
+        /// Reloaded automatically when the file changes; a file that fails to
+        /// load keeps the previous limits.
+        #[arg(long, value_name = "PATH")]
+        limits: Option<PathBuf>,
+
+        #[command(flatten)]
+        policy: PolicyArgs,
+    },
+}
+
+/// Engine policy options shared by processing and validation.
+#[derive(ClapArgs, Debug)]
+struct PolicyArgs {
+    /// Engine policy file (.toml or .json)
+    ///
+    /// Command-line flags such as --scale take precedence over the file.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Number of decimal places allowed in input amounts (0-4) [default: 4]
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u32).range(0..=AmountPolicy::MAX_SCALE as i64)
+    )]
+    scale: Option<u32>,
+
+    /// Handling of amounts with more decimal places than --scale [default: bankers; reject with fixed-point]
+    ///
+    /// One of: reject, bankers, half-up, truncate
+    #[arg(long, value_name = "MODE")]
+    precision: Option<PrecisionMode>,
+}
+
+impl PolicyArgs {
+    /// Resolves the effective engine configuration: the `--config` file (or
+    /// defaults) with any command-line overrides applied on top.
+    fn engine_config(&self) -> Result<EngineConfig, ConfigError> {
+        let mut config = match &self.config {
+            Some(path) => EngineConfig::from_file(path)?,
+            None => EngineConfig::default(),
+        };
+        config.amounts = AmountPolicy::new(
+            self.scale.unwrap_or(config.amounts.scale()),
+            self.precision.unwrap_or(config.amounts.mode()),
+        );
+        Ok(config)
+    }
+
+    /// Like [`engine_config`](Self::engine_config), exiting on failure.
+    fn engine_config_or_exit(&self) -> EngineConfig {
+        self.engine_config().unwrap_or_else(|e| {
+            eprintln!("Error loading configuration: {}", e);
+            process::exit(EXIT_IO);
+        })
+    }
+}
+
+/// Input format and schema options shared by processing and validation.
+#[derive(ClapArgs, Debug)]
+struct SchemaArgs {
+    /// Input format for every input: csv or jsonl
+    ///
+    /// By default it is chosen per input by extension (.jsonl, .ndjson and
+    /// .json are JSON Lines, also when compressed), falling back to CSV.
+    #[arg(long, value_name = "FORMAT")]
+    input_format: Option<InputFormat>,
+
+    /// Input schema file (.toml or .json) describing columns, delimiter and
+    /// type aliases
+    ///
+    /// The flags below take precedence over the file.
+    #[arg(long, value_name = "PATH")]
+    schema: Option<PathBuf>,
+
+    /// Field delimiter [default: ,]
+    #[arg(long, value_name = "CHAR")]
+    delimiter: Option<char>,
+
+    /// Quote character [default: "]
+    #[arg(long, value_name = "CHAR")]
+    quote: Option<char>,
+
+    /// Treat quote characters as ordinary data
+    #[arg(long)]
+    no_quoting: bool,
+
+    /// Inputs have no header row; columns must be mapped by position
+    #[arg(long)]
+    no_header: bool,
+
+    /// Map a field to a column name or zero-based position, e.g. amount=Value
+    ///
+    /// FIELD is one of type, client, tx or amount. May be repeated.
+    #[arg(long = "column", value_name = "FIELD=COLUMN", value_parser = parse_column)]
+    columns: Vec<(Field, ColumnRef)>,
+
+    /// Accept ALIAS as a transaction type, e.g. DEP=deposit. May be repeated.
+    #[arg(long = "type-alias", value_name = "ALIAS=TYPE", value_parser = parse_type_alias)]
+    type_aliases: Vec<(String, String)>,
+
+    /// Header column to ignore without error in --strict and validate. May
+    /// be repeated.
+    #[arg(long = "ignore-column", value_name = "NAME")]
+    ignore_columns: Vec<String>,
+}
+
+impl SchemaArgs {
+    /// Resolves the effective input schema: the `--schema` file (or defaults)
+    /// with any command-line overrides applied on top.
+    fn csv_schema(&self) -> Result<CsvSchema, ConfigError> {
+        let mut schema = match &self.schema {
+            Some(path) => CsvSchema::from_file(path)?,
+            None => CsvSchema::default(),
+        };
+        if let Some(delimiter) = self.delimiter {
+            schema.delimiter = delimiter;
+        }
+        if let Some(quote) = self.quote {
+            schema.quote = quote;
+        }
+        schema.quoting &= !self.no_quoting;
+        schema.has_headers &= !self.no_header;
+        for (field, column) in &self.columns {
+            schema.columns.set(*field, column.clone());
+        }
+        schema
+            .type_aliases
+            .extend(self.type_aliases.iter().cloned());
+        schema.ignore.extend(self.ignore_columns.iter().cloned());
+        schema.validate()?;
+        Ok(schema)
+    }
+
+    /// Resolves the input options, exiting if the schema cannot be loaded.
+    fn input_options_or_exit(&self, strict: bool) -> InputOptions {
+        let schema = self.csv_schema().unwrap_or_else(|e| {
+            eprintln!("Error loading schema: {}", e);
+            process::exit(EXIT_IO);
+        });
+        InputOptions {
+            schema,
+            strict,
+            format: self.input_format,
+        }
+    }
+}
+
+/// Parses a `--column FIELD=COLUMN` override.
+fn parse_column(s: &str) -> Result<(Field, ColumnRef), String> {
+    let (field, column) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected FIELD=COLUMN, got '{s}'"))?;
+    Ok((field.trim().parse()?, column.trim().parse()?))
+}
+
+/// Parses a `--type-alias ALIAS=TYPE` pair.
+fn parse_type_alias(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((alias, target)) if !alias.is_empty() && !target.is_empty() => {
+            Ok((alias.to_string(), target.to_string()))
+        }
+        _ => Err(format!("expected ALIAS=TYPE, got '{s}'")),
+    }
+}
+
+fn main() {
+    // Parse command line arguments
+    let args = Args::parse();
+    args.log.install();
+
+    match &args.command {
+        Some(Command::Validate {
+            inputs,
+            policy,
+            schema,
+        }) => process::exit(run_validate(
+            inputs,
+            policy.engine_config_or_exit(),
+            schema.input_options_or_exit(true),
+        )),
+        Some(Command::Import {
+            inputs,
+            format,
+            clients,
+            first_tx,
+        }) => process::exit(import::run_import(inputs, *format, clients, *first_tx)),
+        #[cfg(feature = "http")]
+        Some(Command::Serve {
+            bind,
+            keys,
+            limits,
+            policy,
+        }) => process::exit(serve::run_serve(
+            *bind,
+            keys.as_deref(),
+            limits.as_deref(),
+            policy.engine_config_or_exit(),
+        )),
+        None => {}
+    }
+
+    // Resolve engine configuration
+    let config = args.policy.engine_config_or_exit();
+    let options = args.schema.input_options_or_exit(args.strict);
+
+    if args.print_config {
+        print!("{}", config.to_toml());
+        return;
+    }
+
+    // Process every input in order through a single engine, so deduplication
+    // and dispute lookups span all of them.
+    // TODO: Consider memory-mapping for parsing large transaction CSV files.
+    let engine = Engine::builder().config(config).build();
+    let payouts = args.payout.plan_or_exit();
+    let journal = args.journal.plan_or_exit();
+    let mut log = RunLog {
+        stats: RunStats::new(),
+        payouts: payouts.as_ref().map(|_| PayoutCollector::new()),
+        journal: journal.as_ref().map(|_| JournalRecorder::new()),
+    };
+    let started = Instant::now();
+    for input in &args.inputs {
+        let _span = tracing::info_span!("input", path = %input.display()).entered();
+        let reader = open_input_or_exit(input);
+
+        let result = match options.format_for(input) {
+            InputFormat::Csv => process_into(&engine, reader, &options, &mut log),
+            InputFormat::Jsonl => process_json_lines_into(&engine, reader, &options, &mut log),
+        };
+        if let Err(e) = result {
+            eprintln!("Error processing '{}': {}", input.display(), e);
+            process::exit(e.exit_code());
+        }
+    }
+
+    // Export the accepted withdrawals
+    if let (Some(plan), Some(collector)) = (&payouts, log.payouts.take()) {
+        plan.write_or_exit(&collector.into_payouts());
+    }
+
+    // Export the journal with closing balance assertions
+    if let (Some(plan), Some(recorder)) = (&journal, &log.journal) {
+        plan.write_or_exit(recorder, &engine.accounts());
+    }
+
+    // Report run statistics
+    if let Some(destination) = &args.stats {
+        log.stats.finish(&engine.accounts(), started.elapsed());
+        if let Err(e) = write_stats(&log.stats, destination.as_deref()) {
+            eprintln!("Error writing stats: {}", e);
+            process::exit(EXIT_IO);
+        }
+    }
+
+    // Dump metrics for the textfile collector
+    if let Some(path) = &args.metrics
+        && let Err(e) = write_metrics(&engine, path)
+    {
+        eprintln!("Error writing metrics to '{}': {}", path.display(), e);
+        process::exit(EXIT_IO);
+    }
+
+    // Write results to stdout
+    if let Err(e) = write_accounts_sorted(
+        &engine,
+        args.format,
+        (args.sort_by, args.order),
+        &args.filter,
+        std::io::stdout(),
+    ) {
+        eprintln!("Error writing output: {}", e);
+        process::exit(EXIT_IO);
+    }
+}
+
+/// Opens an input, exiting with [`EXIT_IO`] on failure.
+fn open_input_or_exit(path: &Path) -> Box<dyn Read> {
+    open_input(path).unwrap_or_else(|e| {
+        eprintln!("Error opening file '{}': {}", path.display(), e);
+        process::exit(EXIT_IO);
+    })
+}
+
+/// Runs the `validate` subcommand, returning the process exit code.
+///
+/// Problems are printed to stdout as `<file>: line <n>: <reason> [<CODE>]`; the
+/// exit code is that of the first problem, or 0 if there are none.
+fn run_validate(inputs: &[PathBuf], config: EngineConfig, options: InputOptions) -> i32 {
+    let mut validator = Validator::new(config, options.schema.clone());
+    let mut first: Option<RowFailure> = None;
+    let mut count = 0;
+
+    for input in inputs {
+        let reader = open_input_or_exit(input);
+        let result = match options.format_for(input) {
+            InputFormat::Csv => validator.validate(reader).map_err(ProcessError::from),
+            InputFormat::Jsonl => validator
+                .validate_json_lines(reader)
+                .map_err(ProcessError::from),
+        };
+        let failures = match result {
+            Ok(failures) => failures,
+            Err(e) => {
+                eprintln!("Error reading '{}': {}", input.display(), e);
+                return EXIT_IO;
+            }
+        };
+
+        for failure in failures {
+            println!("{}: {}", input.display(), failure);
+            count += 1;
+            first.get_or_insert(failure);
+        }
+    }
+
+    match first {
+        None => {
+            eprintln!("No problems found");
+            0
+        }
+        Some(failure) => {
+            eprintln!("{count} problem(s) found");
+            failure.class.exit_code()
+        }
+    }
+}
+
+/// Writes run statistics as text to stderr, or as JSON to `path` if given.
+fn write_stats(stats: &RunStats, path: Option<&Path>) -> io::Result<()> {
+    match path {
+        None => writeln!(io::stderr(), "{stats}"),
+        Some(path) => {
+            let mut file = File::create(path)?;
+            serde_json::to_writer_pretty(&mut file, stats)?;
+            writeln!(file)
+        }
+    }
+}
+
+/// Writes the engine's metrics to `path` via a temporary file in the same
+/// directory, renamed into place once complete.
+fn write_metrics(engine: &Engine, path: &Path) -> io::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    std::fs::write(&temporary, engine.metrics_text())?;
+    std::fs::rename(&temporary, path)
+}
+
+/// Process transactions from a CSV reader.
+///
+/// This function uses streaming parsing to handle arbitrarily large CSV files
+/// without loading the entire file into memory. Malformed rows and invalid
+/// transactions are silently skipped per the specification.
+///
+/// # CSV Format
+///
+/// Expected columns: `type, client, tx, amount`
+/// - `type`: Transaction type (deposit, withdrawal, dispute, resolve, chargeback)
+/// - `client`: Client ID (u16)
+/// - `tx`: Transaction ID (u32)
+/// - `amount`: Decimal amount (optional for dispute/resolve/chargeback)
+///
+/// # Example
+///
+/// ```csv
+/// type,client,tx,amount
+/// deposit,1,1,100.0
+/// withdrawal,1,2,50.0
+/// dispute,1,1,
+/// ```
+///
+/// # Errors
+///
+/// Returns a CSV error if the reader fails or the CSV structure is invalid.
+/// Individual transaction errors are logged in debug mode but don't stop processing.
+pub fn process_transactions<R: Read>(reader: R) -> Result<Engine, ProcessError> {
+    process_transactions_with_config(reader, EngineConfig::default())
+}
+
+/// Process transactions from a CSV reader into an engine built from `config`.
+///
+/// See [`process_transactions`] for the input format and error semantics.
+pub fn process_transactions_with_config<R: Read>(
+    reader: R,
+    config: EngineConfig,
+) -> Result<Engine, ProcessError> {
+    let engine = Engine::builder().config(config).build();
+    process_into(
+        &engine,
+        reader,
+        &InputOptions::default(),
+        &mut RunLog::default(),
+    )?;
+    Ok(engine)
+}
+
+/// Process transactions from a CSV reader into an existing engine.
+///
+/// Each reader must start with its own header row unless the schema says
+/// otherwise. Feeding several readers into the same engine processes them as
+/// one ordered stream: transaction IDs are deduplicated across all of them and
+/// disputes may reference transactions from earlier inputs.
+///
+/// Rows are read according to `options.schema`; a header missing a required
+/// column fails with [`ProcessError::Row`]. Every row's outcome is recorded in
+/// `log`. In strict mode unmapped header columns are rejected and processing
+/// stops at the first failing row; otherwise failing rows are skipped.
+pub fn process_into<R: Read>(
+    engine: &Engine,
+    reader: R,
+    options: &InputOptions,
+    log: &mut RunLog,
+) -> Result<(), ProcessError> {
+    let strict = options.strict;
+    let mut rdr = options.schema.reader_builder().from_reader(reader);
+
+    let schema = resolve_schema(&options.schema, &mut rdr, strict)?;
+    let header_len = if options.schema.has_headers {
+        Some(rdr.headers()?.len())
+    } else {
+        None
+    };
+
+    let rows = rdr.records().map(|result| {
+        // Parse the row into a transaction
+        let record = result.map_err(|e| RowFailure {
+            line: e.position().map_or(0, |p| p.line()),
+            class: failure::FailureClass::Schema,
+            code: "MALFORMED_ROW",
+            reason: e.to_string(),
+        })?;
+        Ok((line_of(&record), parse_row(&record, &schema, header_len)?))
+    });
+    apply_rows(engine, rows, strict, log)
+}
+
+/// Process transactions from a JSON Lines reader into an existing engine.
+///
+/// Each non-blank line holds one [`TransactionRequest`](ledger_demo_rs::TransactionRequest).
+/// Undecodable lines and rejected transactions are handled exactly like
+/// failing CSV rows in [`process_into`]; `options.schema` is not used.
+pub fn process_json_lines_into<R: Read>(
+    engine: &Engine,
+    reader: R,
+    options: &InputOptions,
+    log: &mut RunLog,
+) -> Result<(), ProcessError> {
+    let rows = JsonLines::new(BufReader::new(reader)).map(|line| Ok(parse_json_line(line?)?));
+    apply_rows(engine, rows, options.strict, log)
+}
+
+/// What a run records about its rows besides the balances.
+#[derive(Debug, Default)]
+pub struct RunLog {
+    /// Outcome counts for `--stats`.
+    pub stats: RunStats,
+    /// Accepted withdrawals for `--payouts`, if requested.
+    pub payouts: Option<PayoutCollector>,
+    /// Accepted transactions for `--journal`, if requested.
+    pub journal: Option<JournalRecorder>,
+}
+
+impl RunLog {
+    /// Records the engine's outcome for a transaction.
+    ///
+    /// Payouts and journal entries get the amount the engine applied, after
+    /// its amount policy.
+    fn record(
+        &mut self,
+        engine: &Engine,
+        tx: &TransactionType,
+        result: &Result<(), TransactionError>,
+    ) {
+        self.stats.record(tx, result);
+        if self.payouts.is_none() && self.journal.is_none() {
+            return;
+        }
+        let applied = engine.config().amounts.apply_to(*tx).unwrap_or(*tx);
+        if let Some(payouts) = &mut self.payouts {
+            payouts.record(&applied, result);
+        }
+        if let Some(journal) = &mut self.journal {
+            journal.record(&applied, result);
+        }
+    }
+}
+
+/// Feeds parsed rows to the engine, recording every outcome in `log`.
+///
+/// Row failures ([`ProcessError::Row`]) are skipped unless `strict`; any
+/// other error stops processing.
+fn apply_rows(
+    engine: &Engine,
+    rows: impl Iterator<Item = Result<(u64, TransactionType), ProcessError>>,
+    strict: bool,
+    log: &mut RunLog,
+) -> Result<(), ProcessError> {
+    for parsed in rows {
+        let (line, tx) = match parsed {
+            Ok(parsed) => parsed,
+            Err(ProcessError::Row(failure)) if !strict => {
+                // Skip malformed rows
+                tracing::info!(
+                    line = failure.line,
+                    error = failure.code,
+                    "skipping row: {}",
+                    failure.reason
+                );
+                log.stats.record_parse_failure(failure.code);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        // Process transaction; errors are skipped unless strict, and the
+        // engine logs them with the line number from this span
+        let result = tracing::info_span!("row", line).in_scope(|| engine.process(tx));
+        log.record(engine, &tx, &result);
+        if let Err(e) = result
+            && strict
+        {
+            return Err(RowFailure::from_error(line, &client_mismatch(engine, &tx, e)).into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Reports a dispute, resolve or chargeback of a transaction that exists only
+/// under other clients as a [`TransactionError::ClientMismatch`] rather than
+/// the engine's [`TransactionError::TransactionNotFound`], as `validate` does.
+fn client_mismatch(
+    engine: &Engine,
+    tx: &TransactionType,
+    err: TransactionError,
+) -> TransactionError {
+    let reference = matches!(
+        tx,
+        TransactionType::Dispute { .. }
+            | TransactionType::Resolve { .. }
+            | TransactionType::Chargeback { .. }
+    );
+    if err == TransactionError::TransactionNotFound && reference {
+        let owners = engine.find_transaction(tx.id());
+        if !owners.is_empty()
+            && owners
+                .iter()
+                .all(|stored| stored.transaction.client_id() != tx.client_id())
+        {
+            return TransactionError::ClientMismatch;
+        }
+    }
+    err
+}
+
+/// Write account states to a writer in the given format.
+///
+/// Outputs all accounts with 4 decimal precision, in ascending client ID
+/// order; every format shares the same `AccountSnapshot` serialization.
+///
+/// # CSV Format
+///
+/// Columns: `client, available, held, total, locked`
+///
+/// # Example
+///
+/// ```csv
+/// client,available,held,total,locked
+/// 1,75.5000,0.0000,75.5000,false
+/// 2,100.0000,25.0000,125.0000,false
+/// ```
+///
+/// # Errors
+///
+/// Returns a report error if serialization or writing fails.
+pub fn write_accounts<W: Write>(
+    engine: &Engine,
+    format: OutputFormat,
+    writer: W,
+) -> Result<(), ReportError> {
+    write_report(&engine.accounts(), format, writer)
+}
+
+/// Write the accounts selected by `filters`, sorted by `key` and `order`.
+///
+/// See [`write_accounts`] for the output formats.
+pub fn write_accounts_sorted<W: Write>(
+    engine: &Engine,
+    format: OutputFormat,
+    (key, order): (SortKey, SortOrder),
+    filters: &[AccountFilter],
+    writer: W,
+) -> Result<(), ReportError> {
+    let mut accounts: Vec<_> = engine
+        .accounts()
+        .into_iter()
+        .filter(|account| filters.iter().all(|f| f.matches(account)))
+        .collect();
+    sort_accounts(&mut accounts, key, order);
+    write_report(&accounts, format, writer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::decompress;
+    use ledger_demo_rs::ClientId;
+    use ledger_demo_rs::{ChargebackPolicy, RoundingMode};
+    use rust_decimal_macros::dec;
+    use std::io::Cursor;
+
+    fn strict() -> InputOptions {
+        InputOptions {
+            strict: true,
+            ..InputOptions::default()
+        }
+    }
+
+    #[test]
+    fn parse_simple_deposit() {
+        let csv = "type,client,tx,amount\ndeposit,1,1,100.0\n";
+        let reader = Cursor::new(csv);
+
+        let engine = process_transactions(reader).unwrap();
+
+        assert_eq!(engine.accounts().len(), 1);
+        let account = engine.get_account(&ClientId(1)).unwrap();
+        assert_eq!(account.available, dec!(100.0));
+    }
+
+    #[test]
+    fn parse_deposit_and_withdrawal() {
+        let csv = "type,client,tx,amount\n\
+                   deposit,1,1,100.0\n\
+                   withdrawal,1,2,30.0\n";
+        let reader = Cursor::new(csv);
+
+        let engine = process_transactions(reader).unwrap();
+
+        assert_eq!(engine.accounts().len(), 1);
+        let account = engine.get_account(&ClientId(1)).unwrap();
+        assert_eq!(account.available, dec!(70.0));
+    }
+
+    #[test]
+    fn parse_dispute_sequence() {
+        let csv = "type,client,tx,amount\n\
+                   deposit,1,1,100.0\n\
+                   dispute,1,1,\n";
+        let reader = Cursor::new(csv);
+
+        let engine = process_transactions(reader).unwrap();
+
+        assert_eq!(engine.accounts().len(), 1);
+        let account = engine.get_account(&ClientId(1)).unwrap();
+        assert_eq!(account.available, dec!(0.0));
+        assert_eq!(account.held, dec!(100.0));
+    }
+
+    #[test]
+    fn parse_resolve_sequence() {
+        let csv = "type,client,tx,amount\n\
+                   deposit,1,1,100.0\n\
+                   dispute,1,1,\n\
+                   resolve,1,1,\n";
+        let reader = Cursor::new(csv);
+
+        let engine = process_transactions(reader).unwrap();
+
+        let account = engine.get_account(&ClientId(1)).unwrap();
+        assert_eq!(account.available, dec!(100.0));
+        assert_eq!(account.held, dec!(0.0));
+    }
+
+    #[test]
+    fn parse_chargeback_sequence() {
+        let csv = "type,client,tx,amount\n\
+                   deposit,1,1,100.0\n\
+                   dispute,1,1,\n\
+                   chargeback,1,1,\n";
+        let reader = Cursor::new(csv);
+
+        let engine = process_transactions(reader).unwrap();
+
+        let account = engine.get_account(&ClientId(1)).unwrap();
+        assert_eq!(account.total, dec!(0.0));
+        assert!(account.locked);
+    }
+
+    #[test]
+    fn parse_with_whitespace() {
+        let csv = "type,client,tx,amount\n deposit , 1 , 1 , 100.0 \n";
+        let reader = Cursor::new(csv);
+
+        let engine = process_transactions(reader).unwrap();
+
+        assert_eq!(engine.accounts().len(), 1);
+        let account = engine.get_account(&ClientId(1)).unwrap();
+        assert_eq!(account.available, dec!(100.0));
+    }
+
+    #[test]
+    fn skip_malformed_rows() {
+        let csv = "type,client,tx,amount\n\
+                   deposit,1,1,100.0\n\
+                   invalid,row,data,here\n\
+                   deposit,2,2,50.0\n";
+        let reader = Cursor::new(csv);
+
+        let engine = process_transactions(reader).unwrap();
+
+        assert_eq!(engine.accounts().len(), 2); // Two valid deposits
+    }
+
+    #[test]
+    fn write_accounts_to_csv() {
+        let csv_input = "type,client,tx,amount\n\
+                         deposit,1,1,100.5\n\
+                         deposit,2,2,200.25\n";
+        let reader = Cursor::new(csv_input);
+        let engine = process_transactions(reader).unwrap();
+
+        let mut output = Vec::new();
+        write_accounts(&engine, OutputFormat::Csv, &mut output).unwrap();
+
+        let output_str = String::from_utf8(output).unwrap();
+        assert!(output_str.contains("client,available,held,total,locked"));
+    }
+
+    #[test]
+    fn sorted_and_filtered_output() {
+        let csv_input = "type,client,tx,amount\n\
+                         deposit,3,1,10.0\n\
+                         deposit,1,2,30.0\n\
+                         deposit,2,3,20.0\n\
+                         deposit,4,4,5.0\n\
+                         dispute,4,4,\n";
+        let engine = process_transactions(Cursor::new(csv_input)).unwrap();
+
+        let mut output = Vec::new();
+        write_accounts_sorted(
+            &engine,
+            OutputFormat::Csv,
+            (SortKey::Available, SortOrder::Desc),
+            &["client=..3".parse().unwrap()],
+            &mut output,
+        )
+        .unwrap();
+        let clients: Vec<&str> = std::str::from_utf8(&output)
+            .unwrap()
+            .lines()
+            .skip(1)
+            .map(|line| line.split(',').next().unwrap())
+            .collect();
+        assert_eq!(clients, vec!["1", "2", "3"]);
+
+        let mut output = Vec::new();
+        write_accounts_sorted(
+            &engine,
+            OutputFormat::Csv,
+            (SortKey::Client, SortOrder::Asc),
+            &[AccountFilter::HeldPositive],
+            &mut output,
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().count(), 2);
+        assert!(output.lines().nth(1).unwrap().starts_with("4,"));
+    }
+
+    #[test]
+    fn write_preserves_decimal_values() {
+        let csv_input = "type,client,tx,amount\ndeposit,1,1,1.5\n";
+        let reader = Cursor::new(csv_input);
+        let engine = process_transactions(reader).unwrap();
+
+        let mut output = Vec::new();
+        write_accounts(&engine, OutputFormat::Csv, &mut output).unwrap();
+
+        let account = engine.get_account(&ClientId(1)).unwrap();
+        assert_eq!(account.available, dec!(1.5));
+    }
+
+    #[cfg(not(feature = "fixed-point"))]
+    #[test]
+    fn excess_precision_is_rounded_by_default() {
+        let csv = "type,client,tx,amount\ndeposit,1,1,0.00015\n";
+        let engine = process_transactions(Cursor::new(csv)).unwrap();
+
+        let account = engine.get_account(&ClientId(1)).unwrap();
+        assert_eq!(account.available, dec!(0.0002));
+    }
+
+    #[test]
+    fn excess_precision_rejected_when_configured() {
+        let csv = "type,client,tx,amount\n\
+                   deposit,1,1,1.005\n\
+                   deposit,1,2,2.50\n";
+        let config = EngineConfig {
+            amounts: AmountPolicy::new(2, PrecisionMode::Reject),
+            ..EngineConfig::default()
+        };
+        let engine = process_transactions_with_config(Cursor::new(csv), config).unwrap();
+
+        let account = engine.get_account(&ClientId(1)).unwrap();
+        assert_eq!(account.available, dec!(2.50));
+    }
+
+    #[test]
+    fn metrics_file_replaces_previous_dump() {
+        let path = std::env::temp_dir().join(format!("ledger-cli-{}.prom", process::id()));
+        std::fs::write(&path, "stale\n").unwrap();
+        let csv = "type,client,tx,amount\n\
+                   deposit,1,1,10\n\
+                   withdrawal,1,2,50\n";
+        let engine = process_transactions(Cursor::new(csv)).unwrap();
+
+        write_metrics(&engine, &path).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(!text.contains("stale"));
+        assert!(
+            text.contains("ledger_transactions_total{type=\"deposit\",result=\"applied\"} 1\n")
+        );
+        assert!(text.contains("ledger_transaction_errors_total{code=\"INSUFFICIENT_FUNDS\"} 1\n"));
+        assert!(text.contains("ledger_accounts 1\n"));
+        assert!(text.contains("ledger_liabilities 10\n"));
+        assert!(text.contains("ledger_process_duration_seconds_count 2\n"));
+    }
+
+    #[test]
+    fn command_line_overrides_config_file() {
+        let path = std::env::temp_dir().join(format!("ledger-cli-{}.toml", process::id()));
+        std::fs::write(
+            &path,
+            "chargeback = \"keep-open\"\n[amounts]\nscale = 2\nprecision = \"truncate\"\n",
+        )
+        .unwrap();
+
+        let args = Args::parse_from([
+            "main".as_ref(),
+            "--config".as_ref(),
+            path.as_os_str(),
+            "--scale".as_ref(),
+            "3".as_ref(),
+            "--print-config".as_ref(),
+        ]);
+        let config = args.policy.engine_config().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.chargeback, ChargebackPolicy::KeepOpen);
+        assert_eq!(config.amounts.scale(), 3);
+        assert_eq!(
+            config.amounts.mode(),
+            PrecisionMode::Round(RoundingMode::Truncate)
+        );
+        assert!(args.inputs.is_empty());
+    }
+
+    #[test]
+    fn inputs_share_dedup_and_dispute_state() {
+        let engine = Engine::new();
+        let mut log = RunLog::default();
+        process_into(
+            &engine,
+            Cursor::new("type,client,tx,amount\ndeposit,1,1,100.0\n"),
+            &InputOptions::default(),
+            &mut log,
+        )
+        .unwrap();
+        process_into(
+            &engine,
+            Cursor::new("type,client,tx,amount\ndeposit,1,1,50.0\ndispute,1,1,\n"),
+            &InputOptions::default(),
+            &mut log,
+        )
+        .unwrap();
+        assert_eq!(
+            (log.stats.rows, log.stats.accepted, log.stats.rejected),
+            (3, 2, 1)
+        );
+
+        let account = engine.get_account(&ClientId(1)).unwrap();
+        assert_eq!(account.available, dec!(0));
+        assert_eq!(account.held, dec!(100.0));
+    }
+
+    #[test]
+    fn decodes_concatenated_gzip_members() {
+        let mut data = Vec::new();
+        for part in [&b"type,client,tx,amount\n"[..], b"deposit,1,1,1.0\n"] {
+            let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+            gz.write_all(part).unwrap();
+            data.extend(gz.finish().unwrap());
+        }
+
+        let engine = Engine::new();
+        process_into(
+            &engine,
+            decompress(Cursor::new(data)).unwrap(),
+            &InputOptions::default(),
+            &mut RunLog::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            engine.get_account(&ClientId(1)).unwrap().available,
+            dec!(1.0)
+        );
+    }
+
+    #[test]
+    fn payouts_and_journal_record_applied_amounts() {
+        let csv = "type,client,tx,amount\n\
+                   deposit,1,1,10.0\n\
+                   withdrawal,1,2,4.005\n\
+                   withdrawal,1,3,50.0\n\
+                   withdrawal,1,4,5.99\n";
+        let engine = Engine::builder()
+            .amount_policy(AmountPolicy::new(
+                2,
+                PrecisionMode::Round(RoundingMode::HalfUp),
+            ))
+            .build();
+        let mut log = RunLog {
+            payouts: Some(PayoutCollector::new()),
+            journal: Some(JournalRecorder::new()),
+            ..RunLog::default()
+        };
+        process_into(
+            &engine,
+            Cursor::new(csv),
+            &InputOptions::default(),
+            &mut log,
+        )
+        .unwrap();
+
+        let payouts = log.payouts.unwrap().into_payouts();
+        let paid: Vec<_> = payouts
+            .iter()
+            .map(|p| (p.transaction_id.0, p.amount))
+            .collect();
+        assert_eq!(paid, vec![(2, dec!(4.01)), (4, dec!(5.99))]);
+        let journal = log.journal.unwrap();
+        let amounts: Vec<_> = journal.entries().iter().map(|e| e.amount).collect();
+        assert_eq!(amounts, vec![dec!(10.0), dec!(4.01), dec!(5.99)]);
+        assert_eq!(log.stats.rejected, 1);
+    }
+
+    #[test]
+    fn stats_count_every_row_outcome() {
+        let csv = "type,client,tx,amount\n\
+                   deposit,1,1,10.0\n\
+                   withdrawal,1,2,50.0\n\
+                   deposit,1,3,1.00001\n\
+                   refund,1,4,1.0\n\
+                   deposit,1,5\n\
+                   deposit,x,6,1.0\n\
+                   dispute,1,1,\n";
+        let engine = Engine::builder()
+            .amount_policy(AmountPolicy::new(4, PrecisionMode::Reject))
+            .build();
+        let mut log = RunLog::default();
+        process_into(
+            &engine,
+            Cursor::new(csv),
+            &InputOptions::default(),
+            &mut log,
+        )
+        .unwrap();
+        log.stats
+            .finish(&engine.accounts(), std::time::Duration::from_secs(1));
+
+        assert_eq!(log.stats.rows, 7);
+        assert_eq!(
+            (log.stats.accepted, log.stats.rejected, log.stats.skipped),
+            (2, 2, 3)
+        );
+        assert_eq!(log.stats.transactions["deposit"].accepted, 1);
+        assert_eq!(log.stats.transactions["withdrawal"].rejected, 1);
+        assert_eq!(log.stats.errors["INSUFFICIENT_FUNDS"], 1);
+        assert_eq!(log.stats.errors["EXCESS_PRECISION"], 1);
+        assert_eq!(log.stats.parse_failures["UNKNOWN_TYPE"], 1);
+        assert_eq!(log.stats.parse_failures["MISSING_AMOUNT"], 1);
+        assert_eq!(log.stats.parse_failures["MALFORMED_ROW"], 1);
+        assert_eq!(log.stats.total_held, Some(dec!(10.0)));
+        assert_eq!(log.stats.total_liabilities, Some(dec!(10.0)));
+    }
+
+    #[test]
+    fn strict_mode_stops_at_first_failure() {
+        let csv = "type,client,tx,amount\n\
+                   deposit,1,1,10.0\n\
+                   withdrawal,1,2,50.0\n\
+                   deposit,1,3,5.0\n";
+        let engine = Engine::new();
+        let err =
+            process_into(&engine, Cursor::new(csv), &strict(), &mut RunLog::default()).unwrap_err();
+
+        let ProcessError::Row(failure) = &err else {
+            panic!("expected row failure, got {err}");
+        };
+        assert_eq!(failure.line, 3);
+        assert_eq!(failure.code, "INSUFFICIENT_FUNDS");
+        assert_eq!(err.exit_code(), 8);
+        // Rows after the failure are not processed
+        assert_eq!(
+            engine.get_account(&ClientId(1)).unwrap().available,
+            dec!(10.0)
+        );
+    }
+
+    #[test]
+    fn strict_mode_reports_client_mismatches() {
+        let csv = "type,client,tx,amount\n\
+                   deposit,1,1,10.0\n\
+                   deposit,2,2,1.0\n\
+                   dispute,2,1,\n";
+        let err = process_into(
+            &Engine::new(),
+            Cursor::new(csv),
+            &strict(),
+            &mut RunLog::default(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 4: client does not own this transaction [CLIENT_MISMATCH]"
+        );
+        assert_eq!(err.exit_code(), 7);
+
+        // An ID no client has used is still unknown
+        let csv = "type,client,tx,amount\ndeposit,1,1,10.0\nchargeback,1,9,\n";
+        let err = process_into(
+            &Engine::new(),
+            Cursor::new(csv),
+            &strict(),
+            &mut RunLog::default(),
+        )
+        .unwrap_err();
+        assert_eq!(err.exit_code(), 6);
+    }
+
+    #[test]
+    fn validate_and_strict_mode_classify_rows_alike() {
+        let deposits = EngineConfig::default();
+        let withdrawals = EngineConfig {
+            disputable: ledger_demo_rs::DisputePolicy::DepositsAndWithdrawals,
+            ..EngineConfig::default()
+        };
+        for (config, rows) in [
+            (deposits, "deposit,1,1,10.0\ndeposit,2,1,10.0\n"),
+            (deposits, "deposit,1,1,10.0\ndispute,1,9,\n"),
+            (
+                deposits,
+                "deposit,1,1,10.0\ndeposit,2,2,1.0\ndispute,2,1,\n",
+            ),
+            (
+                deposits,
+                "deposit,1,1,10.0\nwithdrawal,1,2,5.0\ndispute,1,2,\n",
+            ),
+            (
+                withdrawals,
+                "deposit,1,1,10.0\nwithdrawal,1,2,5.0\ndispute,1,2,\n",
+            ),
+            (deposits, "deposit,1,1,-1\n"),
+        ] {
+            let csv = format!("type,client,tx,amount\n{rows}");
+            let validated = Validator::new(config, CsvSchema::default())
+                .validate(csv.as_bytes())
+                .unwrap()
+                .first()
+                .map(|failure| failure.class.exit_code());
+            let engine = Engine::with_config(config);
+            let processed = process_into(
+                &engine,
+                Cursor::new(&csv),
+                &strict(),
+                &mut RunLog::default(),
+            )
+            .err()
+            .map(|err| err.exit_code());
+            assert_eq!(validated, processed, "{rows}");
+        }
+    }
+
+    #[test]
+    fn strict_mode_checks_header_and_rows() {
+        let engine = Engine::new();
+        let err = process_into(
+            &engine,
+            Cursor::new("kind,client,tx,amount\ndeposit,1,1,1.0\n"),
+            &strict(),
+            &mut RunLog::default(),
+        )
+        .unwrap_err();
+        assert_eq!(err.exit_code(), 3);
+
+        let err = process_into(
+            &engine,
+            Cursor::new("type,client,tx,amount\ndeposit,1,1,1.0\nrefund,1,2,1.0\n"),
+            &strict(),
+            &mut RunLog::default(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 3: unknown transaction type 'refund' [UNKNOWN_TYPE]"
+        );
+        assert_eq!(err.exit_code(), 4);
+    }
+
+    #[test]
+    fn schema_flags_read_partner_layout() {
+        let args = Args::parse_from([
+            "main",
+            "--delimiter",
+            ";",
+            "--column",
+            "type=Kind",
+            "--column",
+            "client=Customer",
+            "--column",
+            "amount=Value",
+            "--type-alias",
+            "DEP=deposit",
+            "--type-alias",
+            "WDR=withdrawal",
+            "--ignore-column",
+            "Booked",
+            "in.csv",
+        ]);
+        let options = InputOptions {
+            schema: args.schema.csv_schema().unwrap(),
+            strict: true,
+            format: None,
+        };
+        let csv = "Booked;Kind;Customer;tx;Value\n\
+                   2025-01-02;DEP;1;1;10.0\n\
+                   2025-01-03;WDR;1;2;4.0\n";
+
+        let engine = Engine::new();
+        process_into(&engine, Cursor::new(csv), &options, &mut RunLog::default()).unwrap();
+        assert_eq!(
+            engine.get_account(&ClientId(1)).unwrap().available,
+            dec!(6.0)
+        );
+    }
+
+    #[test]
+    fn headerless_input_maps_columns_by_position() {
+        let args = Args::parse_from(["main", "--no-header", "--column", "type=0", "in.csv"]);
+        // Fields mapped by name have no header to resolve against.
+        assert!(args.schema.csv_schema().is_err());
+
+        let args = Args::parse_from([
+            "main",
+            "--no-header",
+            "--column",
+            "type=0",
+            "--column",
+            "client=1",
+            "--column",
+            "tx=2",
+            "--column",
+            "amount=3",
+            "in.csv",
+        ]);
+        let options = InputOptions {
+            schema: args.schema.csv_schema().unwrap(),
+            strict: false,
+            format: None,
+        };
+        let engine = Engine::new();
+        process_into(
+            &engine,
+            Cursor::new("deposit,1,1,5.0\ndeposit,2,2,3.0\n"),
+            &options,
+            &mut RunLog::default(),
+        )
+        .unwrap();
+        assert_eq!(engine.accounts().len(), 2);
+    }
+
+    #[test]
+    fn missing_mapped_column_is_a_schema_error() {
+        let err = process_into(
+            &Engine::new(),
+            Cursor::new("type,customer,tx,amount\ndeposit,1,1,1.0\n"),
+            &InputOptions::default(),
+            &mut RunLog::default(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 1: required field 'client' is not mapped: the header has no column 'client' \
+             [MISSING_COLUMN]"
+        );
+        assert_eq!(err.exit_code(), 3);
+    }
+
+    #[test]
+    fn json_lines_input_matches_csv_semantics() {
+        let json = "{\"type\":\"deposit\",\"client_id\":1,\"transaction_id\":1,\"amount\":\"10.0\"}\n\
+                    {\"type\":\"withdrawal\",\"client_id\":1,\"transaction_id\":2,\"amount\":\"50.0\"}\n\
+                    \n\
+                    {\"type\":\"refund\",\"client_id\":1,\"transaction_id\":3}\n\
+                    {\"type\":\"dispute\",\"client_id\":1,\"transaction_id\":1}\n";
+        let engine = Engine::new();
+        let mut log = RunLog::default();
+        process_json_lines_into(
+            &engine,
+            Cursor::new(json),
+            &InputOptions::default(),
+            &mut log,
+        )
+        .unwrap();
+        assert_eq!(
+            (log.stats.accepted, log.stats.rejected, log.stats.skipped),
+            (2, 1, 1)
+        );
+        assert_eq!(engine.get_account(&ClientId(1)).unwrap().held, dec!(10.0));
+
+        let err = process_json_lines_into(&Engine::new(), Cursor::new(json), &strict(), &mut log)
+            .unwrap_err();
+        assert_eq!(err.exit_code(), 8);
+        assert!(err.to_string().starts_with("line 2: "), "{err}");
+    }
+
+    #[test]
+    fn multiple_clients() {
+        let csv = "type,client,tx,amount\n\
+                   deposit,3,1,10.0\n\
+                   deposit,1,2,20.0\n\
+                   deposit,2,3,30.0\n";
+        let reader = Cursor::new(csv);
+
+        let engine = process_transactions(reader).unwrap();
+
+        assert_eq!(engine.accounts().len(), 3);
+
+        // Verify each client has correct balance
+        assert_eq!(
+            engine.get_account(&ClientId(1)).unwrap().available,
+            dec!(20.0)
+        );
+        assert_eq!(
+            engine.get_account(&ClientId(2)).unwrap().available,
+            dec!(30.0)
+        );
+        assert_eq!(
+            engine.get_account(&ClientId(3)).unwrap().available,
+            dec!(10.0)
+        );
+    }
+}
@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Payout file export for the accepted withdrawals of a run.
+//!
+//! The withdrawals are collected while processing (see
+//! [`RunLog`](crate::RunLog)) and written once all inputs are done, so a
+//! withdrawal that was rejected or later charged back never reaches the bank.
+
+use crate::failure::EXIT_IO;
+use clap::Args as ClapArgs;
+use ledger_demo_rs::{Beneficiaries, Payout, PayoutConfig, PayoutError, PayoutFile, PayoutFormat};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::process;
+
+/// Payout export options.
+#[derive(ClapArgs, Debug)]
+pub struct PayoutArgs {
+    /// Write the accepted withdrawals to PATH as a payout file
+    #[arg(long, value_name = "PATH", requires_all = ["beneficiaries", "payout_config"])]
+    pub payouts: Option<PathBuf>,
+
+    /// Payout file format: pain001 or nacha [default: by extension]
+    ///
+    /// .xml is SEPA pain.001; .ach and .nacha are NACHA.
+    #[arg(long, value_name = "FORMAT", requires = "payouts")]
+    pub payout_format: Option<PayoutFormat>,
+
+    /// CSV table of beneficiary bank details by client
+    ///
+    /// Columns: client, name and iban/bic (pain001) or routing_number,
+    /// account_number and account_type (nacha).
+    #[arg(long, value_name = "PATH", requires = "payouts")]
+    pub beneficiaries: Option<PathBuf>,
+
+    /// Payout settings file (.toml or .json): execution date and originator
+    #[arg(long, value_name = "PATH", requires = "payouts")]
+    pub payout_config: Option<PathBuf>,
+}
+
+/// Everything needed to write the payout file, loaded before processing so
+/// a bad setting fails fast.
+#[derive(Debug)]
+pub struct PayoutPlan {
+    path: PathBuf,
+    format: PayoutFormat,
+    config: PayoutConfig,
+    beneficiaries: Beneficiaries,
+}
+
+impl PayoutArgs {
+    /// Loads the payout plan if `--payouts` was given, exiting with
+    /// [`EXIT_IO`] if the format is unknown or a file cannot be loaded.
+    pub fn plan_or_exit(&self) -> Option<PayoutPlan> {
+        let path = self.payouts.clone()?;
+        let (Some(beneficiaries), Some(config)) = (&self.beneficiaries, &self.payout_config) else {
+            unreachable!("clap requires --beneficiaries and --payout-config with --payouts");
+        };
+        let Some(format) = self
+            .payout_format
+            .or_else(|| PayoutFormat::from_path(&path))
+        else {
+            eprintln!(
+                "Error: cannot tell the payout format of '{}'; pass --payout-format",
+                path.display()
+            );
+            process::exit(EXIT_IO);
+        };
+        let config = PayoutConfig::from_file(config).unwrap_or_else(|e| {
+            eprintln!("Error loading payout config '{}': {}", config.display(), e);
+            process::exit(EXIT_IO);
+        });
+        let beneficiaries = File::open(beneficiaries)
+            .map_err(PayoutError::from)
+            .and_then(Beneficiaries::from_csv)
+            .unwrap_or_else(|e| {
+                eprintln!(
+                    "Error loading beneficiaries '{}': {}",
+                    beneficiaries.display(),
+                    e
+                );
+                process::exit(EXIT_IO);
+            });
+        Some(PayoutPlan {
+            path,
+            format,
+            config,
+            beneficiaries,
+        })
+    }
+}
+
+impl PayoutPlan {
+    /// Validates and writes `payouts`, returning the file that was written,
+    /// or `None` if there was nothing to pay out.
+    ///
+    /// Nothing is written unless every payout validates.
+    pub fn write(&self, payouts: &[Payout]) -> Result<Option<PayoutFile>, PayoutError> {
+        let file = match PayoutFile::build(self.format, &self.config, &self.beneficiaries, payouts)
+        {
+            Ok(file) => file,
+            Err(PayoutError::Empty) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let mut writer = BufWriter::new(File::create(&self.path)?);
+        file.write(&mut writer)?;
+        writer.flush()?;
+        Ok(Some(file))
+    }
+
+    /// Like [`write`](Self::write), reporting the outcome on stderr and
+    /// exiting with [`EXIT_IO`] on failure.
+    pub fn write_or_exit(&self, payouts: &[Payout]) {
+        match self.write(payouts) {
+            Ok(Some(file)) => eprintln!(
+                "{} payout(s) totalling {} written to '{}' ({})",
+                file.len(),
+                file.control_sum(),
+                self.path.display(),
+                file.format()
+            ),
+            Ok(None) => eprintln!("No accepted withdrawals; no payout file written"),
+            Err(e) => {
+                eprintln!("Error writing payouts '{}': {}", self.path.display(), e);
+                process::exit(EXIT_IO);
+            }
+        }
+    }
+}
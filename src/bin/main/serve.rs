@@ -0,0 +1,95 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The `serve` subcommand: run the HTTP API.
+
+use crate::failure::EXIT_IO;
+use ledger_demo_rs::server::{self, ApiKeys, RateLimiter, RateLimits, ServerOptions};
+use ledger_demo_rs::{Engine, EngineConfig};
+use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+/// How often the rate limits file is checked for changes.
+const LIMITS_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Runs the `serve` subcommand until Ctrl-C, returning the process exit code.
+pub fn run_serve(
+    bind: SocketAddr,
+    keys: Option<&Path>,
+    limits_path: Option<&Path>,
+    config: EngineConfig,
+) -> i32 {
+    let keys = match keys.map(ApiKeys::from_file).transpose() {
+        Ok(keys) => keys,
+        Err(e) => {
+            eprintln!("Error loading API keys: {}", e);
+            return EXIT_IO;
+        }
+    };
+    let limits = match limits_path
+        .map(|path| RateLimiter::new(RateLimits::from_file(path)?))
+        .transpose()
+    {
+        Ok(limits) => limits.map(Arc::new),
+        Err(e) => {
+            eprintln!("Error loading rate limits: {}", e);
+            return EXIT_IO;
+        }
+    };
+    let runtime = match tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Error starting runtime: {}", e);
+            return EXIT_IO;
+        }
+    };
+
+    runtime.block_on(async {
+        let listener = match TcpListener::bind(bind).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                eprintln!("Error binding '{}': {}", bind, e);
+                return EXIT_IO;
+            }
+        };
+        // Report the actual address, which differs from `bind` for port 0.
+        if let Ok(addr) = listener.local_addr() {
+            println!("Listening on http://{addr}");
+            let _ = io::stdout().flush();
+        }
+
+        if let (Some(limiter), Some(path)) = (&limits, limits_path) {
+            tokio::spawn(Arc::clone(limiter).watch(path.to_path_buf(), LIMITS_POLL_INTERVAL));
+        }
+
+        let engine = Arc::new(Engine::builder().config(config).build());
+        match server::serve_with(listener, engine, ServerOptions { keys, limits }).await {
+            Ok(()) => 0,
+            Err(e) => {
+                eprintln!("Server error: {}", e);
+                EXIT_IO
+            }
+        }
+    })
+}
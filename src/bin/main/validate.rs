@@ -0,0 +1,305 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The `validate` subcommand: lint inputs without computing balances.
+//!
+//! Checks the header against the [`CsvSchema`] (including unmapped columns),
+//! field types, transaction types and amounts (against the
+//! configured [`AmountPolicy`]), duplicate transaction IDs, references to
+//! unknown or non-disputable transactions (withdrawals under
+//! [`DisputePolicy::Deposits`]) and client mismatches, classified as
+//! `--strict` does. Unlike `--strict`, every problem is collected rather than
+//! stopping at the first one.
+
+use crate::failure::{FailureClass, ProcessError, RowFailure};
+use crate::input::{line_of, parse_json_line, parse_row, resolve_schema};
+use ledger_demo_rs::{
+    ClientId, CsvSchema, DisputePolicy, DuplicateScope, EngineConfig, JsonLines, TransactionError,
+    TransactionId, TransactionType,
+};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::io::{self, BufReader, Read};
+
+/// Lint state shared across all inputs of one run.
+#[derive(Debug)]
+pub struct Validator {
+    config: EngineConfig,
+    schema: CsvSchema,
+    /// Clients that have used each deposit/withdrawal transaction ID, and
+    /// whether they used it for a deposit.
+    seen: HashMap<TransactionId, Vec<(ClientId, bool)>>,
+}
+
+impl Validator {
+    /// Creates a validator applying the policies in `config` to inputs laid
+    /// out as described by `schema`.
+    pub fn new(config: EngineConfig, schema: CsvSchema) -> Self {
+        Self {
+            config,
+            schema,
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Lints one input, returning every problem found in line order.
+    ///
+    /// Transaction IDs seen in earlier inputs count for duplicate and
+    /// reference checks, mirroring how the engine processes several inputs.
+    ///
+    /// # Errors
+    ///
+    /// Returns a CSV error only if the reader itself fails.
+    pub fn validate<R: Read>(&mut self, reader: R) -> Result<Vec<RowFailure>, csv::Error> {
+        let mut rdr = self.schema.reader_builder().from_reader(reader);
+
+        let resolved = match resolve_schema(&self.schema, &mut rdr, true) {
+            Ok(resolved) => resolved,
+            // Without the required columns no row can be interpreted.
+            Err(ProcessError::Row(failure)) => return Ok(vec![failure]),
+            Err(ProcessError::Csv(e)) => return Err(e),
+            Err(ProcessError::Io(e)) => return Err(e.into()),
+        };
+        let header_len = if self.schema.has_headers {
+            Some(rdr.headers()?.len())
+        } else {
+            None
+        };
+
+        let mut failures = Vec::new();
+        for result in rdr.records() {
+            let record = match result {
+                Ok(record) => record,
+                Err(e) if e.is_io_error() => return Err(e),
+                Err(e) => {
+                    failures.push(RowFailure {
+                        line: e.position().map_or(0, |p| p.line()),
+                        class: FailureClass::Schema,
+                        code: "MALFORMED_ROW",
+                        reason: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+
+            let line = line_of(&record);
+            let checked = parse_row(&record, &resolved, header_len).and_then(|tx| {
+                self.check(&tx)
+                    .map_err(|err| RowFailure::from_error(line, &err))
+            });
+            if let Err(failure) = checked {
+                failures.push(failure);
+            }
+        }
+
+        Ok(failures)
+    }
+
+    /// Lints one JSON Lines input, returning every problem found in line
+    /// order. State is shared with CSV inputs validated before it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an I/O error only if the reader itself fails.
+    pub fn validate_json_lines<R: Read>(&mut self, reader: R) -> io::Result<Vec<RowFailure>> {
+        let mut failures = Vec::new();
+        for json_line in JsonLines::new(BufReader::new(reader)) {
+            let checked = parse_json_line(json_line?).and_then(|(line, tx)| {
+                self.check(&tx)
+                    .map_err(|err| RowFailure::from_error(line, &err))
+            });
+            if let Err(failure) = checked {
+                failures.push(failure);
+            }
+        }
+        Ok(failures)
+    }
+
+    /// Checks a single transaction against the amount policy and the
+    /// transaction IDs seen so far, recording it if it introduces a new ID.
+    fn check(&mut self, tx: &TransactionType) -> Result<(), TransactionError> {
+        let client_id = tx.client_id();
+        match tx {
+            TransactionType::Deposit { amount, .. }
+            | TransactionType::Withdrawal { amount, .. } => {
+                if *amount <= Decimal::ZERO {
+                    return Err(TransactionError::InvalidAmount);
+                }
+                self.config.amounts.apply(*amount)?;
+
+                let clients = self.seen.entry(tx.id()).or_default();
+                let duplicate = match self.config.duplicates {
+                    DuplicateScope::Global => !clients.is_empty(),
+                    DuplicateScope::PerClient => clients.iter().any(|&(c, _)| c == client_id),
+                };
+                if duplicate {
+                    return Err(TransactionError::DuplicateTransaction);
+                }
+                let deposit = matches!(tx, TransactionType::Deposit { .. });
+                clients.push((client_id, deposit));
+            }
+            TransactionType::Dispute { .. }
+            | TransactionType::Resolve { .. }
+            | TransactionType::Chargeback { .. } => {
+                let clients = self
+                    .seen
+                    .get(&tx.id())
+                    .ok_or(TransactionError::TransactionNotFound)?;
+                let Some(&(_, deposit)) = clients.iter().find(|&&(c, _)| c == client_id) else {
+                    return Err(TransactionError::ClientMismatch);
+                };
+                if !deposit && self.config.disputable == DisputePolicy::Deposits {
+                    return Err(TransactionError::TransactionNotFound);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ledger_demo_rs::{AmountPolicy, PrecisionMode};
+
+    fn classes(validator: &mut Validator, csv: &str) -> Vec<(u64, FailureClass)> {
+        validator
+            .validate(csv.as_bytes())
+            .unwrap()
+            .into_iter()
+            .map(|f| (f.line, f.class))
+            .collect()
+    }
+
+    #[test]
+    fn clean_input_has_no_failures() {
+        let mut validator = Validator::new(EngineConfig::default(), CsvSchema::default());
+        let csv = "type,client,tx,amount\n\
+                   deposit,1,1,10.0\n\
+                   withdrawal,1,2,5.0\n\
+                   dispute,1,1,\n\
+                   resolve,1,1,\n";
+        assert!(classes(&mut validator, csv).is_empty());
+    }
+
+    #[test]
+    fn reports_every_failure_class() {
+        let mut validator = Validator::new(EngineConfig::default(), CsvSchema::default());
+        let csv = "type,client,tx,amount\n\
+                   deposit,1,1,10.0\n\
+                   deposit,2,1,10.0\n\
+                   dispute,1,9,\n\
+                   dispute,2,1,\n\
+                   deposit,1,2,-1\n\
+                   deposit,one,3,1.0\n\
+                   deposit,1,4,1.0,extra\n";
+        assert_eq!(
+            classes(&mut validator, csv),
+            vec![
+                (3, FailureClass::DuplicateId),
+                (4, FailureClass::UnknownReference),
+                (5, FailureClass::ClientMismatch),
+                (6, FailureClass::InvalidRow),
+                (7, FailureClass::InvalidRow),
+                (8, FailureClass::Schema),
+            ]
+        );
+    }
+
+    #[test]
+    fn json_lines_share_state_with_csv() {
+        let mut validator = Validator::new(EngineConfig::default(), CsvSchema::default());
+        assert!(classes(&mut validator, "type,client,tx,amount\ndeposit,1,1,1.0\n").is_empty());
+
+        let json = "{\"type\":\"dispute\",\"client_id\":1,\"transaction_id\":1}\n\
+                    {\"type\":\"deposit\",\"client_id\":2,\"transaction_id\":1,\"amount\":\"1\"}\n\
+                    {\"type\":\"resolve\",\"client_id\":1}\n";
+        let failures: Vec<_> = validator
+            .validate_json_lines(json.as_bytes())
+            .unwrap()
+            .into_iter()
+            .map(|f| (f.line, f.class))
+            .collect();
+        assert_eq!(
+            failures,
+            vec![
+                (2, FailureClass::DuplicateId),
+                (3, FailureClass::InvalidRow)
+            ]
+        );
+    }
+
+    #[test]
+    fn state_spans_inputs_and_honors_policies() {
+        let mut validator = Validator::new(
+            EngineConfig {
+                amounts: AmountPolicy::new(2, PrecisionMode::Reject),
+                duplicates: DuplicateScope::PerClient,
+                ..EngineConfig::default()
+            },
+            CsvSchema::default(),
+        );
+        assert!(classes(&mut validator, "type,client,tx,amount\ndeposit,1,1,1.0\n").is_empty());
+        assert_eq!(
+            classes(
+                &mut validator,
+                "type,client,tx,amount\n\
+                 deposit,2,1,1.0\n\
+                 deposit,1,1,1.0\n\
+                 deposit,1,2,1.001\n\
+                 chargeback,1,1,\n"
+            ),
+            vec![
+                (3, FailureClass::DuplicateId),
+                (4, FailureClass::InvalidRow)
+            ]
+        );
+    }
+
+    #[test]
+    fn withdrawal_disputes_follow_the_dispute_policy() {
+        let csv = "type,client,tx,amount\n\
+                   deposit,1,1,10.0\n\
+                   withdrawal,1,2,5.0\n\
+                   dispute,1,2,\n\
+                   chargeback,1,2,\n";
+        let mut validator = Validator::new(EngineConfig::default(), CsvSchema::default());
+        assert_eq!(
+            classes(&mut validator, csv),
+            vec![
+                (4, FailureClass::UnknownReference),
+                (5, FailureClass::UnknownReference)
+            ]
+        );
+
+        let config = EngineConfig {
+            disputable: DisputePolicy::DepositsAndWithdrawals,
+            ..EngineConfig::default()
+        };
+        let mut validator = Validator::new(config, CsvSchema::default());
+        assert!(classes(&mut validator, csv).is_empty());
+    }
+
+    #[test]
+    fn missing_columns_stop_validation() {
+        let mut validator = Validator::new(EngineConfig::default(), CsvSchema::default());
+        assert_eq!(
+            classes(&mut validator, "kind,client,tx\ndeposit,1,1\n"),
+            vec![(1, FailureClass::Schema)]
+        );
+    }
+}
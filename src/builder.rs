@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Fluent construction of a configured [`Engine`].
+//!
+//! # Example
+//!
+//! ```
+//! use ledger_demo_rs::{ChargebackPolicy, DisputePolicy, Engine};
+//!
+//! let engine = Engine::builder()
+//!     .chargeback_policy(ChargebackPolicy::KeepOpen)
+//!     .dispute_policy(DisputePolicy::DepositsAndWithdrawals)
+//!     .build();
+//! assert_eq!(engine.config().chargeback, ChargebackPolicy::KeepOpen);
+//! ```
+
+use crate::config::{AmountPolicy, ChargebackPolicy, DisputePolicy, DuplicateScope, EngineConfig};
+use crate::engine::Engine;
+
+/// Builder for an [`Engine`] with non-default policies.
+///
+/// Starts from [`EngineConfig::default`]; each setter overrides one policy.
+#[derive(Debug, Clone, Default)]
+pub struct EngineBuilder {
+    config: EngineConfig,
+}
+
+impl EngineBuilder {
+    /// Creates a builder with the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the whole configuration, e.g. one loaded with
+    /// [`EngineConfig::from_file`].
+    pub fn config(mut self, config: EngineConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Sets the input amount policy.
+    pub fn amount_policy(mut self, policy: AmountPolicy) -> Self {
+        self.config.amounts = policy;
+        self
+    }
+
+    /// Sets what a chargeback does to the account.
+    pub fn chargeback_policy(mut self, policy: ChargebackPolicy) -> Self {
+        self.config.chargeback = policy;
+        self
+    }
+
+    /// Sets which transaction kinds can be disputed.
+    pub fn dispute_policy(mut self, policy: DisputePolicy) -> Self {
+        self.config.disputable = policy;
+        self
+    }
+
+    /// Sets the scope in which transaction IDs must be unique.
+    pub fn duplicate_scope(mut self, scope: DuplicateScope) -> Self {
+        self.config.duplicates = scope;
+        self
+    }
+
+    /// Builds the engine.
+    pub fn build(self) -> Engine {
+        Engine::with_config(self.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PrecisionMode, RoundingMode};
+
+    #[test]
+    fn defaults_match_engine_config() {
+        let engine = EngineBuilder::new().build();
+        assert_eq!(engine.config(), &EngineConfig::default());
+    }
+
+    #[test]
+    fn setters_override_individual_policies() {
+        let amounts = AmountPolicy::new(2, PrecisionMode::Round(RoundingMode::HalfUp));
+        let engine = EngineBuilder::new()
+            .amount_policy(amounts)
+            .chargeback_policy(ChargebackPolicy::KeepOpen)
+            .dispute_policy(DisputePolicy::DepositsAndWithdrawals)
+            .duplicate_scope(DuplicateScope::PerClient)
+            .build();
+
+        let config = engine.config();
+        assert_eq!(config.amounts, amounts);
+        assert_eq!(config.chargeback, ChargebackPolicy::KeepOpen);
+        assert_eq!(config.disputable, DisputePolicy::DepositsAndWithdrawals);
+        assert_eq!(config.duplicates, DuplicateScope::PerClient);
+    }
+}
@@ -0,0 +1,529 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Engine configuration.
+//!
+//! [`EngineConfig`] collects every engine policy as a typed option. It can be
+//! built in code (usually through [`EngineBuilder`](crate::EngineBuilder)) or
+//! loaded from a TOML or JSON file with [`EngineConfig::from_file`]. Unknown
+//! keys are rejected, and [`EngineConfig::to_toml`] renders the effective
+//! configuration for audit logs.
+//!
+//! ```toml
+//! chargeback = "lock-account"   # or "keep-open"
+//! disputable = "deposits"       # or "deposits-and-withdrawals"
+//! duplicates = "global"         # or "per-client"
+//!
+//! [amounts]
+//! scale = 4
+//! precision = "bankers"         # or "half-up", "truncate", "reject"
+//! ```
+//!
+//! [`AmountPolicy`] controls how many decimal places an input amount may carry
+//! and what happens to amounts that exceed it. The same policy is applied by
+//! every ingestion path (CSV records, server DTOs and [`Engine::process`]), so
+//! balances never accumulate sub-precision dust.
+//!
+//! # Example
+//!
+//! ```
+//! use ledger_demo_rs::{AmountPolicy, PrecisionMode, RoundingMode, TransactionError};
+//! use rust_decimal_macros::dec;
+//!
+//! let policy = AmountPolicy::new(2, PrecisionMode::Round(RoundingMode::HalfUp));
+//! assert_eq!(policy.apply(dec!(1.005)), Ok(dec!(1.01)));
+//!
+//! let strict = AmountPolicy::new(2, PrecisionMode::Reject);
+//! assert_eq!(strict.apply(dec!(1.005)), Err(TransactionError::ExcessPrecision));
+//! ```
+//!
+//! [`Engine::process`]: crate::Engine::process
+
+use crate::error::ConfigError;
+use crate::{Account, TransactionError, TransactionType};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Named rounding strategy for amounts with excess precision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RoundingMode {
+    /// Round half to even (banker's rounding). Matches output rounding.
+    #[default]
+    Bankers,
+    /// Round half away from zero.
+    HalfUp,
+    /// Drop excess digits (round toward zero).
+    Truncate,
+}
+
+impl From<RoundingMode> for RoundingStrategy {
+    fn from(mode: RoundingMode) -> Self {
+        match mode {
+            RoundingMode::Bankers => RoundingStrategy::MidpointNearestEven,
+            RoundingMode::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+            RoundingMode::Truncate => RoundingStrategy::ToZero,
+        }
+    }
+}
+
+/// What to do with an amount that has more decimal places than allowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrecisionMode {
+    /// Reject the transaction with [`TransactionError::ExcessPrecision`].
+    Reject,
+    /// Round the amount to the allowed scale.
+    Round(RoundingMode),
+}
+
+impl Default for PrecisionMode {
+    /// Banker's rounding, or [`Reject`](Self::Reject) with the `fixed-point`
+    /// feature, which promises never to round input amounts.
+    fn default() -> Self {
+        if cfg!(feature = "fixed-point") {
+            Self::Reject
+        } else {
+            Self::Round(RoundingMode::default())
+        }
+    }
+}
+
+impl FromStr for PrecisionMode {
+    type Err = String;
+
+    /// Parses `reject`, `bankers`, `half-up` or `truncate`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "reject" => Ok(Self::Reject),
+            "bankers" => Ok(Self::Round(RoundingMode::Bankers)),
+            "half-up" => Ok(Self::Round(RoundingMode::HalfUp)),
+            "truncate" => Ok(Self::Round(RoundingMode::Truncate)),
+            other => Err(format!(
+                "unknown precision mode '{other}' (expected reject, bankers, half-up or truncate)"
+            )),
+        }
+    }
+}
+
+impl Serialize for PrecisionMode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for PrecisionMode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl fmt::Display for PrecisionMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Reject => "reject",
+            Self::Round(RoundingMode::Bankers) => "bankers",
+            Self::Round(RoundingMode::HalfUp) => "half-up",
+            Self::Round(RoundingMode::Truncate) => "truncate",
+        })
+    }
+}
+
+/// Allowed scale for input amounts and how excess precision is handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "AmountPolicyRepr", into = "AmountPolicyRepr")]
+pub struct AmountPolicy {
+    scale: u32,
+    mode: PrecisionMode,
+}
+
+impl AmountPolicy {
+    /// Largest allowed scale, equal to the precision accounts are reported at.
+    pub const MAX_SCALE: u32 = Account::DECIMAL_PRECISION;
+
+    /// Creates a policy allowing `scale` decimal places.
+    ///
+    /// Use [`try_new`](Self::try_new) for a scale that is not known to be valid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `scale` exceeds [`MAX_SCALE`](Self::MAX_SCALE).
+    pub fn new(scale: u32, mode: PrecisionMode) -> Self {
+        Self::try_new(scale, mode).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Creates a policy allowing `scale` decimal places.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Invalid`] if `scale` exceeds
+    /// [`MAX_SCALE`](Self::MAX_SCALE).
+    pub fn try_new(scale: u32, mode: PrecisionMode) -> Result<Self, ConfigError> {
+        if scale > Self::MAX_SCALE {
+            return Err(ConfigError::Invalid(format!(
+                "amount scale {scale} exceeds maximum of {}",
+                Self::MAX_SCALE
+            )));
+        }
+        Ok(Self { scale, mode })
+    }
+
+    /// Number of decimal places an amount may carry.
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// Handling of amounts with more decimal places than [`scale`](Self::scale).
+    pub fn mode(&self) -> PrecisionMode {
+        self.mode
+    }
+
+    /// Applies the policy to a single amount.
+    ///
+    /// Trailing zeros never count as excess precision, so `1.50000` is accepted
+    /// at a scale of 2.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransactionError::ExcessPrecision`] if the mode is
+    /// [`PrecisionMode::Reject`] and the amount has too many significant digits.
+    pub fn apply(&self, amount: Decimal) -> Result<Decimal, TransactionError> {
+        if amount.scale() <= self.scale {
+            return Ok(amount);
+        }
+        match self.mode {
+            PrecisionMode::Reject => {
+                let normalized = amount.normalize();
+                if normalized.scale() <= self.scale {
+                    Ok(normalized)
+                } else {
+                    Err(TransactionError::ExcessPrecision)
+                }
+            }
+            PrecisionMode::Round(mode) => {
+                Ok(amount.round_dp_with_strategy(self.scale, mode.into()))
+            }
+        }
+    }
+
+    /// Applies the policy to the amount of a deposit or withdrawal.
+    ///
+    /// Dispute, resolve and chargeback transactions carry no amount and are
+    /// returned unchanged.
+    pub fn apply_to(
+        &self,
+        transaction: TransactionType,
+    ) -> Result<TransactionType, TransactionError> {
+        Ok(match transaction {
+            TransactionType::Deposit {
+                client_id,
+                transaction_id,
+                amount,
+            } => TransactionType::Deposit {
+                client_id,
+                transaction_id,
+                amount: self.apply(amount)?,
+            },
+            TransactionType::Withdrawal {
+                client_id,
+                transaction_id,
+                amount,
+            } => TransactionType::Withdrawal {
+                client_id,
+                transaction_id,
+                amount: self.apply(amount)?,
+            },
+            other => other,
+        })
+    }
+}
+
+impl Default for AmountPolicy {
+    /// Four decimal places, matching account output, with the default
+    /// [`PrecisionMode`].
+    fn default() -> Self {
+        Self::new(Self::MAX_SCALE, PrecisionMode::default())
+    }
+}
+
+/// Serialized form of [`AmountPolicy`], validated on conversion.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+struct AmountPolicyRepr {
+    scale: u32,
+    precision: PrecisionMode,
+}
+
+impl Default for AmountPolicyRepr {
+    fn default() -> Self {
+        AmountPolicy::default().into()
+    }
+}
+
+impl From<AmountPolicy> for AmountPolicyRepr {
+    fn from(policy: AmountPolicy) -> Self {
+        Self {
+            scale: policy.scale,
+            precision: policy.mode,
+        }
+    }
+}
+
+impl TryFrom<AmountPolicyRepr> for AmountPolicy {
+    type Error = String;
+
+    fn try_from(repr: AmountPolicyRepr) -> Result<Self, Self::Error> {
+        Self::try_new(repr.scale, repr.precision).map_err(|e| match e {
+            ConfigError::Invalid(reason) => reason,
+            other => other.to_string(),
+        })
+    }
+}
+
+/// Effect of a chargeback on the client account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChargebackPolicy {
+    /// Permanently lock the account.
+    #[default]
+    LockAccount,
+    /// Remove the disputed funds but leave the account open.
+    KeepOpen,
+}
+
+/// Which transactions may be disputed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DisputePolicy {
+    /// Only deposits can be disputed.
+    #[default]
+    Deposits,
+    /// Deposits and withdrawals can be disputed.
+    ///
+    /// Disputing a withdrawal holds the withdrawn amount pending investigation
+    /// (total rises, available is unchanged). A resolve drops the hold and the
+    /// withdrawal stands; a chargeback reverses it, moving the held amount back
+    /// to available.
+    DepositsAndWithdrawals,
+}
+
+/// Scope in which transaction IDs must be unique.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DuplicateScope {
+    /// Transaction IDs are unique across all clients.
+    #[default]
+    Global,
+    /// Transaction IDs are unique per client; different clients may reuse them.
+    PerClient,
+}
+
+/// Configuration for an [`Engine`](crate::Engine).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct EngineConfig {
+    /// Scale and rounding applied to every incoming amount.
+    pub amounts: AmountPolicy,
+    /// Effect of a chargeback on the account.
+    pub chargeback: ChargebackPolicy,
+    /// Which transactions may be disputed.
+    pub disputable: DisputePolicy,
+    /// Scope of transaction ID uniqueness.
+    pub duplicates: DuplicateScope,
+}
+
+impl EngineConfig {
+    /// Loads a configuration file, choosing the format by extension
+    /// (`.toml` or `.json`).
+    ///
+    /// Keys that are not recognized are rejected. Missing keys take their
+    /// default values.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ConfigError`] if the file cannot be read, has an unsupported
+    /// extension, or does not describe a valid configuration.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        load_file(path.as_ref())
+    }
+
+    /// Parses a TOML configuration.
+    pub fn from_toml_str(contents: &str) -> Result<Self, ConfigError> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    /// Parses a JSON configuration.
+    pub fn from_json_str(contents: &str) -> Result<Self, ConfigError> {
+        Ok(serde_json::from_str(contents)?)
+    }
+
+    /// Renders the full effective configuration as TOML.
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("engine config is always representable as TOML")
+    }
+}
+
+/// Reads and deserializes a `.toml` or `.json` file, choosing the format by
+/// extension.
+pub(crate) fn load_file<T: DeserializeOwned>(path: &Path) -> Result<T, ConfigError> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let contents = std::fs::read_to_string(path)?;
+
+    match extension.as_str() {
+        "toml" => Ok(toml::from_str(&contents)?),
+        "json" => Ok(serde_json::from_str(&contents)?),
+        _ => Err(ConfigError::UnsupportedFormat(path.display().to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn amounts_within_scale_are_unchanged() {
+        let policy = AmountPolicy::new(2, PrecisionMode::Reject);
+        assert_eq!(policy.apply(dec!(1.25)), Ok(dec!(1.25)));
+        assert_eq!(policy.apply(dec!(7)), Ok(dec!(7)));
+    }
+
+    #[test]
+    fn reject_ignores_trailing_zeros() {
+        let policy = AmountPolicy::new(2, PrecisionMode::Reject);
+        assert_eq!(policy.apply(dec!(1.50000)), Ok(dec!(1.5)));
+        assert_eq!(
+            policy.apply(dec!(1.501)),
+            Err(TransactionError::ExcessPrecision)
+        );
+    }
+
+    #[test]
+    fn rounding_modes() {
+        let round = |mode, amount| {
+            AmountPolicy::new(4, PrecisionMode::Round(mode))
+                .apply(amount)
+                .unwrap()
+        };
+        assert_eq!(round(RoundingMode::Bankers, dec!(0.00005)), dec!(0.0000));
+        assert_eq!(round(RoundingMode::Bankers, dec!(0.00015)), dec!(0.0002));
+        assert_eq!(round(RoundingMode::HalfUp, dec!(0.00005)), dec!(0.0001));
+        assert_eq!(round(RoundingMode::Truncate, dec!(0.00019)), dec!(0.0001));
+    }
+
+    #[test]
+    fn default_policy_matches_output_precision() {
+        let policy = AmountPolicy::default();
+        assert_eq!(policy.scale(), 4);
+        let mode = if cfg!(feature = "fixed-point") {
+            PrecisionMode::Reject
+        } else {
+            PrecisionMode::Round(RoundingMode::Bankers)
+        };
+        assert_eq!(policy.mode(), mode);
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds maximum")]
+    fn scale_above_output_precision_panics() {
+        AmountPolicy::new(5, PrecisionMode::Reject);
+    }
+
+    #[test]
+    fn try_new_rejects_scale_above_output_precision() {
+        let error = AmountPolicy::try_new(5, PrecisionMode::Reject).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid config: amount scale 5 exceeds maximum of 4"
+        );
+        assert_eq!(
+            AmountPolicy::try_new(4, PrecisionMode::Reject)
+                .map(|p| p.scale())
+                .ok(),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn precision_mode_round_trips_through_strings() {
+        for text in ["reject", "bankers", "half-up", "truncate"] {
+            let mode: PrecisionMode = text.parse().unwrap();
+            assert_eq!(mode.to_string(), text);
+        }
+        assert!("nearest".parse::<PrecisionMode>().is_err());
+    }
+
+    #[test]
+    fn config_file_round_trips_through_toml() {
+        let config = EngineConfig {
+            amounts: AmountPolicy::new(2, PrecisionMode::Reject),
+            chargeback: ChargebackPolicy::KeepOpen,
+            disputable: DisputePolicy::DepositsAndWithdrawals,
+            duplicates: DuplicateScope::PerClient,
+        };
+        let rendered = config.to_toml();
+        assert_eq!(EngineConfig::from_toml_str(&rendered).unwrap(), config);
+    }
+
+    #[test]
+    fn config_missing_keys_use_defaults() {
+        let config = EngineConfig::from_toml_str("chargeback = \"keep-open\"\n").unwrap();
+        assert_eq!(config.chargeback, ChargebackPolicy::KeepOpen);
+        assert_eq!(config.amounts, AmountPolicy::default());
+        assert_eq!(config.duplicates, DuplicateScope::Global);
+
+        let config = EngineConfig::from_json_str(r#"{"amounts": {"scale": 2}}"#).unwrap();
+        assert_eq!(
+            config.amounts,
+            AmountPolicy::new(2, PrecisionMode::default())
+        );
+    }
+
+    #[test]
+    fn config_rejects_unknown_keys() {
+        assert!(EngineConfig::from_toml_str("lock_on_chargeback = false\n").is_err());
+        assert!(EngineConfig::from_toml_str("[amounts]\nrounding = \"bankers\"\n").is_err());
+        assert!(EngineConfig::from_json_str(r#"{"duplicate": "global"}"#).is_err());
+    }
+
+    #[test]
+    fn config_rejects_invalid_values() {
+        assert!(EngineConfig::from_toml_str("[amounts]\nscale = 5\n").is_err());
+        assert!(EngineConfig::from_toml_str("disputable = \"everything\"\n").is_err());
+        assert!(EngineConfig::from_json_str(r#"{"amounts": {"precision": "up"}}"#).is_err());
+    }
+
+    #[test]
+    fn apply_to_leaves_dispute_operations_untouched() {
+        use crate::{ClientId, TransactionId};
+
+        let policy = AmountPolicy::new(0, PrecisionMode::Reject);
+        let dispute = TransactionType::Dispute {
+            client_id: ClientId(1),
+            transaction_id: TransactionId(1),
+        };
+        assert_eq!(policy.apply_to(dispute), Ok(dispute));
+    }
+}
@@ -0,0 +1,138 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Calendar helpers for `YYYY-MM-DD` dates in exported files.
+//!
+//! Conversions use the proleptic Gregorian calendar and Howard Hinnant's
+//! days-from-civil algorithms; only dates from 1970 on are supported.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Returns `true` for a valid `YYYY-MM-DD` date.
+pub(crate) fn is_date(s: &str) -> bool {
+    parse_date(s).is_some()
+}
+
+/// Returns `true` for an `HH:MM:SS` time.
+pub(crate) fn is_time(s: &str) -> bool {
+    let parts: Vec<Option<u32>> = s.split(':').map(|p| p.parse().ok()).collect();
+    matches!(
+        parts.as_slice(),
+        [Some(h), Some(m), Some(sec)] if *h < 24 && *m < 60 && *sec < 60 && s.len() == 8
+    )
+}
+
+/// Formats the current UTC time as `YYYY-MM-DDTHH:MM:SS`.
+pub(crate) fn utc_now() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    format!(
+        "{}T{:02}:{:02}:{:02}",
+        format_date(days),
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60
+    )
+}
+
+/// Returns today's UTC date as `YYYY-MM-DD`.
+pub(crate) fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    format_date(secs / 86_400)
+}
+
+/// Returns the day after a `YYYY-MM-DD` date, or `None` if it is invalid.
+pub(crate) fn next_day(date: &str) -> Option<String> {
+    parse_date(date).map(|days| format_date(days + 1))
+}
+
+/// Parses a `YYYY-MM-DD` date into days since 1970-01-01.
+fn parse_date(s: &str) -> Option<u64> {
+    let (year, rest) = s.split_once('-')?;
+    let (month, day) = rest.split_once('-')?;
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return None;
+    }
+    let digits = |p: &str| p.bytes().all(|b| b.is_ascii_digit());
+    if !(digits(year) && digits(month) && digits(day)) {
+        return None;
+    }
+    let (year, month, day): (u64, u64, u64) =
+        (year.parse().ok()?, month.parse().ok()?, day.parse().ok()?);
+    if year < 1970 || !(1..=12).contains(&month) || day == 0 {
+        return None;
+    }
+
+    // Days-from-civil.
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y / 400;
+    let yoe = y % 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = (era * 146_097 + doe).checked_sub(719_468)?;
+
+    // Reject days past the end of the month, e.g. 2025-02-30.
+    (format_date(days) == s).then_some(days)
+}
+
+/// Formats days since 1970-01-01 as `YYYY-MM-DD` (civil-from-days).
+fn format_date(days: u64) -> String {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_dates_and_times() {
+        assert!(is_date("2025-01-06"));
+        assert!(is_date("2024-02-29"));
+        assert!(!is_date("2025-02-29"));
+        assert!(!is_date("2025-13-01"));
+        assert!(!is_date("06.01.2025"));
+        assert!(is_time("23:59:00"));
+        assert!(!is_time("24:00:00"));
+
+        let now = utc_now();
+        let (date, time) = now.split_once('T').unwrap();
+        assert!(is_date(date) && is_time(time), "{now}");
+    }
+
+    #[test]
+    fn next_day_rolls_over_months_and_years() {
+        assert_eq!(next_day("2025-03-07").as_deref(), Some("2025-03-08"));
+        assert_eq!(next_day("2024-02-28").as_deref(), Some("2024-02-29"));
+        assert_eq!(next_day("2025-02-28").as_deref(), Some("2025-03-01"));
+        assert_eq!(next_day("2025-12-31").as_deref(), Some("2026-01-01"));
+        assert_eq!(next_day("2025-04-31"), None);
+    }
+}
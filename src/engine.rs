@@ -31,14 +31,31 @@
 //!
 //! # Thread Safety
 //!
-//! The engine uses [`DashMap`] for concurrent access to accounts, allowing
-//! multiple transactions to be processed in parallel for different clients.
+//! Accounts live in a dense table indexed by client ID, so each operation takes
+//! exactly one lock (the account's own mutex). Transactions for different clients
+//! are processed in parallel; transactions for the same client are serialized.
+//!
+//! # Tracing
+//!
+//! Every [`Engine::process`] call runs in an `INFO` `process` span with the
+//! `client`, `tx` and `type` of the transaction. Rejections are `INFO` events
+//! with `outcome = "rejected"` and the `error` code, applied transactions
+//! `DEBUG` events, and the balances afterwards `TRACE` events. Nothing is
+//! recorded unless a `tracing` subscriber enables those levels.
 
-use crate::account::{Account, AccountSnapshot};
-use crate::base::ClientId;
+use crate::account::{AccountSnapshot, StagedAccount};
+use crate::account_table::AccountTable;
+use crate::base::{ClientId, TransactionId};
+use crate::batch::{BatchMode, BatchReport};
+use crate::builder::EngineBuilder;
+use crate::config::EngineConfig;
+use crate::history::{HistoryPage, HistoryQuery, StoredTransaction};
+use crate::metrics::EngineMetrics;
+use crate::transaction_queue::DedupKey;
 use crate::{TransactionError, TransactionQueue, TransactionType};
-use dashmap::DashMap;
+use parking_lot::Mutex;
 use std::sync::Arc;
+use std::time::Instant;
 
 /// Transaction processing engine that manages client accounts.
 ///
@@ -48,26 +65,55 @@ use std::sync::Arc;
 ///
 /// # Invariants
 ///
-/// - Transaction IDs are globally unique across all transaction types.
-/// - Only deposits can be disputed (withdrawals cannot).
+/// - Transaction IDs are unique across all transaction types (globally, or per
+///   client under [`DuplicateScope::PerClient`](crate::DuplicateScope::PerClient)).
+/// - Only deposits can be disputed unless
+///   [`DisputePolicy::DepositsAndWithdrawals`](crate::DisputePolicy::DepositsAndWithdrawals)
+///   is configured.
 /// - Disputes can only transition: `Applied` -> `Inflight` -> `Resolved` or `Voided`.
-/// - A chargeback permanently locks the client account.
+/// - A chargeback permanently locks the client account unless
+///   [`ChargebackPolicy::KeepOpen`](crate::ChargebackPolicy::KeepOpen) is configured.
 pub struct Engine {
     /// Client accounts indexed by client ID.
-    accounts: DashMap<ClientId, Account>,
+    accounts: AccountTable,
     /// Global transaction log for deduplication.
     transactions: TransactionQueue,
+    /// Ingestion policies.
+    config: EngineConfig,
+    /// Serializes atomic batches, the only operations that lock several
+    /// accounts at once.
+    batch_lock: Mutex<()>,
+    /// Transaction counters and `process` latency.
+    metrics: EngineMetrics,
 }
 
 impl Engine {
     /// Creates a new engine with no accounts or transactions.
     pub fn new() -> Self {
+        Self::with_config(EngineConfig::default())
+    }
+
+    /// Returns an [`EngineBuilder`] for configuring policies individually.
+    pub fn builder() -> EngineBuilder {
+        EngineBuilder::new()
+    }
+
+    /// Creates a new engine using the given configuration.
+    pub fn with_config(config: EngineConfig) -> Self {
         Engine {
-            accounts: DashMap::new(),
-            transactions: TransactionQueue::new(),
+            accounts: AccountTable::new(),
+            transactions: TransactionQueue::with_scope(config.duplicates),
+            config,
+            batch_lock: Mutex::new(()),
+            metrics: EngineMetrics::new(),
         }
     }
 
+    /// Returns the engine's configuration.
+    pub fn config(&self) -> &EngineConfig {
+        &self.config
+    }
+
     /// Processes a transaction, updating the appropriate client account.
     ///
     /// # Transaction Types
@@ -80,6 +126,10 @@ impl Engine {
     /// | Resolve | Releases held funds back to available |
     /// | Chargeback | Removes held funds, locks account |
     ///
+    /// Deposit and withdrawal amounts are first normalized by the configured
+    /// [`AmountPolicy`](crate::AmountPolicy); a rejected amount does not consume
+    /// the transaction ID.
+    ///
     /// # Errors
     ///
     /// - [`TransactionError::DuplicateTransaction`] - Transaction ID already exists.
@@ -88,41 +138,217 @@ impl Engine {
     /// - [`TransactionError::AlreadyDisputed`] - Deposit is already under dispute.
     /// - [`TransactionError::NotDisputed`] - Resolve/chargeback on non-disputed deposit.
     /// - [`TransactionError::AccountLocked`] - Account is frozen after chargeback.
+    /// - [`TransactionError::Overflow`] - Balance arithmetic would overflow.
+    /// - [`TransactionError::ExcessPrecision`] - Amount exceeds the allowed scale.
     pub fn process(&self, transaction: TransactionType) -> Result<(), TransactionError> {
+        self.run(transaction, None)
+    }
+
+    /// Processes a transaction only if the client's account is at
+    /// `expected_version`; see [`Account::version`](crate::Account::version).
+    ///
+    /// A client without an account is at version 0. The check and the
+    /// transaction happen under the account lock, so no other transaction can
+    /// slip in between them.
+    ///
+    /// # Errors
+    ///
+    /// [`TransactionError::VersionConflict`] if the account is at a different
+    /// version, in which case nothing is applied and the transaction ID is not
+    /// consumed; otherwise as for [`process`](Self::process).
+    pub fn process_expecting(
+        &self,
+        transaction: TransactionType,
+        expected_version: u64,
+    ) -> Result<(), TransactionError> {
+        self.run(transaction, Some(expected_version))
+    }
+
+    /// Times, records and traces a transaction for [`process`](Self::process)
+    /// and [`process_expecting`](Self::process_expecting).
+    fn run(
+        &self,
+        transaction: TransactionType,
+        expected_version: Option<u64>,
+    ) -> Result<(), TransactionError> {
+        let _span = transaction_span(&transaction).entered();
+        let started = Instant::now();
+        let result = self.apply(transaction, expected_version);
+        self.metrics.observe_latency(started.elapsed());
+        self.metrics.record(&transaction, &result);
+        trace_outcome(&result);
+        result
+    }
+
+    /// Applies a transaction for [`run`](Self::run).
+    fn apply(
+        &self,
+        transaction: TransactionType,
+        expected_version: Option<u64>,
+    ) -> Result<(), TransactionError> {
+        let transaction = self.config.amounts.apply_to(transaction)?;
         let client_id = transaction.client_id();
 
         match &transaction {
             TransactionType::Deposit { .. } | TransactionType::Withdrawal { .. } => {
-                // Store in transaction log first to validate unique tx_id.
-                // This prevents duplicate transactions from being processed.
-                let transaction_arc = Arc::new(transaction);
-                self.transactions.push(Arc::clone(&transaction_arc))?;
+                // Claim the tx_id first so duplicates are rejected, but only
+                // keep it once the version check has passed.
+                let key = self.transactions.reserve(Arc::new(transaction))?;
 
                 // Get existing account or create new one, then process the transaction.
-                // New accounts start with zero balance.
-                let mut account = self
-                    .accounts
-                    .entry(client_id)
-                    .or_insert_with(|| Account::new(client_id));
-                account.add_transaction(*transaction_arc)?;
+                // New accounts start with zero balance, at version 0, so a
+                // conflicting transaction must not create one.
+                let result = match (self.accounts.get(client_id), expected_version) {
+                    (None, Some(version)) if version != 0 => Err(TransactionError::VersionConflict),
+                    (account, _) => account
+                        .unwrap_or_else(|| self.accounts.get_or_create(client_id))
+                        .apply(transaction, &self.config, expected_version),
+                };
+                if result == Err(TransactionError::VersionConflict) {
+                    self.transactions.release(key);
+                } else {
+                    self.transactions.confirm(key);
+                }
+                result?;
             }
             TransactionType::Dispute { .. }
             | TransactionType::Resolve { .. }
             | TransactionType::Chargeback { .. } => {
                 // Dispute operations reference existing deposits by transaction ID.
                 // The account must exist (otherwise the referenced deposit can't exist).
-                let mut account = self
-                    .accounts
-                    .get_mut(&client_id)
-                    .ok_or(TransactionError::TransactionNotFound)?;
-                account.add_transaction(transaction)?;
+                self.accounts
+                    .get(client_id)
+                    .ok_or(TransactionError::TransactionNotFound)?
+                    .apply(transaction, &self.config, expected_version)?;
             }
         }
 
         Ok(())
     }
 
-    /// Returns snapshots of all client accounts.
+    /// Processes a batch of transactions in order; see [`BatchMode`].
+    ///
+    /// An atomic batch locks every account it touches until it is committed or
+    /// rolled back, and reserves the IDs of its deposits and withdrawals while
+    /// it runs, so a concurrent transaction reusing one of them is rejected as
+    /// a duplicate even if the batch is then rolled back.
+    pub fn process_batch(
+        &self,
+        transactions: impl IntoIterator<Item = TransactionType>,
+        mode: BatchMode,
+    ) -> BatchReport {
+        match mode {
+            BatchMode::Independent => BatchReport {
+                results: transactions
+                    .into_iter()
+                    .map(|transaction| self.process(transaction))
+                    .collect(),
+                committed: true,
+            },
+            BatchMode::Atomic => self.process_atomic(&transactions.into_iter().collect::<Vec<_>>()),
+        }
+    }
+
+    /// Applies a batch to staged copies of its accounts, committing only if
+    /// every transaction succeeds.
+    fn process_atomic(&self, transactions: &[TransactionType]) -> BatchReport {
+        let _span = tracing::info_span!("batch", size = transactions.len()).entered();
+        let _batch = self.batch_lock.lock();
+        let mut clients: Vec<u16> = transactions.iter().map(|tx| tx.client_id().0).collect();
+        clients.sort_unstable();
+        clients.dedup();
+
+        loop {
+            let mut staged: Vec<StagedAccount<'_>> = clients
+                .iter()
+                .map(|&client| match self.accounts.get(ClientId(client)) {
+                    Some(account) => account.stage(),
+                    None => StagedAccount::new(ClientId(client)),
+                })
+                .collect();
+            let mut reserved = Vec::new();
+            let results: Vec<_> = transactions
+                .iter()
+                .map(|&transaction| {
+                    let index = clients
+                        .binary_search(&transaction.client_id().0)
+                        .expect("every client is staged");
+                    self.stage(transaction, &mut staged[index], &mut reserved)
+                })
+                .collect();
+
+            let committed = results.iter().all(Result::is_ok);
+            if committed {
+                // Accounts created concurrently since staging began invalidate
+                // the staged copies of those clients; retry against them.
+                let attached = staged.iter_mut().all(|account| {
+                    account.attach(self.accounts.get_or_create(account.client_id()))
+                });
+                if !attached {
+                    reserved
+                        .into_iter()
+                        .for_each(|key| self.transactions.release(key));
+                    continue;
+                }
+                staged.into_iter().for_each(StagedAccount::commit);
+                reserved
+                    .into_iter()
+                    .for_each(|key| self.transactions.confirm(key));
+            } else {
+                reserved
+                    .into_iter()
+                    .for_each(|key| self.transactions.release(key));
+            }
+            for (transaction, result) in transactions.iter().zip(&results) {
+                let _span = transaction_span(transaction).entered();
+                match result {
+                    Ok(()) if !committed => {
+                        self.metrics.record_rolled_back(transaction);
+                        tracing::debug!(outcome = "rolled_back", "transaction rolled back");
+                    }
+                    result => {
+                        self.metrics.record(transaction, result);
+                        trace_outcome(result);
+                    }
+                }
+            }
+            if committed {
+                tracing::debug!(committed, "atomic batch committed");
+            } else {
+                tracing::info!(committed, "atomic batch rolled back");
+            }
+            return BatchReport { results, committed };
+        }
+    }
+
+    /// Applies one transaction of an atomic batch to its staged account,
+    /// reserving the ID of a deposit or withdrawal.
+    fn stage(
+        &self,
+        transaction: TransactionType,
+        account: &mut StagedAccount<'_>,
+        reserved: &mut Vec<DedupKey>,
+    ) -> Result<(), TransactionError> {
+        let transaction = self.config.amounts.apply_to(transaction)?;
+        if let TransactionType::Deposit { .. } | TransactionType::Withdrawal { .. } = transaction {
+            reserved.push(self.transactions.reserve(Arc::new(transaction))?);
+        }
+        account.apply(transaction, &self.config)
+    }
+
+    /// Returns the engine's transaction counters and latency histogram.
+    pub fn metrics(&self) -> &EngineMetrics {
+        &self.metrics
+    }
+
+    /// Renders [`metrics`](Self::metrics) with account gauges in the
+    /// Prometheus text format, e.g. for a `/metrics` endpoint or a
+    /// node_exporter textfile.
+    pub fn metrics_text(&self) -> String {
+        self.metrics.render(&self.accounts())
+    }
+
+    /// Returns snapshots of all client accounts in ascending client ID order.
     ///
     /// Useful for generating output reports of account states.
     /// Returns owned data to prevent deadlocks when used alongside `process()`.
@@ -130,12 +356,72 @@ impl Engine {
         self.accounts.iter().map(|r| r.snapshot()).collect()
     }
 
+    /// Returns an accepted deposit or withdrawal of a client, with its
+    /// dispute status.
+    pub fn transaction(
+        &self,
+        client_id: ClientId,
+        transaction_id: TransactionId,
+    ) -> Option<StoredTransaction> {
+        self.accounts.get(client_id)?.transaction(transaction_id)
+    }
+
+    /// Returns every accepted deposit or withdrawal with ID `transaction_id`.
+    ///
+    /// There is at most one unless transaction IDs are unique only
+    /// [per client](crate::DuplicateScope::PerClient). The result is in
+    /// ascending client ID order.
+    pub fn find_transaction(&self, transaction_id: TransactionId) -> Vec<StoredTransaction> {
+        let mut clients = self.transactions.clients_of(transaction_id);
+        clients.sort_unstable_by_key(|client| client.0);
+        clients
+            .into_iter()
+            .filter_map(|client| self.transaction(client, transaction_id))
+            .collect()
+    }
+
+    /// Returns a page of a client's accepted deposits and withdrawals, or
+    /// `None` if the client has no account.
+    ///
+    /// See [`HistoryQuery`] for paging and filters.
+    pub fn transaction_history(
+        &self,
+        client_id: ClientId,
+        query: &HistoryQuery,
+    ) -> Option<HistoryPage> {
+        self.accounts
+            .get(client_id)
+            .map(|account| account.history(query))
+    }
+
     /// Retrieves a snapshot of a client account by ID.
     ///
     /// Returns `None` if no account exists for the given client ID.
     /// Returns owned data to prevent deadlocks when used alongside `process()`.
     pub fn get_account(&self, client_id: &ClientId) -> Option<AccountSnapshot> {
-        self.accounts.get(client_id).map(|r| r.snapshot())
+        self.accounts.get(*client_id).map(|r| r.snapshot())
+    }
+}
+
+/// The span a transaction is processed in.
+fn transaction_span(transaction: &TransactionType) -> tracing::Span {
+    tracing::info_span!(
+        "process",
+        client = transaction.client_id().0,
+        tx = transaction.id().0,
+        "type" = transaction.kind(),
+    )
+}
+
+/// Records the outcome of a transaction in its span.
+fn trace_outcome(result: &Result<(), TransactionError>) {
+    match result {
+        Ok(()) => tracing::debug!(outcome = "applied", "transaction applied"),
+        Err(error) => tracing::info!(
+            outcome = "rejected",
+            error = error.code(),
+            "transaction rejected: {error}"
+        ),
     }
 }
 
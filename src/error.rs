@@ -61,11 +61,247 @@ pub enum TransactionError {
     /// Account is locked (after chargeback)
     #[error("account is locked")]
     AccountLocked,
+
+    /// Balance arithmetic would overflow the representable range
+    #[error("balance arithmetic overflow")]
+    Overflow,
+
+    /// Amount has more decimal places than the engine allows
+    #[error("amount exceeds allowed decimal places")]
+    ExcessPrecision,
+
+    /// Account version differs from the one the transaction expected
+    #[error("account version does not match the expected version")]
+    VersionConflict,
+}
+
+impl TransactionError {
+    /// Every variant, in declaration order.
+    pub const ALL: [TransactionError; 13] = [
+        Self::MissingAmount,
+        Self::InvalidAmount,
+        Self::InsufficientFunds,
+        Self::TransactionNotFound,
+        Self::ClientMismatch,
+        Self::AlreadyDisputed,
+        Self::NotDisputed,
+        Self::NotDisputable,
+        Self::DuplicateTransaction,
+        Self::AccountLocked,
+        Self::Overflow,
+        Self::ExcessPrecision,
+        Self::VersionConflict,
+    ];
+
+    /// Stable machine-readable identifier, e.g. `INSUFFICIENT_FUNDS`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::MissingAmount => "MISSING_AMOUNT",
+            Self::InvalidAmount => "INVALID_AMOUNT",
+            Self::InsufficientFunds => "INSUFFICIENT_FUNDS",
+            Self::TransactionNotFound => "TRANSACTION_NOT_FOUND",
+            Self::ClientMismatch => "CLIENT_MISMATCH",
+            Self::AlreadyDisputed => "ALREADY_DISPUTED",
+            Self::NotDisputed => "NOT_DISPUTED",
+            Self::NotDisputable => "NOT_DISPUTABLE",
+            Self::DuplicateTransaction => "DUPLICATE_TRANSACTION",
+            Self::AccountLocked => "ACCOUNT_LOCKED",
+            Self::Overflow => "OVERFLOW",
+            Self::ExcessPrecision => "EXCESS_PRECISION",
+            Self::VersionConflict => "VERSION_CONFLICT",
+        }
+    }
+}
+
+/// Errors loading an [`EngineConfig`](crate::EngineConfig).
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    /// The configuration file could not be read
+    #[error("failed to read config file: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// The file extension is not `.toml` or `.json`
+    #[error("unsupported config format '{0}' (expected .toml or .json)")]
+    UnsupportedFormat(String),
+
+    /// The TOML document is malformed or contains unknown keys
+    #[error("invalid TOML config: {0}")]
+    Toml(#[from] toml::de::Error),
+
+    /// The JSON document is malformed or contains unknown keys
+    #[error("invalid JSON config: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// The document parsed but describes an invalid configuration
+    #[error("invalid config: {0}")]
+    Invalid(String),
+}
+
+/// Errors matching a [`CsvSchema`](crate::CsvSchema) against an input's header.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SchemaError {
+    /// A required field's column is not in the header
+    #[error("required field '{field}' is not mapped: the header has no column '{column}'")]
+    MissingColumn {
+        /// Field name (`type`, `client`, `tx` or `amount`).
+        field: &'static str,
+        /// Configured column name.
+        column: String,
+    },
+
+    /// A field is mapped by name but the input has no header row
+    #[error("field '{field}' is mapped to column name '{column}', but the input has no header row")]
+    NamedColumnWithoutHeader {
+        /// Field name (`type`, `client`, `tx` or `amount`).
+        field: &'static str,
+        /// Configured column name.
+        column: String,
+    },
+
+    /// A field is mapped to a position past the header's last column
+    #[error("field '{field}' is mapped to column #{index}, but the header has {width} column(s)")]
+    ColumnOutOfRange {
+        /// Field name (`type`, `client`, `tx` or `amount`).
+        field: &'static str,
+        /// Configured zero-based position.
+        index: usize,
+        /// Number of header columns.
+        width: usize,
+    },
+
+    /// Two fields are mapped to the same column
+    #[error("fields '{first}' and '{second}' are both mapped to column #{index}")]
+    DuplicateColumn {
+        /// Field mapped first, in [`Field::ALL`](crate::Field::ALL) order.
+        first: &'static str,
+        /// Field mapped second.
+        second: &'static str,
+        /// Zero-based position of the shared column.
+        index: usize,
+    },
+}
+
+impl SchemaError {
+    /// Returns a machine-readable error code.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::MissingColumn { .. } | Self::NamedColumnWithoutHeader { .. } => "MISSING_COLUMN",
+            Self::ColumnOutOfRange { .. } => "COLUMN_OUT_OF_RANGE",
+            Self::DuplicateColumn { .. } => "DUPLICATE_COLUMN",
+        }
+    }
+}
+
+/// Errors reading a bank statement or client lookup table.
+#[derive(Error, Debug)]
+pub enum StatementError {
+    /// The input could not be read
+    #[error("failed to read statement: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// A camt.053 document is not well-formed XML
+    #[error("invalid XML: {0}")]
+    Xml(#[from] roxmltree::Error),
+
+    /// A statement entry is structurally invalid
+    #[error("line {line}: {reason}")]
+    Invalid {
+        /// 1-based line number of the offending entry.
+        line: u64,
+        /// What is wrong with it.
+        reason: String,
+    },
+
+    /// The client lookup table could not be parsed
+    #[error("invalid client lookup table: {0}")]
+    Lookup(#[from] csv::Error),
+}
+
+/// Errors building or writing a payout file.
+#[derive(Error, Debug)]
+pub enum PayoutError {
+    /// Reading the beneficiary table or writing the file failed
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// The beneficiary table is malformed
+    #[error("invalid beneficiary table: {0}")]
+    Beneficiaries(#[from] csv::Error),
+
+    /// The payout configuration is invalid or lacks the format's section
+    #[error("payout config: {0}")]
+    Config(#[from] ConfigError),
+
+    /// There are no payouts to write
+    #[error("no accepted withdrawals to pay out")]
+    Empty,
+
+    /// A paid-out client has no beneficiary record
+    #[error("no beneficiary for client {0}")]
+    MissingBeneficiary(u16),
+
+    /// A beneficiary lacks a detail the format requires, or it is invalid
+    #[error("beneficiary for client {client}: {reason}")]
+    InvalidBeneficiary {
+        /// Client ID of the beneficiary.
+        client: u16,
+        /// What is missing or wrong.
+        reason: String,
+    },
+
+    /// A payout amount cannot be expressed in the file format
+    #[error("transaction {transaction}: {reason}")]
+    InvalidAmount {
+        /// Withdrawal transaction ID.
+        transaction: u32,
+        /// Why the amount is not payable.
+        reason: String,
+    },
+
+    /// The batch totals exceed the format's limits
+    #[error("batch totals exceed format limits: {0}")]
+    TotalsOverflow(String),
+}
+
+/// Errors writing an account report.
+#[derive(Error, Debug)]
+pub enum ReportError {
+    /// Writing to the output failed
+    #[error("failed to write report: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// Delimited (CSV/TSV) serialization failed
+    #[error("failed to write CSV report: {0}")]
+    Csv(#[from] csv::Error),
+
+    /// JSON serialization failed
+    #[error("failed to write JSON report: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// A column total does not fit the decimal range
+    #[error("the {0} total overflows the decimal range")]
+    TotalOverflow(&'static str),
+}
+
+/// Errors converting or parsing a [`FixedAmount`](crate::FixedAmount).
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmountError {
+    /// Input is not a plain decimal number
+    #[error("invalid amount format")]
+    Invalid,
+
+    /// More than four fractional digits
+    #[error("amount has more than 4 decimal places")]
+    TooPrecise,
+
+    /// Value does not fit the fixed-point range
+    #[error("amount out of range")]
+    OutOfRange,
 }
 
 #[cfg(test)]
 mod tests {
-    use super::TransactionError;
+    use super::{AmountError, TransactionError};
 
     #[test]
     fn error_display_messages() {
@@ -109,6 +345,28 @@ mod tests {
             TransactionError::AccountLocked.to_string(),
             "account is locked"
         );
+        assert_eq!(
+            TransactionError::Overflow.to_string(),
+            "balance arithmetic overflow"
+        );
+        assert_eq!(
+            TransactionError::ExcessPrecision.to_string(),
+            "amount exceeds allowed decimal places"
+        );
+        assert_eq!(
+            TransactionError::VersionConflict.to_string(),
+            "account version does not match the expected version"
+        );
+    }
+
+    #[test]
+    fn amount_error_display_messages() {
+        assert_eq!(AmountError::Invalid.to_string(), "invalid amount format");
+        assert_eq!(
+            AmountError::TooPrecise.to_string(),
+            "amount has more than 4 decimal places"
+        );
+        assert_eq!(AmountError::OutOfRange.to_string(), "amount out of range");
     }
 
     #[test]
@@ -117,4 +375,31 @@ mod tests {
         let cloned = error.clone();
         assert_eq!(error, cloned);
     }
+
+    #[test]
+    fn all_lists_every_transaction_error_once() {
+        for error in &TransactionError::ALL {
+            // No wildcard arm: a new variant stops this from compiling, as a
+            // reminder to add it to `ALL`.
+            match error {
+                TransactionError::MissingAmount
+                | TransactionError::InvalidAmount
+                | TransactionError::InsufficientFunds
+                | TransactionError::TransactionNotFound
+                | TransactionError::ClientMismatch
+                | TransactionError::AlreadyDisputed
+                | TransactionError::NotDisputed
+                | TransactionError::NotDisputable
+                | TransactionError::DuplicateTransaction
+                | TransactionError::AccountLocked
+                | TransactionError::Overflow
+                | TransactionError::ExcessPrecision
+                | TransactionError::VersionConflict => {}
+            }
+        }
+        let mut codes: Vec<_> = TransactionError::ALL.iter().map(|e| e.code()).collect();
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), TransactionError::ALL.len());
+    }
 }
@@ -0,0 +1,86 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Transaction history queries.
+//!
+//! Every account keeps its accepted deposits and withdrawals in the order
+//! they were applied, each with its dispute [`TransactionStatus`]. Dispute,
+//! resolve and chargeback operations are not stored themselves; they show up
+//! as the status of the transaction they refer to. Transactions the engine
+//! rejected are not stored.
+//!
+//! [`Engine::transaction_history`](crate::Engine::transaction_history) returns
+//! the history a [`HistoryPage`] at a time; pass the page's `next_cursor` back
+//! in the next [`HistoryQuery`] to continue.
+
+use crate::transaction::{TransactionStatus, TransactionType};
+
+/// A stored deposit or withdrawal with its current dispute status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StoredTransaction {
+    /// The transaction as requested, with the amount the engine applied.
+    pub transaction: TransactionType,
+    /// Dispute status.
+    pub status: TransactionStatus,
+}
+
+/// Which part of an account's history to return.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryQuery {
+    /// Position to start from: 0, or a previous page's `next_cursor`.
+    pub cursor: u64,
+    /// Maximum number of transactions per page (at least 1).
+    pub limit: usize,
+    /// Only transactions of this type (`deposit` or `withdrawal`).
+    pub kind: Option<String>,
+    /// Only transactions with this status.
+    pub status: Option<TransactionStatus>,
+}
+
+impl HistoryQuery {
+    /// Default page size.
+    pub const DEFAULT_LIMIT: usize = 50;
+
+    /// Returns `true` if `stored` passes the type and status filters.
+    pub fn matches(&self, stored: &StoredTransaction) -> bool {
+        self.kind
+            .as_deref()
+            .is_none_or(|kind| stored.transaction.kind() == kind)
+            && self.status.is_none_or(|status| stored.status == status)
+    }
+}
+
+impl Default for HistoryQuery {
+    /// The first page of the full history.
+    fn default() -> Self {
+        Self {
+            cursor: 0,
+            limit: Self::DEFAULT_LIMIT,
+            kind: None,
+            status: None,
+        }
+    }
+}
+
+/// One page of an account's history.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryPage {
+    /// Matching transactions, oldest first.
+    pub transactions: Vec<StoredTransaction>,
+    /// Cursor of the next page, or `None` if the history is exhausted.
+    pub next_cursor: Option<u64>,
+}
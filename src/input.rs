@@ -0,0 +1,331 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Transaction input formats and the shared JSON transaction DTO.
+//!
+//! [`TransactionRequest`] is the wire shape used both by the HTTP server's
+//! request bodies and by newline-delimited JSON (NDJSON) input, so the two
+//! cannot drift:
+//!
+//! ```json
+//! {"type": "deposit", "client_id": 1, "transaction_id": 1, "amount": "100.00"}
+//! {"type": "dispute", "client_id": 1, "transaction_id": 1}
+//! ```
+//!
+//! [`JsonLines`] reads such a stream one transaction per line, reporting the
+//! line number of every record so callers can skip and report bad lines the
+//! same way CSV rows are handled.
+
+use crate::base::{ClientId, TransactionId};
+use crate::transaction::TransactionType;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{self, BufRead};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Transaction input format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputFormat {
+    /// Delimited text read according to a [`CsvSchema`](crate::CsvSchema).
+    #[default]
+    Csv,
+    /// One JSON [`TransactionRequest`] per line.
+    Jsonl,
+}
+
+impl InputFormat {
+    /// Guesses the format from a file extension, looking through a trailing
+    /// `.gz` or `.zst` compression suffix.
+    ///
+    /// `.jsonl`, `.ndjson` and `.json` select [`Jsonl`](Self::Jsonl) and `.csv`
+    /// and `.tsv` select [`Csv`](Self::Csv); anything else is `None`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let mut path = path.to_path_buf();
+        if matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("gz" | "zst")
+        ) {
+            path.set_extension("");
+        }
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "jsonl" | "ndjson" | "json" => Some(Self::Jsonl),
+            "csv" | "tsv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for InputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(Self::Csv),
+            "jsonl" | "ndjson" => Ok(Self::Jsonl),
+            other => Err(format!(
+                "unknown input format '{other}' (expected csv or jsonl)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for InputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Csv => "csv",
+            Self::Jsonl => "jsonl",
+        })
+    }
+}
+
+/// JSON representation of a transaction.
+///
+/// Uses a tagged enum for clean JSON representation; amounts are decimal
+/// strings. Amounts are normalized by the engine's
+/// [`AmountPolicy`](crate::AmountPolicy) when processed, exactly like CSV input.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "http", derive(utoipa::ToSchema))]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum TransactionRequest {
+    Deposit {
+        client_id: u16,
+        transaction_id: u32,
+        #[cfg_attr(feature = "http", schema(value_type = String, example = "100.00"))]
+        amount: Decimal,
+    },
+    Withdrawal {
+        client_id: u16,
+        transaction_id: u32,
+        #[cfg_attr(feature = "http", schema(value_type = String, example = "25.00"))]
+        amount: Decimal,
+    },
+    Dispute {
+        client_id: u16,
+        transaction_id: u32,
+    },
+    Resolve {
+        client_id: u16,
+        transaction_id: u32,
+    },
+    Chargeback {
+        client_id: u16,
+        transaction_id: u32,
+    },
+}
+
+impl From<TransactionRequest> for TransactionType {
+    fn from(request: TransactionRequest) -> Self {
+        match request {
+            TransactionRequest::Deposit {
+                client_id,
+                transaction_id,
+                amount,
+            } => Self::Deposit {
+                client_id: ClientId(client_id),
+                transaction_id: TransactionId(transaction_id),
+                amount,
+            },
+            TransactionRequest::Withdrawal {
+                client_id,
+                transaction_id,
+                amount,
+            } => Self::Withdrawal {
+                client_id: ClientId(client_id),
+                transaction_id: TransactionId(transaction_id),
+                amount,
+            },
+            TransactionRequest::Dispute {
+                client_id,
+                transaction_id,
+            } => Self::Dispute {
+                client_id: ClientId(client_id),
+                transaction_id: TransactionId(transaction_id),
+            },
+            TransactionRequest::Resolve {
+                client_id,
+                transaction_id,
+            } => Self::Resolve {
+                client_id: ClientId(client_id),
+                transaction_id: TransactionId(transaction_id),
+            },
+            TransactionRequest::Chargeback {
+                client_id,
+                transaction_id,
+            } => Self::Chargeback {
+                client_id: ClientId(client_id),
+                transaction_id: TransactionId(transaction_id),
+            },
+        }
+    }
+}
+
+impl From<TransactionType> for TransactionRequest {
+    fn from(transaction: TransactionType) -> Self {
+        match transaction {
+            TransactionType::Deposit {
+                client_id,
+                transaction_id,
+                amount,
+            } => Self::Deposit {
+                client_id: client_id.0,
+                transaction_id: transaction_id.0,
+                amount,
+            },
+            TransactionType::Withdrawal {
+                client_id,
+                transaction_id,
+                amount,
+            } => Self::Withdrawal {
+                client_id: client_id.0,
+                transaction_id: transaction_id.0,
+                amount,
+            },
+            TransactionType::Dispute {
+                client_id,
+                transaction_id,
+            } => Self::Dispute {
+                client_id: client_id.0,
+                transaction_id: transaction_id.0,
+            },
+            TransactionType::Resolve {
+                client_id,
+                transaction_id,
+            } => Self::Resolve {
+                client_id: client_id.0,
+                transaction_id: transaction_id.0,
+            },
+            TransactionType::Chargeback {
+                client_id,
+                transaction_id,
+            } => Self::Chargeback {
+                client_id: client_id.0,
+                transaction_id: transaction_id.0,
+            },
+        }
+    }
+}
+
+/// One non-blank line of a JSON Lines stream.
+#[derive(Debug)]
+pub struct JsonLine {
+    /// 1-based line number.
+    pub line: u64,
+    /// The decoded transaction, or why the line could not be decoded.
+    pub request: Result<TransactionRequest, serde_json::Error>,
+    /// The line as untyped JSON if it is well formed but not a transaction,
+    /// so that callers can tell what is wrong with it.
+    pub json: Option<serde_json::Value>,
+}
+
+/// Iterator over the transactions of a JSON Lines stream.
+///
+/// Blank lines are skipped. Undecodable lines are yielded as errors so the
+/// caller decides whether to skip or stop; only I/O failures end the stream.
+#[derive(Debug)]
+pub struct JsonLines<R> {
+    reader: R,
+    line: u64,
+    buf: String,
+}
+
+impl<R: BufRead> JsonLines<R> {
+    /// Wraps a buffered reader.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: 0,
+            buf: String::new(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for JsonLines<R> {
+    type Item = io::Result<JsonLine>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            match self.reader.read_line(&mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => self.line += 1,
+                Err(e) => return Some(Err(e)),
+            }
+            if self.buf.trim().is_empty() {
+                continue;
+            }
+            let request = serde_json::from_str(&self.buf);
+            // Only undecodable lines pay for a second, untyped parse.
+            let json = match request {
+                Ok(_) => None,
+                Err(_) => serde_json::from_str(&self.buf).ok(),
+            };
+            return Some(Ok(JsonLine {
+                line: self.line,
+                request,
+                json,
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn format_from_extension() {
+        let format = |p: &str| InputFormat::from_path(Path::new(p));
+        assert_eq!(format("feed.jsonl"), Some(InputFormat::Jsonl));
+        assert_eq!(format("feed.NDJSON.gz"), Some(InputFormat::Jsonl));
+        assert_eq!(format("day1.csv.zst"), Some(InputFormat::Csv));
+        assert_eq!(format("-"), None);
+        assert_eq!(format("feed.gz"), None);
+    }
+
+    #[test]
+    fn request_round_trips_through_transaction_type() {
+        let json = r#"{"type":"deposit","client_id":1,"transaction_id":7,"amount":"2.5"}"#;
+        let request: TransactionRequest = serde_json::from_str(json).unwrap();
+        let tx = TransactionType::from(request.clone());
+        assert_eq!(
+            tx,
+            TransactionType::Deposit {
+                client_id: ClientId(1),
+                transaction_id: TransactionId(7),
+                amount: dec!(2.5),
+            }
+        );
+        assert_eq!(TransactionRequest::from(tx), request);
+        assert_eq!(serde_json::to_string(&request).unwrap(), json);
+    }
+
+    #[test]
+    fn lines_report_numbers_and_skip_blanks() {
+        let input = "{\"type\":\"dispute\",\"client_id\":1,\"transaction_id\":1}\n\
+                     \n\
+                     {\"type\":\"refund\",\"client_id\":1,\"transaction_id\":2}\n\
+                     not json\n";
+        let lines: Vec<_> = JsonLines::new(input.as_bytes())
+            .map(|line| line.unwrap())
+            .map(|line| (line.line, line.request.is_ok()))
+            .collect();
+        assert_eq!(lines, vec![(1, true), (3, false), (4, false)]);
+    }
+}
@@ -0,0 +1,531 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Plain-text accounting export for Beancount and ledger-cli.
+//!
+//! A [`JournalRecorder`] records the transactions the engine accepted during a
+//! run and writes them as double-entry journal entries, followed by a balance
+//! assertion for every client account, so the journal can be checked against
+//! the engine's own balances with `bean-check` or `ledger`.
+//!
+//! Client funds are owed to the clients and so are booked as liabilities
+//! (negative balances in both tools), split into `Available` and `Held`
+//! sub-accounts. Money enters and leaves through a settlement asset account;
+//! a disputed withdrawal is parked in a disputes asset account until it is
+//! resolved or charged back:
+//!
+//! | Movement | Debit | Credit |
+//! |----------|-------|--------|
+//! | Deposit | Settlement | Available |
+//! | Withdrawal | Available | Settlement |
+//! | Dispute (deposit) | Available | Held |
+//! | Resolve (deposit) | Held | Available |
+//! | Chargeback (deposit) | Held | Settlement |
+//! | Dispute (withdrawal) | Disputes | Held |
+//! | Resolve (withdrawal) | Held | Disputes |
+//! | Chargeback (withdrawal) | Held, Settlement | Available, Disputes |
+
+use crate::account::{Account, AccountSnapshot};
+use crate::base::{ClientId, TransactionId};
+use crate::date::{is_date, next_day, today};
+use crate::error::{ConfigError, TransactionError};
+use crate::transaction::TransactionType;
+use rust_decimal::Decimal;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Journal file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalFormat {
+    /// Beancount, checked with `bean-check`.
+    Beancount,
+    /// ledger-cli (also read by hledger).
+    Ledger,
+}
+
+impl JournalFormat {
+    /// Guesses the format from a file extension: `.beancount` and `.bean` are
+    /// Beancount; `.ledger`, `.journal` and `.dat` are ledger-cli.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "beancount" | "bean" => Some(Self::Beancount),
+            "ledger" | "journal" | "dat" => Some(Self::Ledger),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for JournalFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "beancount" => Ok(Self::Beancount),
+            "ledger" => Ok(Self::Ledger),
+            other => Err(format!(
+                "unknown journal format '{other}' (expected beancount or ledger)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for JournalFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Beancount => "beancount",
+            Self::Ledger => "ledger",
+        })
+    }
+}
+
+/// Date, commodity and account names of an exported journal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalOptions {
+    /// Date of every entry, `YYYY-MM-DD`. Beancount balance assertions are
+    /// dated the following day, as they apply at the start of their day.
+    pub date: String,
+    /// Commodity of all amounts.
+    pub currency: String,
+    /// Asset account money enters and leaves through.
+    pub settlement_account: String,
+    /// Asset account holding disputed withdrawals.
+    pub disputes_account: String,
+    /// Parent of the per-client `<client>:Available` and `<client>:Held`
+    /// liability accounts.
+    pub clients_account: String,
+}
+
+impl JournalOptions {
+    /// Creates options for entries dated `date`, in USD, with the default
+    /// account names.
+    pub fn new(date: impl Into<String>) -> Self {
+        Self {
+            date: date.into(),
+            currency: "USD".to_string(),
+            settlement_account: "Assets:Settlement".to_string(),
+            disputes_account: "Assets:Disputes".to_string(),
+            clients_account: "Liabilities:Clients".to_string(),
+        }
+    }
+
+    /// Checks the date, commodity and account names.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !is_date(&self.date) {
+            return Err(ConfigError::Invalid(format!(
+                "journal date must be YYYY-MM-DD, got '{}'",
+                self.date
+            )));
+        }
+        let currency = self.currency.as_bytes();
+        let valid_currency = (2..=24).contains(&currency.len())
+            && currency[0].is_ascii_uppercase()
+            && currency
+                .iter()
+                .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit());
+        if !valid_currency {
+            return Err(ConfigError::Invalid(format!(
+                "journal currency must be 2-24 upper-case letters or digits, got '{}'",
+                self.currency
+            )));
+        }
+        for account in [
+            &self.settlement_account,
+            &self.disputes_account,
+            &self.clients_account,
+        ] {
+            if !is_account_name(account) {
+                return Err(ConfigError::Invalid(format!(
+                    "'{account}' is not a valid account name"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn available(&self, client: ClientId) -> String {
+        format!("{}:{}:Available", self.clients_account, client.0)
+    }
+
+    fn held(&self, client: ClientId) -> String {
+        format!("{}:{}:Held", self.clients_account, client.0)
+    }
+}
+
+impl Default for JournalOptions {
+    /// Options dated today (UTC).
+    fn default() -> Self {
+        Self::new(today())
+    }
+}
+
+/// An accepted transaction with the amount it moved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JournalEntry {
+    /// The accepted transaction.
+    pub transaction: TransactionType,
+    /// Amount moved: the transaction's own amount, or for a dispute, resolve
+    /// or chargeback that of the referenced transaction.
+    pub amount: Decimal,
+    /// Whether a dispute, resolve or chargeback refers to a withdrawal.
+    pub of_withdrawal: bool,
+}
+
+impl JournalEntry {
+    /// Returns the entry's postings; positive amounts are debits.
+    fn postings(&self, options: &JournalOptions) -> Vec<(String, Decimal)> {
+        let client = self.transaction.client_id();
+        let available = options.available(client);
+        let held = options.held(client);
+        let settlement = options.settlement_account.clone();
+        let disputes = options.disputes_account.clone();
+        let x = self.amount;
+
+        match (self.transaction, self.of_withdrawal) {
+            (TransactionType::Deposit { .. }, _) => vec![(settlement, x), (available, -x)],
+            (TransactionType::Withdrawal { .. }, _) => vec![(available, x), (settlement, -x)],
+            (TransactionType::Dispute { .. }, false) => vec![(available, x), (held, -x)],
+            (TransactionType::Resolve { .. }, false) => vec![(held, x), (available, -x)],
+            (TransactionType::Chargeback { .. }, false) => vec![(held, x), (settlement, -x)],
+            (TransactionType::Dispute { .. }, true) => vec![(disputes, x), (held, -x)],
+            (TransactionType::Resolve { .. }, true) => vec![(held, x), (disputes, -x)],
+            (TransactionType::Chargeback { .. }, true) => {
+                vec![(held, x), (available, -x), (settlement, x), (disputes, -x)]
+            }
+        }
+    }
+
+    /// Returns a one-line description, e.g. `Dispute of deposit tx 7`.
+    fn narration(&self) -> String {
+        let id = self.transaction.id().0;
+        let referenced = if self.of_withdrawal {
+            "withdrawal"
+        } else {
+            "deposit"
+        };
+        match self.transaction {
+            TransactionType::Deposit { .. } => format!("Deposit tx {id}"),
+            TransactionType::Withdrawal { .. } => format!("Withdrawal tx {id}"),
+            TransactionType::Dispute { .. } => format!("Dispute of {referenced} tx {id}"),
+            TransactionType::Resolve { .. } => format!("Resolve of {referenced} tx {id}"),
+            TransactionType::Chargeback { .. } => format!("Chargeback of {referenced} tx {id}"),
+        }
+    }
+}
+
+/// Records the transactions accepted during a run as journal entries.
+///
+/// Feed it every transaction, with amounts as normalized by the engine's
+/// [`AmountPolicy`](crate::AmountPolicy), together with the engine's result.
+#[derive(Debug, Clone, Default)]
+pub struct JournalRecorder {
+    entries: Vec<JournalEntry>,
+    /// Amount of every accepted deposit and withdrawal, and whether it was a
+    /// withdrawal, for dispute operations to refer to.
+    amounts: HashMap<(ClientId, TransactionId), (Decimal, bool)>,
+}
+
+impl JournalRecorder {
+    /// Creates an empty recorder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the engine's outcome for a transaction.
+    pub fn record(&mut self, transaction: &TransactionType, result: &Result<(), TransactionError>) {
+        if result.is_err() {
+            return;
+        }
+        let key = (transaction.client_id(), transaction.id());
+        let (amount, of_withdrawal) = match transaction {
+            TransactionType::Deposit { amount, .. } => {
+                self.amounts.insert(key, (*amount, false));
+                (*amount, false)
+            }
+            TransactionType::Withdrawal { amount, .. } => {
+                self.amounts.insert(key, (*amount, true));
+                (*amount, true)
+            }
+            // Accepted, so the referenced transaction was recorded.
+            _ => match self.amounts.get(&key) {
+                Some(&referenced) => referenced,
+                None => return,
+            },
+        };
+        self.entries.push(JournalEntry {
+            transaction: *transaction,
+            amount,
+            of_withdrawal,
+        });
+    }
+
+    /// Returns the recorded entries in the order they were accepted.
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// Writes the journal: account openings, the recorded entries, and
+    /// balance assertions for every account in `accounts` (the engine's final
+    /// snapshots).
+    pub fn write<W: Write>(
+        &self,
+        format: JournalFormat,
+        options: &JournalOptions,
+        accounts: &[AccountSnapshot],
+        mut w: W,
+    ) -> io::Result<()> {
+        let clients: BTreeSet<u16> = accounts
+            .iter()
+            .map(|a| a.client_id.0)
+            .chain(self.entries.iter().map(|e| e.transaction.client_id().0))
+            .collect();
+        let mut names = vec![
+            options.settlement_account.clone(),
+            options.disputes_account.clone(),
+        ];
+        for client in clients.into_iter().map(ClientId) {
+            names.push(options.available(client));
+            names.push(options.held(client));
+        }
+
+        let date = &options.date;
+        let currency = &options.currency;
+        writeln!(w, "; Generated by ledger-demo-rs")?;
+        writeln!(w)?;
+        for name in &names {
+            match format {
+                JournalFormat::Beancount => writeln!(w, "{date} open {name} {currency}")?,
+                JournalFormat::Ledger => writeln!(w, "account {name}")?,
+            }
+        }
+
+        for entry in &self.entries {
+            let id = entry.transaction.id().0;
+            let client = entry.transaction.client_id().0;
+            writeln!(w)?;
+            match format {
+                JournalFormat::Beancount => {
+                    writeln!(w, "{date} * \"{}\"", entry.narration())?;
+                    writeln!(w, "  client: {client}")?;
+                    writeln!(w, "  tx: {id}")?;
+                }
+                JournalFormat::Ledger => {
+                    writeln!(w, "{date} * {}", entry.narration())?;
+                    writeln!(w, "    ; client: {client}")?;
+                    writeln!(w, "    ; tx: {id}")?;
+                }
+            }
+            for (account, amount) in entry.postings(options) {
+                write_posting(&mut w, format, &account, amount, currency)?;
+            }
+        }
+
+        // Client balances are liabilities, so the assertions are negated.
+        writeln!(w)?;
+        match format {
+            JournalFormat::Beancount => {
+                // Beancount checks balances at the start of the assertion's day.
+                let day_after = next_day(date).unwrap_or_else(|| date.clone());
+                for account in accounts {
+                    for (name, balance) in [
+                        (options.available(account.client_id), account.available),
+                        (options.held(account.client_id), account.held),
+                    ] {
+                        writeln!(
+                            w,
+                            "{day_after} balance {name:<40} {:>20} {currency}",
+                            amount(-balance)
+                        )?;
+                    }
+                }
+            }
+            JournalFormat::Ledger => {
+                writeln!(w, "{date} * Closing balances")?;
+                for account in accounts {
+                    for (name, balance) in [
+                        (options.available(account.client_id), account.available),
+                        (options.held(account.client_id), account.held),
+                    ] {
+                        writeln!(
+                            w,
+                            "    {name:<40} {:>20} {currency} = {} {currency}",
+                            amount(Decimal::ZERO),
+                            amount(-balance)
+                        )?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes one posting line.
+fn write_posting<W: Write>(
+    w: &mut W,
+    format: JournalFormat,
+    account: &str,
+    value: Decimal,
+    currency: &str,
+) -> io::Result<()> {
+    let indent = match format {
+        JournalFormat::Beancount => "  ",
+        JournalFormat::Ledger => "    ",
+    };
+    writeln!(w, "{indent}{account:<40} {:>20} {currency}", amount(value))
+}
+
+/// Formats an amount with the engine's reporting precision.
+fn amount(value: Decimal) -> String {
+    let mut value = value.round_dp(Account::DECIMAL_PRECISION);
+    value.rescale(Account::DECIMAL_PRECISION);
+    // Avoid `-0.0000`.
+    if value.is_zero() {
+        value.set_sign_positive(true);
+    }
+    value.to_string()
+}
+
+/// Returns `true` for a `Root:Component:...` account name accepted by both
+/// Beancount and ledger-cli.
+fn is_account_name(name: &str) -> bool {
+    let mut components = name.split(':');
+    let root_ok = components.next().is_some_and(|root| {
+        ["Assets", "Liabilities", "Equity", "Income", "Expenses"].contains(&root)
+    });
+    root_ok
+        && name.contains(':')
+        && components.all(|c| {
+            c.chars()
+                .next()
+                .is_some_and(|first| first.is_ascii_uppercase() || first.is_ascii_digit())
+                && c.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '-')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn postings(
+        transaction: TransactionType,
+        amount: Decimal,
+        of_withdrawal: bool,
+    ) -> Vec<(String, Decimal)> {
+        JournalEntry {
+            transaction,
+            amount,
+            of_withdrawal,
+        }
+        .postings(&JournalOptions::new("2025-03-07"))
+    }
+
+    #[test]
+    fn every_entry_balances() {
+        let client_id = ClientId(7);
+        let transaction_id = TransactionId(1);
+        let amount = dec!(12.5);
+        let dispute_ops = [
+            TransactionType::Dispute {
+                client_id,
+                transaction_id,
+            },
+            TransactionType::Resolve {
+                client_id,
+                transaction_id,
+            },
+            TransactionType::Chargeback {
+                client_id,
+                transaction_id,
+            },
+        ];
+        let deposit = TransactionType::Deposit {
+            client_id,
+            transaction_id,
+            amount,
+        };
+        let withdrawal = TransactionType::Withdrawal {
+            client_id,
+            transaction_id,
+            amount,
+        };
+        for (tx, of_withdrawal) in [(deposit, false), (withdrawal, true)]
+            .into_iter()
+            .chain(dispute_ops.iter().flat_map(|&op| [(op, false), (op, true)]))
+        {
+            let postings = postings(tx, amount, of_withdrawal);
+            let sum: Decimal = postings.iter().map(|(_, x)| x).sum();
+            assert_eq!(sum, Decimal::ZERO, "{tx:?} {of_withdrawal}");
+        }
+
+        assert_eq!(
+            postings(dispute_ops[0], amount, false),
+            vec![
+                ("Liabilities:Clients:7:Available".to_string(), amount),
+                ("Liabilities:Clients:7:Held".to_string(), -amount),
+            ]
+        );
+    }
+
+    #[test]
+    fn recorder_skips_rejected_transactions() {
+        let client_id = ClientId(1);
+        let mut recorder = JournalRecorder::new();
+        let deposit = TransactionType::Deposit {
+            client_id,
+            transaction_id: TransactionId(1),
+            amount: dec!(10),
+        };
+        let dispute = TransactionType::Dispute {
+            client_id,
+            transaction_id: TransactionId(1),
+        };
+        recorder.record(&deposit, &Ok(()));
+        recorder.record(&dispute, &Err(TransactionError::InsufficientFunds));
+        recorder.record(&dispute, &Ok(()));
+
+        let amounts: Vec<_> = recorder.entries().iter().map(|e| e.amount).collect();
+        assert_eq!(amounts, vec![dec!(10), dec!(10)]);
+        assert_eq!(recorder.entries()[1].narration(), "Dispute of deposit tx 1");
+    }
+
+    #[test]
+    fn options_are_validated() {
+        assert!(JournalOptions::new("2025-03-07").validate().is_ok());
+        assert!(JournalOptions::new("2025-02-30").validate().is_err());
+
+        let mut options = JournalOptions::new("2025-03-07");
+        options.currency = "usd".to_string();
+        assert!(options.validate().is_err());
+
+        let mut options = JournalOptions::new("2025-03-07");
+        options.clients_account = "Clients".to_string();
+        assert!(options.validate().is_err());
+        options.clients_account = "Liabilities:Client funds".to_string();
+        assert!(options.validate().is_err());
+    }
+
+    #[test]
+    fn amounts_use_reporting_precision() {
+        assert_eq!(amount(dec!(1.5)), "1.5000");
+        assert_eq!(amount(-dec!(0)), "0.0000");
+        assert_eq!(amount(dec!(-2.25)), "-2.2500");
+    }
+}
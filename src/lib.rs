@@ -26,6 +26,19 @@
 //! - [`Account`]: Client account with balance tracking and dispute handling
 //! - [`TransactionType`]: Supported transaction types (deposit, withdrawal, etc.)
 //! - [`TransactionError`]: Error types for transaction processing failures
+//! - [`BatchMode`]: Independent or all-or-nothing processing of transaction batches
+//! - [`EngineConfig`]: Engine policies, including the input [`AmountPolicy`]
+//! - [`EngineBuilder`]: Fluent construction of a configured [`Engine`]
+//! - [`write_report`]: Account reports as CSV, TSV, JSON, JSON Lines or an aligned table
+//! - [`CsvSchema`]: Column mapping, delimiter and type aliases for CSV inputs
+//! - [`TransactionRequest`]: JSON transaction shape shared by the server and NDJSON input
+//! - [`StatementImporter`]: Deposits and withdrawals from camt.053 and MT940 bank statements
+//! - [`PayoutFile`]: SEPA pain.001 and NACHA payout files for accepted withdrawals
+//! - [`JournalRecorder`]: Beancount and ledger-cli journals of accepted transactions
+//! - [`server`]: HTTP API router and server (feature `http`)
+//! - [`RunStats`]: Counters and aggregates summarizing a processing run
+//! - [`EngineMetrics`]: Prometheus counters, gauges and latency histogram of an [`Engine`]
+//! - [`FixedAmount`]: Four-decimal fixed-point amount used by the `fixed-point` feature
 //!
 //! ## Example
 //!
@@ -50,19 +63,61 @@
 //!
 //! ## Thread Safety
 //!
-//! The engine uses handles concurrent access to accounts, allowing multiple transactions to be
-//! processed in parallel for different clients.
+//! The engine stores accounts in a dense table indexed by client ID and takes a single
+//! lock per account, allowing multiple transactions to be processed in parallel for
+//! different clients.
 
 pub mod account;
+mod account_table;
+mod amount;
 mod base;
+mod batch;
+mod builder;
+mod config;
+mod date;
 mod engine;
 pub mod error;
+mod history;
+mod input;
+mod journal;
+mod metrics;
+mod payout;
+mod report;
+mod schema;
+#[cfg(feature = "http")]
+pub mod server;
+mod statement;
+mod stats;
 mod transaction;
 mod transaction_queue;
 
 pub use account::{Account, AccountSnapshot};
+pub use amount::FixedAmount;
 pub use base::{ClientId, TransactionId};
+pub use batch::{BatchMode, BatchReport};
+pub use builder::EngineBuilder;
+pub use config::{
+    AmountPolicy, ChargebackPolicy, DisputePolicy, DuplicateScope, EngineConfig, PrecisionMode,
+    RoundingMode,
+};
 pub use engine::Engine;
-pub use error::TransactionError;
-pub use transaction::TransactionType;
+pub use error::{
+    AmountError, ConfigError, PayoutError, ReportError, SchemaError, StatementError,
+    TransactionError,
+};
+pub use history::{HistoryPage, HistoryQuery, StoredTransaction};
+pub use input::{InputFormat, JsonLine, JsonLines, TransactionRequest};
+pub use journal::{JournalEntry, JournalFormat, JournalOptions, JournalRecorder};
+pub use metrics::{EngineMetrics, LATENCY_BUCKETS};
+pub use payout::{
+    AccountType, Beneficiaries, Beneficiary, NachaOriginator, Payout, PayoutCollector,
+    PayoutConfig, PayoutEntry, PayoutFile, PayoutFormat, SepaOriginator,
+};
+pub use report::{AccountFilter, OutputFormat, SortKey, SortOrder, sort_accounts, write_report};
+pub use schema::{ColumnMapping, ColumnRef, CsvSchema, Field, ResolvedSchema};
+pub use statement::{
+    ClientLookup, Direction, StatementEntry, StatementFormat, StatementImport, StatementImporter,
+};
+pub use stats::{AccountStats, RunStats, TypeStats};
+pub use transaction::{TransactionStatus, TransactionType};
 pub use transaction_queue::TransactionQueue;
@@ -0,0 +1,366 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Engine metrics in the Prometheus text exposition format.
+//!
+//! [`EngineMetrics`] counts every transaction the engine processes, by type and
+//! outcome and by [`TransactionError::code`], and times [`Engine::process`]
+//! in a histogram. Account gauges are computed from the accounts when the
+//! metrics are rendered by [`Engine::metrics_text`], so they are always
+//! consistent with the balances at that moment.
+//!
+//! [`Engine::process`]: crate::Engine::process
+//! [`Engine::metrics_text`]: crate::Engine::metrics_text
+
+use crate::account::AccountSnapshot;
+use crate::error::TransactionError;
+use crate::transaction::TransactionType;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use std::fmt::{self, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Transaction type names, indexed like [`EngineMetrics::transactions`].
+const TYPES: [&str; 5] = ["deposit", "withdrawal", "dispute", "resolve", "chargeback"];
+
+/// Values of the `result` label: applied, rejected by the engine, or valid
+/// but undone with the rest of a failed atomic batch.
+const RESULTS: [&str; 3] = ["applied", "rejected", "rolled_back"];
+
+/// Upper bounds in seconds of the `ledger_process_duration_seconds` buckets.
+pub const LATENCY_BUCKETS: [f64; 12] = [
+    0.000_001,
+    0.000_002_5,
+    0.000_005,
+    0.000_01,
+    0.000_025,
+    0.000_05,
+    0.000_1,
+    0.000_25,
+    0.000_5,
+    0.001,
+    0.005,
+    0.01,
+];
+
+/// Counters and latency histogram maintained by an [`Engine`](crate::Engine).
+///
+/// All updates are relaxed atomic increments, so recording never blocks a
+/// transaction.
+#[derive(Debug, Default)]
+pub struct EngineMetrics {
+    /// Counts per transaction type and [`RESULTS`] entry.
+    transactions: [[AtomicU64; RESULTS.len()]; TYPES.len()],
+    /// Rejections per [`TransactionError::ALL`] entry.
+    errors: [AtomicU64; TransactionError::ALL.len()],
+    /// Duration of [`Engine::process`](crate::Engine::process).
+    latency: Histogram,
+}
+
+impl EngineMetrics {
+    /// Creates zeroed metrics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts the outcome of a transaction.
+    pub(crate) fn record(
+        &self,
+        transaction: &TransactionType,
+        result: &Result<(), TransactionError>,
+    ) {
+        let outcome = usize::from(result.is_err());
+        self.transactions[type_index(transaction)][outcome].fetch_add(1, Ordering::Relaxed);
+        if let Err(error) = result {
+            let index = TransactionError::ALL
+                .iter()
+                .position(|known| known == error)
+                .expect("every error is in TransactionError::ALL");
+            self.errors[index].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Counts a valid transaction of an atomic batch that was rolled back.
+    pub(crate) fn record_rolled_back(&self, transaction: &TransactionType) {
+        self.transactions[type_index(transaction)][2].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records how long one [`Engine::process`](crate::Engine::process) call took.
+    pub(crate) fn observe_latency(&self, elapsed: Duration) {
+        self.latency.observe(elapsed);
+    }
+
+    /// Number of transactions of `kind` (e.g. `deposit`) with `result`
+    /// `applied`, `rejected` or `rolled_back`.
+    pub fn transactions(&self, kind: &str, result: &str) -> u64 {
+        let kind = TYPES.iter().position(|known| *known == kind);
+        let result = RESULTS.iter().position(|known| *known == result);
+        match (kind, result) {
+            (Some(kind), Some(result)) => self.transactions[kind][result].load(Ordering::Relaxed),
+            _ => 0,
+        }
+    }
+
+    /// Number of transactions rejected with `error`.
+    pub fn errors(&self, error: &TransactionError) -> u64 {
+        TransactionError::ALL
+            .iter()
+            .position(|known| known == error)
+            .map_or(0, |index| self.errors[index].load(Ordering::Relaxed))
+    }
+
+    /// Number of timed [`Engine::process`](crate::Engine::process) calls.
+    pub fn processed(&self) -> u64 {
+        self.latency.count.load(Ordering::Relaxed)
+    }
+
+    /// Renders the counters, histogram and account gauges over `accounts`.
+    pub fn render(&self, accounts: &[AccountSnapshot]) -> String {
+        let mut out = String::new();
+        self.write(&mut out, accounts)
+            .expect("writing to a String cannot fail");
+        out
+    }
+
+    fn write(&self, out: &mut String, accounts: &[AccountSnapshot]) -> fmt::Result {
+        header(
+            out,
+            "ledger_transactions_total",
+            "counter",
+            "Transactions processed, by type and result.",
+        )?;
+        for (kind, counts) in TYPES.iter().zip(&self.transactions) {
+            for (result, count) in RESULTS.iter().zip(counts) {
+                writeln!(
+                    out,
+                    "ledger_transactions_total{{type=\"{kind}\",result=\"{result}\"}} {}",
+                    count.load(Ordering::Relaxed)
+                )?;
+            }
+        }
+
+        header(
+            out,
+            "ledger_transaction_errors_total",
+            "counter",
+            "Rejected transactions, by error code.",
+        )?;
+        for (error, count) in TransactionError::ALL.iter().zip(&self.errors) {
+            writeln!(
+                out,
+                "ledger_transaction_errors_total{{code=\"{}\"}} {}",
+                error.code(),
+                count.load(Ordering::Relaxed)
+            )?;
+        }
+
+        header(
+            out,
+            "ledger_process_duration_seconds",
+            "histogram",
+            "Time spent in Engine::process.",
+        )?;
+        self.latency.write(out, "ledger_process_duration_seconds")?;
+
+        let locked = accounts.iter().filter(|account| account.locked).count();
+        let held = sum(accounts.iter().map(|account| account.held));
+        let liabilities = sum(accounts.iter().map(|account| account.total));
+        gauge(out, "ledger_accounts", "Client accounts.", accounts.len())?;
+        gauge(
+            out,
+            "ledger_accounts_locked",
+            "Accounts locked after a chargeback.",
+            locked,
+        )?;
+        gauge(out, "ledger_held_funds", "Sum of held funds.", held)?;
+        gauge(
+            out,
+            "ledger_liabilities",
+            "Sum of account totals owed to clients.",
+            liabilities,
+        )
+    }
+}
+
+/// Index of `transaction`'s type in [`TYPES`].
+fn type_index(transaction: &TransactionType) -> usize {
+    match transaction {
+        TransactionType::Deposit { .. } => 0,
+        TransactionType::Withdrawal { .. } => 1,
+        TransactionType::Dispute { .. } => 2,
+        TransactionType::Resolve { .. } => 3,
+        TransactionType::Chargeback { .. } => 4,
+    }
+}
+
+/// Sums balances for a gauge: exactly while the sum fits a [`Decimal`], as a
+/// float (which is all Prometheus keeps anyway) once it overflows.
+fn sum(values: impl Iterator<Item = Decimal> + Clone) -> String {
+    match values.clone().try_fold(Decimal::ZERO, Decimal::checked_add) {
+        Some(sum) => sum.normalize().to_string(),
+        None => {
+            let sum: f64 = values.map(|value| value.to_f64().unwrap_or(0.0)).sum();
+            format!("{sum:e}")
+        }
+    }
+}
+
+/// Writes the `# HELP` and `# TYPE` lines of a metric family.
+pub(crate) fn header(out: &mut String, name: &str, kind: &str, help: &str) -> fmt::Result {
+    writeln!(out, "# HELP {name} {help}")?;
+    writeln!(out, "# TYPE {name} {kind}")
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: impl fmt::Display) -> fmt::Result {
+    header(out, name, "gauge", help)?;
+    writeln!(out, "{name} {value}")
+}
+
+/// A latency histogram over [`LATENCY_BUCKETS`].
+#[derive(Debug, Default)]
+pub(crate) struct Histogram {
+    /// Observations per bucket, not cumulative; the last is `+Inf`.
+    buckets: [AtomicU64; LATENCY_BUCKETS.len() + 1],
+    /// Sum of observations in nanoseconds.
+    sum_nanos: AtomicU64,
+    count: AtomicU64,
+}
+
+impl Histogram {
+    pub(crate) fn observe(&self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+        self.sum_nanos.fetch_add(nanos, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Writes the `_bucket`, `_sum` and `_count` series of `name`.
+    pub(crate) fn write(&self, out: &mut String, name: &str) -> fmt::Result {
+        let mut cumulative = 0;
+        for (index, count) in self.buckets.iter().enumerate() {
+            cumulative += count.load(Ordering::Relaxed);
+            match LATENCY_BUCKETS.get(index) {
+                Some(bound) => writeln!(out, "{name}_bucket{{le=\"{bound}\"}} {cumulative}")?,
+                None => writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {cumulative}")?,
+            }
+        }
+        let sum = Duration::from_nanos(self.sum_nanos.load(Ordering::Relaxed));
+        writeln!(out, "{name}_sum {}", sum.as_secs_f64())?;
+        writeln!(out, "{name}_count {}", self.count.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{ClientId, TransactionId};
+    use rust_decimal_macros::dec;
+
+    fn deposit() -> TransactionType {
+        TransactionType::Deposit {
+            client_id: ClientId(1),
+            transaction_id: TransactionId(1),
+            amount: dec!(1),
+        }
+    }
+
+    #[test]
+    fn counts_outcomes_by_type_and_error() {
+        let metrics = EngineMetrics::new();
+        metrics.record(&deposit(), &Ok(()));
+        metrics.record(&deposit(), &Err(TransactionError::DuplicateTransaction));
+        metrics.record_rolled_back(&deposit());
+
+        assert_eq!(metrics.transactions("deposit", "applied"), 1);
+        assert_eq!(metrics.transactions("deposit", "rejected"), 1);
+        assert_eq!(metrics.transactions("deposit", "rolled_back"), 1);
+        assert_eq!(metrics.transactions("withdrawal", "applied"), 0);
+        assert_eq!(metrics.errors(&TransactionError::DuplicateTransaction), 1);
+
+        let text = metrics.render(&[]);
+        assert!(
+            text.contains("ledger_transactions_total{type=\"deposit\",result=\"applied\"} 1\n")
+        );
+        assert!(
+            text.contains("ledger_transaction_errors_total{code=\"DUPLICATE_TRANSACTION\"} 1\n")
+        );
+        assert!(text.contains("ledger_transaction_errors_total{code=\"OVERFLOW\"} 0\n"));
+    }
+
+    #[test]
+    fn histogram_buckets_are_cumulative() {
+        let histogram = Histogram::default();
+        histogram.observe(Duration::from_nanos(500));
+        histogram.observe(Duration::from_micros(30));
+        histogram.observe(Duration::from_secs(1));
+
+        let mut out = String::new();
+        histogram.write(&mut out, "h").unwrap();
+        assert!(out.contains("h_bucket{le=\"0.000001\"} 1\n"));
+        assert!(out.contains("h_bucket{le=\"0.000025\"} 1\n"));
+        assert!(out.contains("h_bucket{le=\"0.00005\"} 2\n"));
+        assert!(out.contains("h_bucket{le=\"0.01\"} 2\n"));
+        assert!(out.contains("h_bucket{le=\"+Inf\"} 3\n"));
+        assert!(out.contains("h_sum 1.0000305\n"));
+        assert!(out.contains("h_count 3\n"));
+    }
+
+    #[test]
+    fn gauges_aggregate_accounts() {
+        let account = |client, held, total, locked| AccountSnapshot {
+            client_id: ClientId(client),
+            available: total - held,
+            held,
+            total,
+            locked,
+            version: 0,
+        };
+        let accounts = [
+            account(1, dec!(2.5), dec!(10), false),
+            account(2, dec!(0), dec!(4.25), true),
+        ];
+
+        let text = EngineMetrics::new().render(&accounts);
+        assert!(text.contains("# TYPE ledger_accounts gauge\nledger_accounts 2\n"));
+        assert!(text.contains("ledger_accounts_locked 1\n"));
+        assert!(text.contains("ledger_held_funds 2.5\n"));
+        assert!(text.contains("ledger_liabilities 14.25\n"));
+    }
+
+    #[test]
+    fn overflowing_gauges_fall_back_to_floats() {
+        let huge = dec!(50000000000000000000000000000);
+        let account = |client| AccountSnapshot {
+            client_id: ClientId(client),
+            available: huge,
+            held: dec!(0),
+            total: huge,
+            locked: false,
+            version: 1,
+        };
+
+        let text = EngineMetrics::new().render(&[account(1), account(2)]);
+        assert!(text.contains("ledger_held_funds 0\n"));
+        assert!(text.contains("ledger_liabilities 1e29\n"), "{text}");
+    }
+}
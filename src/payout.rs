@@ -0,0 +1,692 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Payout file export.
+//!
+//! Withdrawals accepted by the engine must be paid out by the bank. A
+//! [`PayoutCollector`] records the accepted withdrawals of a processing run
+//! (rejected ones never enter it); [`PayoutFile::build`] joins them with
+//! beneficiary bank details from a [`Beneficiaries`] table, validates every
+//! entry and computes the batch totals, and [`PayoutFile::write`] renders a
+//! SEPA credit transfer initiation (pain.001.001.03) or a NACHA PPD credit
+//! file.
+//!
+//! Originator details come from a [`PayoutConfig`] file:
+//!
+//! ```toml
+//! execution_date = "2025-01-06"
+//!
+//! [sepa]
+//! debtor_name = "Ledger Demo Ltd"
+//! debtor_iban = "DE89370400440532013000"
+//! debtor_bic = "COBADEFFXXX"
+//!
+//! [nacha]
+//! immediate_destination = "091000019"
+//! immediate_destination_name = "FIRST BANK"
+//! immediate_origin = "1234567890"
+//! immediate_origin_name = "LEDGER DEMO"
+//! company_name = "LEDGER DEMO"
+//! company_id = "1234567890"
+//! originating_dfi = "09100001"
+//! ```
+
+mod nacha;
+mod pain001;
+
+use crate::base::{ClientId, TransactionId};
+use crate::config::load_file;
+use crate::date::{is_date, is_time, utc_now};
+use crate::error::{ConfigError, PayoutError, TransactionError};
+use crate::transaction::TransactionType;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+/// An accepted withdrawal to be paid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Payout {
+    /// Client receiving the funds.
+    pub client_id: ClientId,
+    /// Withdrawal transaction ID, used as the payment reference.
+    pub transaction_id: TransactionId,
+    /// Amount withdrawn.
+    pub amount: Decimal,
+}
+
+/// Records the withdrawals accepted during a run.
+///
+/// Feed it every transaction with the engine's result. A withdrawal that is
+/// charged back later in the same run (possible under
+/// [`DisputePolicy::DepositsAndWithdrawals`](crate::DisputePolicy::DepositsAndWithdrawals))
+/// has been returned to the client and is dropped again.
+#[derive(Debug, Clone, Default)]
+pub struct PayoutCollector {
+    payouts: Vec<Option<Payout>>,
+    index: HashMap<(ClientId, TransactionId), usize>,
+}
+
+impl PayoutCollector {
+    /// Creates an empty collector.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the engine's outcome for a transaction.
+    pub fn record(&mut self, transaction: &TransactionType, result: &Result<(), TransactionError>) {
+        if result.is_err() {
+            return;
+        }
+        match *transaction {
+            TransactionType::Withdrawal {
+                client_id,
+                transaction_id,
+                amount,
+            } => {
+                self.index
+                    .insert((client_id, transaction_id), self.payouts.len());
+                self.payouts.push(Some(Payout {
+                    client_id,
+                    transaction_id,
+                    amount,
+                }));
+            }
+            TransactionType::Chargeback {
+                client_id,
+                transaction_id,
+            } => {
+                if let Some(index) = self.index.remove(&(client_id, transaction_id)) {
+                    self.payouts[index] = None;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the payouts in the order the withdrawals were accepted.
+    pub fn into_payouts(self) -> Vec<Payout> {
+        self.payouts.into_iter().flatten().collect()
+    }
+}
+
+/// Bank account type of a NACHA beneficiary.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccountType {
+    /// Checking (demand deposit) account.
+    #[default]
+    Checking,
+    /// Savings account.
+    Savings,
+}
+
+/// Bank details of a client receiving payouts.
+///
+/// SEPA needs `iban` (and optionally `bic`); NACHA needs `routing_number`
+/// and `account_number`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Beneficiary {
+    /// Client ID.
+    pub client: u16,
+    /// Account holder name.
+    pub name: String,
+    /// International bank account number (SEPA).
+    #[serde(default)]
+    pub iban: Option<String>,
+    /// Bank identifier code (SEPA, optional).
+    #[serde(default)]
+    pub bic: Option<String>,
+    /// Nine-digit ABA routing number (NACHA).
+    #[serde(default)]
+    pub routing_number: Option<String>,
+    /// Bank account number (NACHA).
+    #[serde(default)]
+    pub account_number: Option<String>,
+    /// Account type (NACHA).
+    #[serde(default)]
+    pub account_type: Option<AccountType>,
+}
+
+/// Beneficiary details by client.
+#[derive(Debug, Clone, Default)]
+pub struct Beneficiaries {
+    by_client: HashMap<ClientId, Beneficiary>,
+}
+
+impl Beneficiaries {
+    /// Creates an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a CSV table with a `client` and `name` column and any of
+    /// `iban`, `bic`, `routing_number`, `account_number` and `account_type`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PayoutError::Beneficiaries`] if the CSV is malformed.
+    pub fn from_csv<R: Read>(reader: R) -> Result<Self, PayoutError> {
+        let mut table = Self::new();
+        let mut rdr = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(reader);
+        for row in rdr.deserialize() {
+            table.insert(row?);
+        }
+        Ok(table)
+    }
+
+    /// Adds or replaces a beneficiary.
+    pub fn insert(&mut self, beneficiary: Beneficiary) {
+        self.by_client
+            .insert(ClientId(beneficiary.client), beneficiary);
+    }
+
+    /// Returns the beneficiary of a client.
+    pub fn get(&self, client_id: ClientId) -> Option<&Beneficiary> {
+        self.by_client.get(&client_id)
+    }
+}
+
+/// Payout file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayoutFormat {
+    /// SEPA credit transfer initiation, pain.001.001.03 XML.
+    Pain001,
+    /// NACHA fixed-width ACH file with one PPD credit batch.
+    Nacha,
+}
+
+impl PayoutFormat {
+    /// Guesses the format from a file extension: `.xml` is pain.001 and
+    /// `.ach` and `.nacha` are NACHA.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "xml" => Some(Self::Pain001),
+            "ach" | "nacha" => Some(Self::Nacha),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for PayoutFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pain001" | "pain.001" => Ok(Self::Pain001),
+            "nacha" => Ok(Self::Nacha),
+            other => Err(format!(
+                "unknown payout format '{other}' (expected pain001 or nacha)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for PayoutFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Pain001 => "pain001",
+            Self::Nacha => "nacha",
+        })
+    }
+}
+
+/// Originator details for SEPA payouts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SepaOriginator {
+    /// Name of the paying (debtor) account holder.
+    pub debtor_name: String,
+    /// IBAN the payouts are debited from.
+    pub debtor_iban: String,
+    /// BIC of the debtor's bank.
+    #[serde(default)]
+    pub debtor_bic: Option<String>,
+    /// Transfer currency; SEPA credit transfers must be in `EUR`, the default.
+    #[serde(default = "SepaOriginator::default_currency")]
+    pub currency: String,
+}
+
+impl SepaOriginator {
+    fn default_currency() -> String {
+        "EUR".to_string()
+    }
+}
+
+/// Originator details for NACHA payouts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NachaOriginator {
+    /// Routing number of the receiving ACH operator or bank (9 digits).
+    pub immediate_destination: String,
+    /// Name of the immediate destination.
+    pub immediate_destination_name: String,
+    /// Originator identification, usually `1` plus the tax ID (10 characters).
+    pub immediate_origin: String,
+    /// Name of the immediate origin.
+    pub immediate_origin_name: String,
+    /// Company name shown to receivers.
+    pub company_name: String,
+    /// Company identification (10 characters).
+    pub company_id: String,
+    /// First eight digits of the originating bank's routing number.
+    pub originating_dfi: String,
+    /// Entry description shown to receivers.
+    #[serde(default = "NachaOriginator::default_entry_description")]
+    pub entry_description: String,
+    /// File ID modifier distinguishing files created on the same day.
+    #[serde(default = "NachaOriginator::default_file_id_modifier")]
+    pub file_id_modifier: char,
+}
+
+impl NachaOriginator {
+    fn default_entry_description() -> String {
+        "PAYOUT".to_string()
+    }
+
+    fn default_file_id_modifier() -> char {
+        'A'
+    }
+}
+
+/// Payout run settings: dates, message ID and per-format originator details.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PayoutConfig {
+    /// Requested execution (effective entry) date, `YYYY-MM-DD`.
+    pub execution_date: String,
+    /// File creation time, `YYYY-MM-DDTHH:MM:SS` (UTC); defaults to now.
+    #[serde(default)]
+    pub created: Option<String>,
+    /// Message identification; defaults to `PAYOUT-` plus the creation time.
+    #[serde(default)]
+    pub message_id: Option<String>,
+    /// SEPA originator, required for pain.001.
+    #[serde(default)]
+    pub sepa: Option<SepaOriginator>,
+    /// NACHA originator, required for NACHA files.
+    #[serde(default)]
+    pub nacha: Option<NachaOriginator>,
+}
+
+impl PayoutConfig {
+    /// Loads a payout config file, choosing the format by extension (`.toml`
+    /// or `.json`), and validates its dates.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ConfigError`] if the file cannot be read or parsed, or a
+    /// date is malformed.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let config: Self = load_file(path.as_ref())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks the date formats.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !is_date(&self.execution_date) {
+            return Err(ConfigError::Invalid(format!(
+                "execution_date must be YYYY-MM-DD, got '{}'",
+                self.execution_date
+            )));
+        }
+        if let Some(created) = &self.created {
+            let valid = created
+                .split_once('T')
+                .is_some_and(|(date, time)| is_date(date) && is_time(time));
+            if !valid {
+                return Err(ConfigError::Invalid(format!(
+                    "created must be YYYY-MM-DDTHH:MM:SS, got '{created}'"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the creation time, defaulting to the current UTC time.
+    fn created_at(&self) -> String {
+        self.created.clone().unwrap_or_else(utc_now)
+    }
+}
+
+/// A validated payout: the withdrawal, its beneficiary and amount in cents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayoutEntry {
+    /// The accepted withdrawal.
+    pub payout: Payout,
+    /// Who receives it.
+    pub beneficiary: Beneficiary,
+    /// Amount in minor units.
+    pub cents: i64,
+}
+
+/// A validated batch of payouts ready to be written.
+#[derive(Debug, Clone)]
+pub struct PayoutFile {
+    originator: Originator,
+    execution_date: String,
+    created: String,
+    message_id: String,
+    entries: Vec<PayoutEntry>,
+    total_cents: i64,
+}
+
+impl PayoutFile {
+    /// Validates `payouts` for `format` and computes the batch totals.
+    ///
+    /// # Errors
+    ///
+    /// - [`PayoutError::Empty`] - There are no payouts.
+    /// - [`PayoutError::Config`] - The config lacks the format's originator
+    ///   section or it is invalid.
+    /// - [`PayoutError::MissingBeneficiary`] - A client has no beneficiary.
+    /// - [`PayoutError::InvalidBeneficiary`] - Bank details the format needs
+    ///   are missing or fail their checksum.
+    /// - [`PayoutError::InvalidAmount`] - An amount has fractions of a cent or
+    ///   exceeds the per-entry limit.
+    /// - [`PayoutError::TotalsOverflow`] - The control sum exceeds the format's
+    ///   limit.
+    pub fn build(
+        format: PayoutFormat,
+        config: &PayoutConfig,
+        beneficiaries: &Beneficiaries,
+        payouts: &[Payout],
+    ) -> Result<Self, PayoutError> {
+        config.validate()?;
+        if payouts.is_empty() {
+            return Err(PayoutError::Empty);
+        }
+        let originator = match format {
+            PayoutFormat::Pain001 => Originator::Sepa(pain001::check_originator(config)?),
+            PayoutFormat::Nacha => Originator::Nacha(nacha::check_originator(config)?),
+        };
+
+        let (max_entry, max_total) = match format {
+            PayoutFormat::Pain001 => (pain001::MAX_ENTRY_CENTS, pain001::MAX_TOTAL_CENTS),
+            PayoutFormat::Nacha => (nacha::MAX_ENTRY_CENTS, nacha::MAX_TOTAL_CENTS),
+        };
+
+        let mut entries = Vec::with_capacity(payouts.len());
+        let mut total_cents: i64 = 0;
+        for payout in payouts {
+            let beneficiary = beneficiaries
+                .get(payout.client_id)
+                .ok_or(PayoutError::MissingBeneficiary(payout.client_id.0))?;
+            match format {
+                PayoutFormat::Pain001 => pain001::check_beneficiary(beneficiary)?,
+                PayoutFormat::Nacha => nacha::check_beneficiary(beneficiary)?,
+            }
+
+            let cents = to_cents(payout, max_entry)?;
+            total_cents = total_cents
+                .checked_add(cents)
+                .filter(|total| *total <= max_total)
+                .ok_or_else(|| {
+                    PayoutError::TotalsOverflow(format!(
+                        "control sum exceeds {}",
+                        Decimal::new(max_total, 2)
+                    ))
+                })?;
+            entries.push(PayoutEntry {
+                payout: *payout,
+                beneficiary: beneficiary.clone(),
+                cents,
+            });
+        }
+
+        let created = config.created_at();
+        let message_id = config.message_id.clone().unwrap_or_else(|| {
+            let digits: String = created.chars().filter(char::is_ascii_digit).collect();
+            format!("PAYOUT-{digits}")
+        });
+        Ok(Self {
+            originator,
+            execution_date: config.execution_date.clone(),
+            created,
+            message_id,
+            entries,
+            total_cents,
+        })
+    }
+
+    /// Returns the format the file will be written in.
+    pub fn format(&self) -> PayoutFormat {
+        match self.originator {
+            Originator::Sepa(_) => PayoutFormat::Pain001,
+            Originator::Nacha(_) => PayoutFormat::Nacha,
+        }
+    }
+
+    /// Returns the validated entries in payout order.
+    pub fn entries(&self) -> &[PayoutEntry] {
+        &self.entries
+    }
+
+    /// Returns the number of payments.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if there are no payments (never the case for a built file).
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the sum of all payment amounts.
+    pub fn control_sum(&self) -> Decimal {
+        Decimal::new(self.total_cents, 2)
+    }
+
+    /// Writes the file.
+    pub fn write<W: Write>(&self, writer: W) -> Result<(), PayoutError> {
+        match &self.originator {
+            Originator::Sepa(originator) => pain001::write(self, originator, writer),
+            Originator::Nacha(originator) => nacha::write(self, originator, writer),
+        }
+    }
+}
+
+/// The originator section matching a file's format.
+#[derive(Debug, Clone)]
+enum Originator {
+    Sepa(SepaOriginator),
+    Nacha(NachaOriginator),
+}
+
+/// Converts a payout amount to cents, rejecting fractions of a cent and
+/// amounts above `max_cents`.
+fn to_cents(payout: &Payout, max_cents: i64) -> Result<i64, PayoutError> {
+    let invalid = |reason: String| PayoutError::InvalidAmount {
+        transaction: payout.transaction_id.0,
+        reason,
+    };
+    let out_of_range = || {
+        invalid(format!(
+            "amount {} is outside 0.01..={}",
+            payout.amount,
+            Decimal::new(max_cents, 2)
+        ))
+    };
+    let cents = payout
+        .amount
+        .checked_mul(Decimal::ONE_HUNDRED)
+        .ok_or_else(out_of_range)?;
+    if !cents.fract().is_zero() {
+        return Err(invalid(format!(
+            "amount {} has fractions of a cent",
+            payout.amount
+        )));
+    }
+    cents
+        .to_i64()
+        .filter(|cents| (1..=max_cents).contains(cents))
+        .ok_or_else(out_of_range)
+}
+
+/// Checks an IBAN's format and ISO 13616 mod-97 checksum.
+fn is_valid_iban(iban: &str) -> bool {
+    let iban: String = iban.chars().filter(|c| !c.is_whitespace()).collect();
+    let well_formed = (15..=34).contains(&iban.len())
+        && iban.chars().all(|c| c.is_ascii_alphanumeric())
+        && iban[..2].chars().all(|c| c.is_ascii_uppercase())
+        && iban[2..4].chars().all(|c| c.is_ascii_digit());
+    if !well_formed {
+        return false;
+    }
+    let rearranged = iban[4..].chars().chain(iban[..4].chars());
+    let mut remainder: u32 = 0;
+    for c in rearranged {
+        let value = c.to_digit(36).unwrap_or(0);
+        remainder = if value >= 10 {
+            (remainder * 100 + value) % 97
+        } else {
+            (remainder * 10 + value) % 97
+        };
+    }
+    remainder == 1
+}
+
+/// Replaces characters outside `allowed` with spaces and truncates to `max`
+/// characters.
+fn sanitize(text: &str, max: usize, allowed: impl Fn(char) -> bool) -> String {
+    text.trim()
+        .chars()
+        .map(|c| match c {
+            c if allowed(c) => c,
+            c => fold_accent(c).filter(|&base| allowed(base)).unwrap_or(' '),
+        })
+        .take(max)
+        .collect()
+}
+
+/// Returns the unaccented base letter of a common Latin-1 letter, e.g. `ü`
+/// becomes `u`.
+fn fold_accent(c: char) -> Option<char> {
+    let base = match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => 'a',
+        'À' | 'Á' | 'Â' | 'Ã' | 'Ä' | 'Å' => 'A',
+        'ç' => 'c',
+        'Ç' => 'C',
+        'è' | 'é' | 'ê' | 'ë' => 'e',
+        'È' | 'É' | 'Ê' | 'Ë' => 'E',
+        'ì' | 'í' | 'î' | 'ï' => 'i',
+        'Ì' | 'Í' | 'Î' | 'Ï' => 'I',
+        'ñ' => 'n',
+        'Ñ' => 'N',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' => 'o',
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ö' | 'Ø' => 'O',
+        'ù' | 'ú' | 'û' | 'ü' => 'u',
+        'Ù' | 'Ú' | 'Û' | 'Ü' => 'U',
+        'ý' | 'ÿ' => 'y',
+        'Ý' => 'Y',
+        _ => return None,
+    };
+    Some(base)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn withdrawal(client: u16, tx: u32, amount: Decimal) -> TransactionType {
+        TransactionType::Withdrawal {
+            client_id: ClientId(client),
+            transaction_id: TransactionId(tx),
+            amount,
+        }
+    }
+
+    #[test]
+    fn collector_keeps_only_accepted_withdrawals() {
+        let mut collector = PayoutCollector::new();
+        collector.record(&withdrawal(1, 1, dec!(5)), &Ok(()));
+        collector.record(
+            &withdrawal(1, 2, dec!(500)),
+            &Err(TransactionError::InsufficientFunds),
+        );
+        collector.record(&withdrawal(2, 3, dec!(7)), &Ok(()));
+        collector.record(
+            &TransactionType::Deposit {
+                client_id: ClientId(1),
+                transaction_id: TransactionId(4),
+                amount: dec!(1),
+            },
+            &Ok(()),
+        );
+        // Charged back in the same run: the funds went back to the client.
+        collector.record(
+            &TransactionType::Chargeback {
+                client_id: ClientId(2),
+                transaction_id: TransactionId(3),
+            },
+            &Ok(()),
+        );
+
+        assert_eq!(
+            collector.into_payouts(),
+            vec![Payout {
+                client_id: ClientId(1),
+                transaction_id: TransactionId(1),
+                amount: dec!(5),
+            }]
+        );
+    }
+
+    #[test]
+    fn amounts_must_be_whole_cents_within_limits() {
+        let payout = |amount| Payout {
+            client_id: ClientId(1),
+            transaction_id: TransactionId(9),
+            amount,
+        };
+        assert_eq!(to_cents(&payout(dec!(12.3)), 10_000).unwrap(), 1_230);
+        assert_eq!(to_cents(&payout(dec!(12.3000)), 10_000).unwrap(), 1_230);
+        assert!(matches!(
+            to_cents(&payout(dec!(0.005)), 10_000),
+            Err(PayoutError::InvalidAmount { transaction: 9, .. })
+        ));
+        assert!(to_cents(&payout(dec!(100.01)), 10_000).is_err());
+        assert!(matches!(
+            to_cents(&payout(dec!(50000000000000000000000000000)), i64::MAX),
+            Err(PayoutError::InvalidAmount { transaction: 9, .. })
+        ));
+    }
+
+    #[test]
+    fn validates_ibans() {
+        assert!(is_valid_iban("DE89 3704 0044 0532 0130 00"));
+        assert!(is_valid_iban("GB82WEST12345698765432"));
+        assert!(!is_valid_iban("DE89370400440532013001"));
+        assert!(!is_valid_iban("not an iban"));
+    }
+
+    #[test]
+    fn sanitize_folds_accents_and_blanks_the_rest() {
+        let ascii = |c: char| c.is_ascii_alphanumeric() || c == ' ';
+        assert_eq!(sanitize(" Anna Müller ", 35, ascii), "Anna Muller");
+        assert_eq!(sanitize("Zoë €5", 35, ascii), "Zoe  5");
+        assert_eq!(sanitize("Ångström", 3, ascii), "Ang");
+    }
+}
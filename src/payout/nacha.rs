@@ -0,0 +1,251 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! NACHA (ACH) file writer.
+//!
+//! Writes one PPD batch of credits (service class 220): a file header, batch
+//! header, one entry detail record per payout, the batch control and the file
+//! control, padded with `9` records to a multiple of ten. Every record is 94
+//! characters. Entry hash, counts and credit totals are computed from the
+//! validated entries.
+
+use super::{AccountType, Beneficiary, NachaOriginator, PayoutConfig, PayoutFile, sanitize};
+use crate::error::{ConfigError, PayoutError};
+use std::io::Write;
+
+/// Largest single entry, in cents (10-digit amount field).
+pub(super) const MAX_ENTRY_CENTS: i64 = 9_999_999_999;
+
+/// Largest batch or file total, in cents (12-digit amount field).
+pub(super) const MAX_TOTAL_CENTS: i64 = 999_999_999_999;
+
+/// Length of every record.
+const RECORD_LEN: usize = 94;
+
+/// Records per block.
+const BLOCKING_FACTOR: usize = 10;
+
+/// Service class code for credits only.
+const CREDITS_ONLY: &str = "220";
+
+/// Returns the NACHA originator, checking its identifiers.
+pub(super) fn check_originator(config: &PayoutConfig) -> Result<NachaOriginator, ConfigError> {
+    let nacha = config
+        .nacha
+        .clone()
+        .ok_or_else(|| ConfigError::Invalid("NACHA requires a [nacha] section".to_string()))?;
+    let invalid = |field: &str, expected: &str| {
+        Err(ConfigError::Invalid(format!(
+            "nacha.{field} must be {expected}"
+        )))
+    };
+    if !is_valid_routing(&nacha.immediate_destination) {
+        return invalid("immediate_destination", "a valid 9-digit routing number");
+    }
+    if !is_digits(&nacha.originating_dfi, 8) {
+        return invalid("originating_dfi", "8 digits");
+    }
+    if nacha.immediate_origin.len() != 10 {
+        return invalid("immediate_origin", "10 characters");
+    }
+    if nacha.company_id.is_empty() || nacha.company_id.len() > 10 {
+        return invalid("company_id", "1 to 10 characters");
+    }
+    if !nacha.file_id_modifier.is_ascii_alphanumeric() {
+        return invalid("file_id_modifier", "A-Z or 0-9");
+    }
+    Ok(nacha)
+}
+
+/// Checks that a beneficiary has a name, a valid routing number and an
+/// account number that fits the 17-character field.
+pub(super) fn check_beneficiary(beneficiary: &Beneficiary) -> Result<(), PayoutError> {
+    let invalid = |reason: String| PayoutError::InvalidBeneficiary {
+        client: beneficiary.client,
+        reason,
+    };
+    if beneficiary.name.trim().is_empty() {
+        return Err(invalid("name is empty".to_string()));
+    }
+    match beneficiary.routing_number.as_deref() {
+        None => return Err(invalid("NACHA requires a routing_number".to_string())),
+        Some(routing) if !is_valid_routing(routing) => {
+            return Err(invalid(format!(
+                "'{routing}' is not a valid routing number"
+            )));
+        }
+        Some(_) => {}
+    }
+    match beneficiary.account_number.as_deref() {
+        None => Err(invalid("NACHA requires an account_number".to_string())),
+        Some(account)
+            if account.is_empty()
+                || account.len() > 17
+                || !account
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-') =>
+        {
+            Err(invalid(format!(
+                "'{account}' is not a valid account number"
+            )))
+        }
+        Some(_) => Ok(()),
+    }
+}
+
+/// Writes the file.
+pub(super) fn write<W: Write>(
+    file: &PayoutFile,
+    originator: &NachaOriginator,
+    mut w: W,
+) -> Result<(), PayoutError> {
+    let (date, time) = file.created.split_once('T').unwrap_or_default();
+    let creation_date = yymmdd(date);
+    let creation_time: String = time.chars().filter(char::is_ascii_digit).take(4).collect();
+    let effective_date = yymmdd(&file.execution_date);
+    let odfi = &originator.originating_dfi;
+    let batch_number = num(1, 7);
+
+    let mut records = Vec::with_capacity(file.entries.len() + 4);
+    records.push(format!(
+        "101 {}{}{}{}{}094101{}{}{}",
+        originator.immediate_destination,
+        alpha(&originator.immediate_origin, 10),
+        creation_date,
+        creation_time,
+        originator.file_id_modifier.to_ascii_uppercase(),
+        alpha(&originator.immediate_destination_name, 23),
+        alpha(&originator.immediate_origin_name, 23),
+        alpha(&file.message_id, 8),
+    ));
+    records.push(format!(
+        "5{CREDITS_ONLY}{}{}{}PPD{}{}{}   1{odfi}{batch_number}",
+        alpha(&originator.company_name, 16),
+        alpha("", 20),
+        alpha(&originator.company_id, 10),
+        alpha(&originator.entry_description, 10),
+        alpha("", 6),
+        effective_date,
+    ));
+
+    let mut entry_hash: u64 = 0;
+    for (sequence, entry) in file.entries.iter().enumerate() {
+        let beneficiary = &entry.beneficiary;
+        let routing = beneficiary.routing_number.as_deref().unwrap_or_default();
+        entry_hash += routing[..8].parse::<u64>().unwrap_or(0);
+        let transaction_code = match beneficiary.account_type.unwrap_or_default() {
+            AccountType::Checking => "22",
+            AccountType::Savings => "32",
+        };
+        records.push(format!(
+            "6{transaction_code}{routing}{}{}{}{}  0{odfi}{}",
+            alpha(
+                beneficiary.account_number.as_deref().unwrap_or_default(),
+                17
+            ),
+            num(entry.cents as u64, 10),
+            alpha(&beneficiary.client.to_string(), 15),
+            alpha(&beneficiary.name, 22),
+            num(sequence as u64 + 1, 7),
+        ));
+    }
+
+    let entry_count = num(file.entries.len() as u64, 6);
+    let entry_hash = num(entry_hash % 10_000_000_000, 10);
+    let total_credit = num(file.total_cents as u64, 12);
+    let total_debit = num(0, 12);
+    records.push(format!(
+        "8{CREDITS_ONLY}{entry_count}{entry_hash}{total_debit}{total_credit}{}{}{}{odfi}{batch_number}",
+        alpha(&originator.company_id, 10),
+        alpha("", 19),
+        alpha("", 6),
+    ));
+
+    let record_count = records.len() + 1;
+    let block_count = record_count.div_ceil(BLOCKING_FACTOR);
+    records.push(format!(
+        "9{}{}{}{entry_hash}{total_debit}{total_credit}{}",
+        num(1, 6),
+        num(block_count as u64, 6),
+        num(file.entries.len() as u64, 8),
+        alpha("", 39),
+    ));
+    records.resize(block_count * BLOCKING_FACTOR, "9".repeat(RECORD_LEN));
+
+    for record in &records {
+        debug_assert_eq!(record.len(), RECORD_LEN, "{record}");
+        writeln!(w, "{record}")?;
+    }
+    Ok(())
+}
+
+/// Returns `true` if `s` is exactly `len` ASCII digits.
+fn is_digits(s: &str, len: usize) -> bool {
+    s.len() == len && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Checks an ABA routing number: nine digits with a valid check digit.
+fn is_valid_routing(routing: &str) -> bool {
+    if !is_digits(routing, 9) {
+        return false;
+    }
+    let sum: u32 = routing
+        .bytes()
+        .map(|b| u32::from(b - b'0'))
+        .zip([3, 7, 1, 3, 7, 1, 3, 7, 1])
+        .map(|(digit, weight)| digit * weight)
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// Left-justified, space-padded, upper-case alphanumeric field.
+fn alpha(s: &str, len: usize) -> String {
+    let text = sanitize(s, len, |c| c.is_ascii_graphic() || c == ' ').to_ascii_uppercase();
+    format!("{text:<len$}")
+}
+
+/// Right-justified, zero-padded numeric field.
+fn num(n: u64, len: usize) -> String {
+    format!("{n:0len$}")
+}
+
+/// Converts `YYYY-MM-DD` to `YYMMDD`.
+fn yymmdd(date: &str) -> String {
+    date.chars().filter(char::is_ascii_digit).skip(2).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{alpha, is_valid_routing, num, yymmdd};
+
+    #[test]
+    fn routing_numbers_need_a_valid_check_digit() {
+        assert!(is_valid_routing("021000021"));
+        assert!(is_valid_routing("011401533"));
+        assert!(!is_valid_routing("021000022"));
+        assert!(!is_valid_routing("02100002"));
+        assert!(!is_valid_routing("02100002a"));
+    }
+
+    #[test]
+    fn fields_are_padded_and_truncated() {
+        assert_eq!(alpha("Acme Ltd", 10), "ACME LTD  ");
+        assert_eq!(alpha("A very long company name", 6), "A VERY");
+        assert_eq!(num(1234, 10), "0000001234");
+        assert_eq!(yymmdd("2025-03-07"), "250307");
+    }
+}
@@ -0,0 +1,245 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! SEPA credit transfer initiation (pain.001.001.03) writer.
+//!
+//! All payouts go into one payment information block debiting the configured
+//! account. `NbOfTxs` and `CtrlSum` are written both in the group header and
+//! the payment block. Text is restricted to the SEPA Latin character set.
+
+use super::{Beneficiary, PayoutConfig, PayoutFile, SepaOriginator, is_valid_iban, sanitize};
+use crate::error::{ConfigError, PayoutError};
+use rust_decimal::Decimal;
+use std::io::Write;
+
+/// Largest single SEPA credit transfer, in cents (999,999,999.99).
+pub(super) const MAX_ENTRY_CENTS: i64 = 99_999_999_999;
+
+/// Largest control sum written, in cents.
+pub(super) const MAX_TOTAL_CENTS: i64 = i64::MAX;
+
+/// Maximum length of names and unstructured remittance text.
+const MAX_TEXT: usize = 70;
+
+/// Maximum length of identifiers such as `MsgId` and `EndToEndId`.
+const MAX_ID: usize = 35;
+
+/// Returns the SEPA originator, checking its account details.
+pub(super) fn check_originator(config: &PayoutConfig) -> Result<SepaOriginator, ConfigError> {
+    let sepa = config
+        .sepa
+        .clone()
+        .ok_or_else(|| ConfigError::Invalid("pain.001 requires a [sepa] section".to_string()))?;
+    if !is_valid_iban(&sepa.debtor_iban) {
+        return Err(ConfigError::Invalid(format!(
+            "sepa.debtor_iban '{}' is not a valid IBAN",
+            sepa.debtor_iban
+        )));
+    }
+    if let Some(bic) = &sepa.debtor_bic
+        && !is_valid_bic(bic)
+    {
+        return Err(ConfigError::Invalid(format!(
+            "sepa.debtor_bic '{bic}' is not a valid BIC"
+        )));
+    }
+    // Written into an XML attribute unescaped, so checked strictly
+    let currency = &sepa.currency;
+    if !(currency.len() == 3 && currency.chars().all(|c| c.is_ascii_uppercase())) {
+        return Err(ConfigError::Invalid(format!(
+            "sepa.currency '{currency}' is not an ISO 4217 currency code"
+        )));
+    }
+    if currency != "EUR" {
+        return Err(ConfigError::Invalid(format!(
+            "sepa.currency '{currency}' is not supported: SEPA credit transfers are in EUR"
+        )));
+    }
+    Ok(sepa)
+}
+
+/// Checks that a beneficiary has a name and a valid IBAN (and BIC, if given).
+pub(super) fn check_beneficiary(beneficiary: &Beneficiary) -> Result<(), PayoutError> {
+    let invalid = |reason: String| PayoutError::InvalidBeneficiary {
+        client: beneficiary.client,
+        reason,
+    };
+    if beneficiary.name.trim().is_empty() {
+        return Err(invalid("name is empty".to_string()));
+    }
+    match beneficiary.iban.as_deref() {
+        None => return Err(invalid("pain.001 requires an IBAN".to_string())),
+        Some(iban) if !is_valid_iban(iban) => {
+            return Err(invalid(format!("'{iban}' is not a valid IBAN")));
+        }
+        Some(_) => {}
+    }
+    if let Some(bic) = beneficiary.bic.as_deref()
+        && !is_valid_bic(bic)
+    {
+        return Err(invalid(format!("'{bic}' is not a valid BIC")));
+    }
+    Ok(())
+}
+
+/// Writes the document.
+pub(super) fn write<W: Write>(
+    file: &PayoutFile,
+    originator: &SepaOriginator,
+    mut w: W,
+) -> Result<(), PayoutError> {
+    let count = file.entries.len();
+    let control_sum = Decimal::new(file.total_cents, 2);
+    let message_id = id(&file.message_id);
+    let debtor = text(&originator.debtor_name);
+    let currency = &originator.currency;
+
+    writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        w,
+        r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:pain.001.001.03">"#
+    )?;
+    writeln!(w, "  <CstmrCdtTrfInitn>")?;
+    writeln!(w, "    <GrpHdr>")?;
+    writeln!(w, "      <MsgId>{message_id}</MsgId>")?;
+    writeln!(w, "      <CreDtTm>{}</CreDtTm>", file.created)?;
+    writeln!(w, "      <NbOfTxs>{count}</NbOfTxs>")?;
+    writeln!(w, "      <CtrlSum>{control_sum}</CtrlSum>")?;
+    writeln!(w, "      <InitgPty><Nm>{debtor}</Nm></InitgPty>")?;
+    writeln!(w, "    </GrpHdr>")?;
+    writeln!(w, "    <PmtInf>")?;
+    writeln!(
+        w,
+        "      <PmtInfId>{}</PmtInfId>",
+        id(&format!("{}-1", file.message_id))
+    )?;
+    writeln!(w, "      <PmtMtd>TRF</PmtMtd>")?;
+    writeln!(w, "      <BtchBookg>true</BtchBookg>")?;
+    writeln!(w, "      <NbOfTxs>{count}</NbOfTxs>")?;
+    writeln!(w, "      <CtrlSum>{control_sum}</CtrlSum>")?;
+    writeln!(
+        w,
+        "      <PmtTpInf><SvcLvl><Cd>SEPA</Cd></SvcLvl></PmtTpInf>"
+    )?;
+    writeln!(
+        w,
+        "      <ReqdExctnDt>{}</ReqdExctnDt>",
+        file.execution_date
+    )?;
+    writeln!(w, "      <Dbtr><Nm>{debtor}</Nm></Dbtr>")?;
+    writeln!(
+        w,
+        "      <DbtrAcct><Id><IBAN>{}</IBAN></Id></DbtrAcct>",
+        compact(&originator.debtor_iban)
+    )?;
+    match &originator.debtor_bic {
+        Some(bic) => writeln!(
+            w,
+            "      <DbtrAgt><FinInstnId><BIC>{bic}</BIC></FinInstnId></DbtrAgt>"
+        )?,
+        None => writeln!(
+            w,
+            "      <DbtrAgt><FinInstnId><Othr><Id>NOTPROVIDED</Id></Othr></FinInstnId></DbtrAgt>"
+        )?,
+    }
+    writeln!(w, "      <ChrgBr>SLEV</ChrgBr>")?;
+
+    for entry in &file.entries {
+        let tx = entry.payout.transaction_id.0;
+        let beneficiary = &entry.beneficiary;
+        writeln!(w, "      <CdtTrfTxInf>")?;
+        writeln!(w, "        <PmtId><EndToEndId>TX{tx}</EndToEndId></PmtId>")?;
+        writeln!(
+            w,
+            r#"        <Amt><InstdAmt Ccy="{currency}">{}</InstdAmt></Amt>"#,
+            Decimal::new(entry.cents, 2)
+        )?;
+        if let Some(bic) = &beneficiary.bic {
+            writeln!(
+                w,
+                "        <CdtrAgt><FinInstnId><BIC>{bic}</BIC></FinInstnId></CdtrAgt>"
+            )?;
+        }
+        writeln!(
+            w,
+            "        <Cdtr><Nm>{}</Nm></Cdtr>",
+            text(&beneficiary.name)
+        )?;
+        writeln!(
+            w,
+            "        <CdtrAcct><Id><IBAN>{}</IBAN></Id></CdtrAcct>",
+            compact(beneficiary.iban.as_deref().unwrap_or_default())
+        )?;
+        writeln!(
+            w,
+            "        <RmtInf><Ustrd>Payout client {} tx {tx}</Ustrd></RmtInf>",
+            beneficiary.client
+        )?;
+        writeln!(w, "      </CdtTrfTxInf>")?;
+    }
+
+    writeln!(w, "    </PmtInf>")?;
+    writeln!(w, "  </CstmrCdtTrfInitn>")?;
+    writeln!(w, "</Document>")?;
+    Ok(())
+}
+
+/// Returns `true` for an 8 or 11 character BIC.
+fn is_valid_bic(bic: &str) -> bool {
+    // All ASCII first, so that the byte slice below is on a char boundary
+    (bic.len() == 8 || bic.len() == 11)
+        && bic.chars().all(|c| c.is_ascii_alphanumeric())
+        && bic[..6].chars().all(|c| c.is_ascii_uppercase())
+}
+
+/// Returns `true` for characters of the SEPA Latin character set. None of
+/// them needs escaping in XML text.
+fn is_sepa_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || " /-?:().,'+".contains(c)
+}
+
+/// Sanitizes free text such as names.
+fn text(s: &str) -> String {
+    sanitize(s, MAX_TEXT, is_sepa_char)
+}
+
+/// Sanitizes an identifier.
+fn id(s: &str) -> String {
+    sanitize(s, MAX_ID, is_sepa_char).replace(' ', "-")
+}
+
+/// Removes spaces from an IBAN.
+fn compact(iban: &str) -> String {
+    iban.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_valid_bic;
+
+    #[test]
+    fn bics_are_ascii_with_an_alphabetic_bank_and_country_code() {
+        assert!(is_valid_bic("COBADEFF"));
+        assert!(is_valid_bic("COBADEFFXXX"));
+        assert!(!is_valid_bic("COBADEF"));
+        assert!(!is_valid_bic("C0BADEFF"));
+        assert!(!is_valid_bic("COBADEF-XXX"));
+        // A multibyte character across byte 6 must not panic
+        assert!(!is_valid_bic("ABCDEÄG"));
+        assert!(!is_valid_bic("ABCDEÄGXX"));
+    }
+}
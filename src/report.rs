@@ -0,0 +1,546 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Account report rendering.
+//!
+//! Every format is produced from the same [`AccountSnapshot`] serialization, so
+//! amounts are rounded to [`Account::DECIMAL_PRECISION`] identically whether
+//! the report is CSV, JSON or an aligned table.
+//!
+//! [`Account::DECIMAL_PRECISION`]: crate::Account::DECIMAL_PRECISION
+//!
+//! # Example
+//!
+//! ```
+//! use ledger_demo_rs::{
+//!     ClientId, Engine, OutputFormat, TransactionId, TransactionType, write_report,
+//! };
+//! use rust_decimal_macros::dec;
+//!
+//! let engine = Engine::new();
+//! engine
+//!     .process(TransactionType::Deposit {
+//!         client_id: ClientId(1),
+//!         transaction_id: TransactionId(1),
+//!         amount: dec!(2.5),
+//!     })
+//!     .unwrap();
+//!
+//! let mut out = Vec::new();
+//! write_report(&engine.accounts(), OutputFormat::Jsonl, &mut out).unwrap();
+//! let line: serde_json::Value = serde_json::from_slice(&out).unwrap();
+//! assert_eq!(line["client"], 1);
+//! assert_eq!(line["locked"], false);
+//! ```
+
+use crate::account::AccountSnapshot;
+use crate::base::ClientId;
+use crate::error::ReportError;
+use csv::{ReaderBuilder, StringRecord, WriterBuilder};
+use rust_decimal::Decimal;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+/// Output format for account reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Comma-separated values with a header row.
+    #[default]
+    Csv,
+    /// A single JSON array of account objects.
+    Json,
+    /// One JSON object per line.
+    Jsonl,
+    /// Tab-separated values with a header row.
+    Tsv,
+    /// Human-readable aligned table with a totals row.
+    Table,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    /// Parses `csv`, `json`, `jsonl`, `tsv` or `table`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            "jsonl" => Ok(Self::Jsonl),
+            "tsv" => Ok(Self::Tsv),
+            "table" => Ok(Self::Table),
+            other => Err(format!(
+                "unknown output format '{other}' (expected csv, json, jsonl, tsv or table)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+            Self::Jsonl => "jsonl",
+            Self::Tsv => "tsv",
+            Self::Table => "table",
+        })
+    }
+}
+
+/// Column used to order account reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortKey {
+    /// Client ID.
+    #[default]
+    Client,
+    /// Available funds.
+    Available,
+    /// Held funds.
+    Held,
+    /// Total funds.
+    Total,
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    /// Parses `client`, `available`, `held` or `total`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "client" => Ok(Self::Client),
+            "available" => Ok(Self::Available),
+            "held" => Ok(Self::Held),
+            "total" => Ok(Self::Total),
+            other => Err(format!(
+                "unknown sort key '{other}' (expected client, available, held or total)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Client => "client",
+            Self::Available => "available",
+            Self::Held => "held",
+            Self::Total => "total",
+        })
+    }
+}
+
+/// Direction of a report sort.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    /// Smallest first.
+    #[default]
+    Asc,
+    /// Largest first.
+    Desc,
+}
+
+impl FromStr for SortOrder {
+    type Err = String;
+
+    /// Parses `asc` or `desc`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "asc" => Ok(Self::Asc),
+            "desc" => Ok(Self::Desc),
+            other => Err(format!(
+                "unknown sort order '{other}' (expected asc or desc)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Asc => "asc",
+            Self::Desc => "desc",
+        })
+    }
+}
+
+/// Sorts accounts by `key` in the given order.
+///
+/// Accounts with equal keys are always ordered by ascending client ID, so the
+/// result is fully deterministic regardless of how the accounts were produced.
+pub fn sort_accounts(accounts: &mut [AccountSnapshot], key: SortKey, order: SortOrder) {
+    accounts.sort_by(|a, b| {
+        let ordering = match key {
+            SortKey::Client => a.client_id.0.cmp(&b.client_id.0),
+            SortKey::Available => a.available.cmp(&b.available),
+            SortKey::Held => a.held.cmp(&b.held),
+            SortKey::Total => a.total.cmp(&b.total),
+        };
+        let ordering = match order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        };
+        ordering.then_with(|| a.client_id.0.cmp(&b.client_id.0))
+    });
+}
+
+/// Predicate selecting which accounts appear in a report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountFilter {
+    /// Only locked accounts.
+    Locked,
+    /// Only accounts with held funds.
+    HeldPositive,
+    /// Only accounts whose client ID lies in `min..=max`.
+    ClientRange {
+        /// Smallest client ID included.
+        min: u16,
+        /// Largest client ID included.
+        max: u16,
+    },
+}
+
+impl AccountFilter {
+    /// Returns `true` if `account` passes the filter.
+    pub fn matches(&self, account: &AccountSnapshot) -> bool {
+        match *self {
+            Self::Locked => account.locked,
+            Self::HeldPositive => account.held > Decimal::ZERO,
+            Self::ClientRange { min, max } => (min..=max).contains(&account.client_id.0),
+        }
+    }
+}
+
+impl FromStr for AccountFilter {
+    type Err = String;
+
+    /// Parses `locked`, `held>0` or `client=<min>..<max>` (inclusive, either
+    /// bound may be omitted).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        match s.as_str() {
+            "locked" => return Ok(Self::Locked),
+            "held>0" => return Ok(Self::HeldPositive),
+            _ => {}
+        }
+
+        let invalid =
+            || format!("unknown filter '{s}' (expected locked, held>0 or client=<min>..<max>)");
+        let range = s.strip_prefix("client=").ok_or_else(invalid)?;
+        let (min, max) = range.split_once("..").ok_or_else(invalid)?;
+        let bound = |value: &str, default: u16| match value {
+            "" => Ok(default),
+            value => value.parse::<u16>().map_err(|_| invalid()),
+        };
+        Ok(Self::ClientRange {
+            min: bound(min, u16::MIN)?,
+            max: bound(max, u16::MAX)?,
+        })
+    }
+}
+
+/// Writes `accounts` to `writer` in the given format.
+///
+/// # Errors
+///
+/// Returns a [`ReportError`] if serialization or writing fails.
+pub fn write_report<W: Write>(
+    accounts: &[AccountSnapshot],
+    format: OutputFormat,
+    mut writer: W,
+) -> Result<(), ReportError> {
+    match format {
+        OutputFormat::Csv => write_delimited(accounts, b',', &mut writer)?,
+        OutputFormat::Tsv => write_delimited(accounts, b'\t', &mut writer)?,
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, accounts)?;
+            writeln!(writer)?;
+        }
+        OutputFormat::Jsonl => {
+            for account in accounts {
+                serde_json::to_writer(&mut writer, account)?;
+                writeln!(writer)?;
+            }
+        }
+        OutputFormat::Table => write_table(accounts, &mut writer)?,
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes accounts as delimited text with a header row.
+fn write_delimited<W: Write>(
+    accounts: &[AccountSnapshot],
+    delimiter: u8,
+    writer: W,
+) -> Result<(), csv::Error> {
+    let mut wtr = WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(writer);
+    for account in accounts {
+        wtr.serialize(account)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Serializes accounts through the CSV serializer and reads the cells back,
+/// yielding the header and one rendered record per account.
+fn render_cells(
+    accounts: &[AccountSnapshot],
+) -> Result<(StringRecord, Vec<StringRecord>), csv::Error> {
+    let mut buffer = Vec::new();
+    write_delimited(accounts, b',', &mut buffer)?;
+
+    let mut rdr = ReaderBuilder::new().from_reader(buffer.as_slice());
+    let header = rdr.headers()?.clone();
+    let rows = rdr.records().collect::<Result<_, _>>()?;
+    Ok((header, rows))
+}
+
+/// Writes an aligned table followed by a totals row.
+///
+/// The totals row is itself an [`AccountSnapshot`] (sums of every column),
+/// rendered through the same serialization before its `client` and `locked`
+/// cells are relabeled. Fails with [`ReportError::TotalOverflow`] if a sum
+/// leaves the decimal range.
+fn write_table<W: Write>(accounts: &[AccountSnapshot], mut writer: W) -> Result<(), ReportError> {
+    let sum = |column, field: fn(&AccountSnapshot) -> Decimal| {
+        accounts
+            .iter()
+            .map(field)
+            .try_fold(Decimal::ZERO, Decimal::checked_add)
+            .ok_or(ReportError::TotalOverflow(column))
+    };
+    let totals = AccountSnapshot {
+        client_id: ClientId(0),
+        available: sum("available", |a| a.available)?,
+        held: sum("held", |a| a.held)?,
+        total: sum("total", |a| a.total)?,
+        locked: false,
+        version: 0,
+    };
+
+    let mut all = accounts.to_vec();
+    all.push(totals);
+    let (header, mut rows) = render_cells(&all)?;
+
+    let locked = accounts.iter().filter(|a| a.locked).count();
+    let totals = rows.pop().expect("totals row is always rendered");
+    let totals: StringRecord = totals
+        .iter()
+        .zip(header.iter())
+        .map(|(cell, column)| match column {
+            "client" => format!("TOTAL ({})", accounts.len()),
+            "locked" => format!("{locked} locked"),
+            _ => cell.to_string(),
+        })
+        .collect();
+
+    let widths: Vec<usize> = (0..header.len())
+        .map(|i| {
+            std::iter::once(&header)
+                .chain(&rows)
+                .chain(std::iter::once(&totals))
+                .map(|record| record[i].len())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let rule = widths
+        .iter()
+        .map(|w| "-".repeat(*w))
+        .collect::<Vec<_>>()
+        .join("-+-");
+
+    let write_row = |writer: &mut W, record: &StringRecord| -> std::io::Result<()> {
+        let cells: Vec<String> = record
+            .iter()
+            .zip(&widths)
+            .zip(header.iter())
+            .map(|((cell, width), column)| match column {
+                "available" | "held" | "total" => format!("{cell:>width$}"),
+                _ => format!("{cell:<width$}"),
+            })
+            .collect();
+        writeln!(writer, "{}", cells.join(" | ").trim_end())
+    };
+
+    write_row(&mut writer, &header)?;
+    writeln!(writer, "{rule}")?;
+    for row in &rows {
+        write_row(&mut writer, row)?;
+    }
+    writeln!(writer, "{rule}")?;
+    write_row(&mut writer, &totals)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn accounts() -> Vec<AccountSnapshot> {
+        vec![
+            AccountSnapshot {
+                client_id: ClientId(1),
+                available: dec!(75.5),
+                held: dec!(0),
+                total: dec!(75.5),
+                locked: false,
+                version: 0,
+            },
+            AccountSnapshot {
+                client_id: ClientId(2),
+                available: dec!(100.123456),
+                held: dec!(25),
+                total: dec!(125.123456),
+                locked: true,
+                version: 0,
+            },
+        ]
+    }
+
+    fn render(format: OutputFormat) -> String {
+        let mut out = Vec::new();
+        write_report(&accounts(), format, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn format_names_round_trip() {
+        for format in [
+            OutputFormat::Csv,
+            OutputFormat::Json,
+            OutputFormat::Jsonl,
+            OutputFormat::Tsv,
+            OutputFormat::Table,
+        ] {
+            assert_eq!(format.to_string().parse::<OutputFormat>(), Ok(format));
+        }
+        assert!("xml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn delimited_formats_share_rounding() {
+        assert_eq!(
+            render(OutputFormat::Csv),
+            "client,available,held,total,locked\n\
+             1,75.5,0,75.5,false\n\
+             2,100.1235,25,125.1235,true\n"
+        );
+        assert_eq!(
+            render(OutputFormat::Tsv),
+            "client\tavailable\theld\ttotal\tlocked\n\
+             1\t75.5\t0\t75.5\tfalse\n\
+             2\t100.1235\t25\t125.1235\ttrue\n"
+        );
+    }
+
+    #[test]
+    fn json_formats_share_rounding() {
+        let jsonl = render(OutputFormat::Jsonl);
+        let lines: Vec<&str> = jsonl.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[1],
+            r#"{"client":2,"available":"100.1235","held":"25","total":"125.1235","locked":true}"#
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&render(OutputFormat::Json)).unwrap();
+        assert_eq!(json[1]["available"], "100.1235");
+        assert_eq!(json.as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn sort_is_deterministic_with_client_tie_break() {
+        let mut accounts = accounts();
+        accounts.push(AccountSnapshot {
+            client_id: ClientId(0),
+            available: dec!(1),
+            held: dec!(25),
+            total: dec!(26),
+            locked: false,
+            version: 0,
+        });
+
+        let ids = |accounts: &[AccountSnapshot]| -> Vec<u16> {
+            accounts.iter().map(|a| a.client_id.0).collect()
+        };
+
+        sort_accounts(&mut accounts, SortKey::Total, SortOrder::Desc);
+        assert_eq!(ids(&accounts), vec![2, 1, 0]);
+
+        sort_accounts(&mut accounts, SortKey::Held, SortOrder::Desc);
+        assert_eq!(ids(&accounts), vec![0, 2, 1]);
+
+        sort_accounts(&mut accounts, SortKey::Client, SortOrder::Asc);
+        assert_eq!(ids(&accounts), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn filters_parse_and_match() {
+        let [open, locked] = accounts().try_into().unwrap();
+
+        let filter: AccountFilter = "locked".parse().unwrap();
+        assert!(!filter.matches(&open) && filter.matches(&locked));
+
+        let filter: AccountFilter = "held>0".parse().unwrap();
+        assert!(!filter.matches(&open) && filter.matches(&locked));
+
+        let filter: AccountFilter = "client=..1".parse().unwrap();
+        assert_eq!(filter, AccountFilter::ClientRange { min: 0, max: 1 });
+        assert!(filter.matches(&open) && !filter.matches(&locked));
+
+        assert_eq!(
+            "client=2..".parse(),
+            Ok(AccountFilter::ClientRange {
+                min: 2,
+                max: u16::MAX
+            })
+        );
+        assert!("client=a..b".parse::<AccountFilter>().is_err());
+        assert!("total>0".parse::<AccountFilter>().is_err());
+    }
+
+    #[test]
+    fn table_is_aligned_with_totals() {
+        assert_eq!(
+            render(OutputFormat::Table),
+            "client    | available | held |    total | locked\n\
+             ----------+-----------+------+----------+---------\n\
+             1         |      75.5 |    0 |     75.5 | false\n\
+             2         |  100.1235 |   25 | 125.1235 | true\n\
+             ----------+-----------+------+----------+---------\n\
+             TOTAL (2) |  175.6235 |   25 | 200.6235 | 1 locked\n"
+        );
+    }
+
+    #[test]
+    fn table_reports_overflowing_totals() {
+        let huge = dec!(50000000000000000000000000000);
+        let mut accounts = accounts();
+        for account in &mut accounts {
+            (account.available, account.total) = (huge, huge);
+        }
+        let error = write_report(&accounts, OutputFormat::Table, Vec::new()).unwrap_err();
+        assert!(matches!(error, ReportError::TotalOverflow("available")));
+        // The other formats have no totals and are unaffected
+        write_report(&accounts, OutputFormat::Csv, Vec::new()).unwrap();
+    }
+}
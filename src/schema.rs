@@ -0,0 +1,545 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Configurable CSV input schema.
+//!
+//! [`CsvSchema`] maps arbitrary column names or positions to the transaction
+//! fields, sets the delimiter and quoting, aliases transaction type strings and
+//! names extra columns to ignore. It is loaded from TOML or JSON like
+//! [`EngineConfig`](crate::EngineConfig); unknown keys are rejected.
+//!
+//! ```toml
+//! delimiter = ";"
+//! has_headers = true
+//! ignore = ["booking_date"]
+//!
+//! [columns]
+//! type = "Kind"
+//! client = "Customer"
+//! tx = 0              # zero-based position
+//! amount = "Value"
+//!
+//! [type_aliases]
+//! DEP = "deposit"
+//! WDR = "withdrawal"
+//! ```
+//!
+//! A schema is matched against each input's header with [`CsvSchema::resolve`],
+//! which reports required fields whose column is missing.
+
+use crate::config::load_file;
+use crate::error::{ConfigError, SchemaError};
+use csv::{ReaderBuilder, StringRecord, Trim};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Transaction type names accepted as alias targets.
+const TRANSACTION_TYPES: [&str; 5] = ["deposit", "withdrawal", "dispute", "resolve", "chargeback"];
+
+/// A transaction field read from an input column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Field {
+    /// Transaction type.
+    Type,
+    /// Client ID.
+    Client,
+    /// Transaction ID.
+    Tx,
+    /// Amount (deposits and withdrawals only).
+    Amount,
+}
+
+impl Field {
+    /// Every field, in default column order.
+    pub const ALL: [Field; 4] = [Field::Type, Field::Client, Field::Tx, Field::Amount];
+
+    /// Field name as used in schema files.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Type => "type",
+            Self::Client => "client",
+            Self::Tx => "tx",
+            Self::Amount => "amount",
+        }
+    }
+}
+
+impl FromStr for Field {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|field| field.name() == s)
+            .ok_or_else(|| format!("unknown field '{s}' (expected type, client, tx or amount)"))
+    }
+}
+
+/// Reference to an input column by header name or zero-based position.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ColumnRef {
+    /// Zero-based column position.
+    Index(usize),
+    /// Header name.
+    Name(String),
+}
+
+impl FromStr for ColumnRef {
+    type Err = String;
+
+    /// Parses a zero-based position if `s` is all digits, else a header name.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err("column reference must not be empty".to_string());
+        }
+        Ok(s.parse()
+            .map(Self::Index)
+            .unwrap_or_else(|_| Self::Name(s.to_string())))
+    }
+}
+
+impl fmt::Display for ColumnRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Index(index) => write!(f, "#{index}"),
+            Self::Name(name) => f.write_str(name),
+        }
+    }
+}
+
+/// Column for each transaction field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct ColumnMapping {
+    /// Transaction type column.
+    #[serde(rename = "type")]
+    pub tx_type: ColumnRef,
+    /// Client ID column.
+    pub client: ColumnRef,
+    /// Transaction ID column.
+    pub tx: ColumnRef,
+    /// Amount column. May be absent from the header if no row needs it.
+    pub amount: ColumnRef,
+}
+
+impl ColumnMapping {
+    /// Returns the column mapped to `field`.
+    pub fn get(&self, field: Field) -> &ColumnRef {
+        match field {
+            Field::Type => &self.tx_type,
+            Field::Client => &self.client,
+            Field::Tx => &self.tx,
+            Field::Amount => &self.amount,
+        }
+    }
+
+    /// Maps `field` to `column`.
+    pub fn set(&mut self, field: Field, column: ColumnRef) {
+        match field {
+            Field::Type => self.tx_type = column,
+            Field::Client => self.client = column,
+            Field::Tx => self.tx = column,
+            Field::Amount => self.amount = column,
+        }
+    }
+}
+
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        Self {
+            tx_type: ColumnRef::Name("type".to_string()),
+            client: ColumnRef::Name("client".to_string()),
+            tx: ColumnRef::Name("tx".to_string()),
+            amount: ColumnRef::Name("amount".to_string()),
+        }
+    }
+}
+
+/// Layout of a CSV transaction input.
+///
+/// The default schema is the standard `type,client,tx,amount` layout with a
+/// header row and comma delimiter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct CsvSchema {
+    /// Field delimiter (ASCII).
+    pub delimiter: char,
+    /// Quote character (ASCII).
+    pub quote: char,
+    /// Whether quoted fields are recognized.
+    pub quoting: bool,
+    /// Whether the first row is a header.
+    pub has_headers: bool,
+    /// Column for each field.
+    pub columns: ColumnMapping,
+    /// Transaction type aliases, e.g. `DEP = "deposit"`. Matched
+    /// case-insensitively.
+    pub type_aliases: BTreeMap<String, String>,
+    /// Header columns that are expected and ignored.
+    pub ignore: Vec<String>,
+}
+
+impl Default for CsvSchema {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            quote: '"',
+            quoting: true,
+            has_headers: true,
+            columns: ColumnMapping::default(),
+            type_aliases: BTreeMap::new(),
+            ignore: Vec::new(),
+        }
+    }
+}
+
+impl CsvSchema {
+    /// Loads a schema file, choosing the format by extension (`.toml` or
+    /// `.json`), and validates it.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ConfigError`] if the file cannot be read or parsed, or
+    /// if [`validate`](Self::validate) fails.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let schema: Self = load_file(path.as_ref())?;
+        schema.validate()?;
+        Ok(schema)
+    }
+
+    /// Parses and validates a TOML schema.
+    pub fn from_toml_str(contents: &str) -> Result<Self, ConfigError> {
+        let schema: Self = toml::from_str(contents)?;
+        schema.validate()?;
+        Ok(schema)
+    }
+
+    /// Checks settings that do not depend on the input.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Invalid`] for a non-ASCII delimiter or quote, an
+    /// alias to an unknown transaction type, or a column mapped by name when
+    /// the input has no header row.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        for (name, c) in [("delimiter", self.delimiter), ("quote", self.quote)] {
+            if !c.is_ascii() {
+                return Err(ConfigError::Invalid(format!(
+                    "{name} must be a single ASCII character, got '{c}'"
+                )));
+            }
+        }
+        for (alias, target) in &self.type_aliases {
+            if !TRANSACTION_TYPES.contains(&target.to_ascii_lowercase().as_str()) {
+                return Err(ConfigError::Invalid(format!(
+                    "type alias '{alias}' targets unknown transaction type '{target}'"
+                )));
+            }
+        }
+        if !self.has_headers {
+            self.resolve(None)
+                .map_err(|e| ConfigError::Invalid(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Returns a CSV reader builder configured for this schema.
+    ///
+    /// Fields are trimmed and rows may have fewer fields than the header.
+    pub fn reader_builder(&self) -> ReaderBuilder {
+        let mut builder = ReaderBuilder::new();
+        builder
+            .delimiter(self.delimiter as u8)
+            .quote(self.quote as u8)
+            .quoting(self.quoting)
+            .has_headers(self.has_headers)
+            .trim(Trim::All)
+            .flexible(true);
+        builder
+    }
+
+    /// Matches the schema against an input's header row (`None` for
+    /// headerless inputs).
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SchemaError`] if a required field (`type`, `client` or
+    /// `tx`) names a column the header lacks, if any field is mapped by name
+    /// without a header or by position past the header's last column, or if
+    /// two fields share a column.
+    pub fn resolve(&self, headers: Option<&StringRecord>) -> Result<ResolvedSchema, SchemaError> {
+        let locate = |field: Field| -> Result<Option<usize>, SchemaError> {
+            match (self.columns.get(field), headers) {
+                (ColumnRef::Index(index), Some(headers)) if *index >= headers.len() => {
+                    Err(SchemaError::ColumnOutOfRange {
+                        field: field.name(),
+                        index: *index,
+                        width: headers.len(),
+                    })
+                }
+                (ColumnRef::Index(index), _) => Ok(Some(*index)),
+                (ColumnRef::Name(name), Some(headers)) => {
+                    match headers.iter().position(|h| h == name) {
+                        Some(index) => Ok(Some(index)),
+                        None if field == Field::Amount => Ok(None),
+                        None => Err(SchemaError::MissingColumn {
+                            field: field.name(),
+                            column: name.clone(),
+                        }),
+                    }
+                }
+                (ColumnRef::Name(name), None) => Err(SchemaError::NamedColumnWithoutHeader {
+                    field: field.name(),
+                    column: name.clone(),
+                }),
+            }
+        };
+
+        let required = |field| locate(field).map(|index| index.expect("required fields resolve"));
+        let tx_type = required(Field::Type)?;
+        let client = required(Field::Client)?;
+        let tx = required(Field::Tx)?;
+        let amount = locate(Field::Amount)?;
+
+        let mapped = [Some(tx_type), Some(client), Some(tx), amount];
+        for (i, index) in mapped.iter().enumerate() {
+            if let Some(index) = *index
+                && let Some(first) = mapped[..i].iter().position(|m| *m == Some(index))
+            {
+                return Err(SchemaError::DuplicateColumn {
+                    first: Field::ALL[first].name(),
+                    second: Field::ALL[i].name(),
+                    index,
+                });
+            }
+        }
+        let unexpected = headers
+            .map(|headers| {
+                headers
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, h)| {
+                        !mapped.contains(&Some(*i)) && !self.ignore.iter().any(|c| c == h)
+                    })
+                    .map(|(_, h)| h.to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(ResolvedSchema {
+            indices: [tx_type, client, tx]
+                .map(Some)
+                .into_iter()
+                .chain([amount])
+                .collect(),
+            aliases: self
+                .type_aliases
+                .iter()
+                .map(|(alias, target)| (alias.to_ascii_lowercase(), target.to_ascii_lowercase()))
+                .collect(),
+            unexpected,
+        })
+    }
+}
+
+/// A [`CsvSchema`] matched against a specific input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedSchema {
+    /// Column index per field, in [`Field::ALL`] order.
+    indices: Vec<Option<usize>>,
+    /// Lowercased alias → lowercased canonical type.
+    aliases: BTreeMap<String, String>,
+    /// Header columns neither mapped nor ignored.
+    unexpected: Vec<String>,
+}
+
+impl ResolvedSchema {
+    /// Returns the value of `field` in `record`, if the column exists.
+    pub fn get<'r>(&self, record: &'r StringRecord, field: Field) -> Option<&'r str> {
+        let index = self.indices[field as usize]?;
+        record.get(index)
+    }
+
+    /// Returns the canonical, lowercase transaction type for a raw type
+    /// string, applying aliases.
+    pub fn transaction_type(&self, raw: &str) -> String {
+        let raw = raw.to_ascii_lowercase();
+        self.aliases.get(&raw).cloned().unwrap_or(raw)
+    }
+
+    /// Header columns that are neither mapped to a field nor listed in
+    /// [`CsvSchema::ignore`].
+    pub fn unexpected_columns(&self) -> &[String] {
+        &self.unexpected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(names: &[&str]) -> StringRecord {
+        StringRecord::from(names.to_vec())
+    }
+
+    #[test]
+    fn default_schema_matches_standard_header() {
+        let resolved = CsvSchema::default()
+            .resolve(Some(&headers(&["type", "client", "tx", "amount"])))
+            .unwrap();
+        let record = StringRecord::from(vec!["deposit", "1", "2", "3.5"]);
+
+        assert_eq!(resolved.get(&record, Field::Type), Some("deposit"));
+        assert_eq!(resolved.get(&record, Field::Amount), Some("3.5"));
+        assert!(resolved.unexpected_columns().is_empty());
+    }
+
+    #[test]
+    fn maps_names_positions_and_aliases() {
+        let schema = CsvSchema::from_toml_str(
+            r#"
+            delimiter = ";"
+            ignore = ["note"]
+
+            [columns]
+            type = "Kind"
+            client = "Customer"
+            tx = 0
+            amount = "Value"
+
+            [type_aliases]
+            DEP = "deposit"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(schema.delimiter, ';');
+
+        let resolved = schema
+            .resolve(Some(&headers(&[
+                "Id", "Kind", "Customer", "Value", "note", "extra",
+            ])))
+            .unwrap();
+        let record = StringRecord::from(vec!["7", "DEP", "1", "2.0", "", ""]);
+
+        assert_eq!(resolved.get(&record, Field::Tx), Some("7"));
+        assert_eq!(resolved.get(&record, Field::Client), Some("1"));
+        assert_eq!(resolved.transaction_type("dep"), "deposit");
+        assert_eq!(resolved.transaction_type("Withdrawal"), "withdrawal");
+        assert_eq!(resolved.unexpected_columns(), ["extra"]);
+    }
+
+    #[test]
+    fn missing_required_column_is_reported() {
+        let err = CsvSchema::default()
+            .resolve(Some(&headers(&["type", "customer", "tx"])))
+            .unwrap_err();
+        assert_eq!(
+            err,
+            SchemaError::MissingColumn {
+                field: "client",
+                column: "client".to_string()
+            }
+        );
+
+        // A missing amount column is allowed
+        let resolved = CsvSchema::default()
+            .resolve(Some(&headers(&["type", "client", "tx"])))
+            .unwrap();
+        assert_eq!(
+            resolved.get(
+                &StringRecord::from(vec!["dispute", "1", "1"]),
+                Field::Amount
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn headerless_schema_requires_positions() {
+        let err = CsvSchema::from_toml_str("has_headers = false").unwrap_err();
+        assert!(err.to_string().contains("no header row"), "{err}");
+
+        let schema = CsvSchema::from_toml_str(
+            "has_headers = false\n[columns]\ntype = 0\nclient = 1\ntx = 2\namount = 3\n",
+        )
+        .unwrap();
+        assert!(schema.resolve(None).is_ok());
+    }
+
+    #[test]
+    fn positions_past_the_header_are_rejected() {
+        let mut schema = CsvSchema::default();
+        schema.columns.set(Field::Amount, ColumnRef::Index(4));
+        let err = schema
+            .resolve(Some(&headers(&["type", "client", "tx", "amount"])))
+            .unwrap_err();
+        assert_eq!(
+            err,
+            SchemaError::ColumnOutOfRange {
+                field: "amount",
+                index: 4,
+                width: 4
+            }
+        );
+        assert_eq!(err.code(), "COLUMN_OUT_OF_RANGE");
+    }
+
+    #[test]
+    fn fields_sharing_a_column_are_rejected() {
+        let mut schema = CsvSchema::default();
+        schema
+            .columns
+            .set(Field::Tx, ColumnRef::Name("client".to_string()));
+        let err = schema
+            .resolve(Some(&headers(&["type", "client", "tx", "amount"])))
+            .unwrap_err();
+        assert_eq!(
+            err,
+            SchemaError::DuplicateColumn {
+                first: "client",
+                second: "tx",
+                index: 1
+            }
+        );
+
+        // Headerless schemas are checked when loaded
+        let err = CsvSchema::from_toml_str(
+            "has_headers = false\n[columns]\ntype = 0\nclient = 1\ntx = 2\namount = 0\n",
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("fields 'type' and 'amount' are both mapped to column #0"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        for toml in [
+            "delimiter = \"§\"",
+            "[type_aliases]\nDEP = \"refund\"",
+            "[columns]\nkind = \"type\"",
+        ] {
+            assert!(CsvSchema::from_toml_str(toml).is_err(), "{toml}");
+        }
+    }
+}
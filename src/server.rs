@@ -0,0 +1,1009 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! HTTP API over an [`Engine`] (feature `http`).
+//!
+//! [`create_router`] builds the axum router that the `serve` subcommand, the
+//! `server` example and the integration tests all run, so request and
+//! response shapes and the error mapping are defined once.
+//!
+//! ## Endpoints
+//!
+//! - `POST /transactions` - Process a [`TransactionSubmission`]; `201 Created`
+//!   on success, an [`ErrorResponse`] otherwise. An `expected_version` makes
+//!   the transaction conditional on the account's version (`409` if it moved)
+//! - `POST /transactions/batch` - Process a JSON array or, with content type
+//!   `application/x-ndjson`, JSON Lines of transactions in order; answers a
+//!   [`BatchResponse`]. With `?atomic=true` the batch is all-or-nothing and
+//!   answers `422` if any transaction is rejected
+//! - `GET /accounts/stream` - Server-Sent Events of transaction outcomes and
+//!   account changes; see [`Event`]. `?client=` limits the stream to one
+//!   client, and the `Last-Event-ID` header or `?last_event_id=` resumes after
+//!   a reconnect
+//! - `GET /accounts/ws` - The same events as WebSocket text messages
+//! - `GET /accounts` - List all accounts as [`AccountResponse`]s
+//! - `GET /accounts/{id}` - Get an account by client ID, with its version as
+//!   the `ETag`; `If-None-Match` answers `304 Not Modified` while it holds
+//! - `GET /accounts/{id}/transactions` - Page through a client's deposits and
+//!   withdrawals; query parameters `cursor`, `limit` (1-500, default 50),
+//!   `type` (`deposit` or `withdrawal`) and `status` (`applied`, `inflight`,
+//!   `resolved` or `voided`)
+//! - `GET /transactions/{id}` - Get a deposit or withdrawal with its owning
+//!   client and dispute status; `?client=` picks one when transaction IDs are
+//!   unique only per client
+//! - `GET /openapi.json` - This API as an OpenAPI 3.1 document; see [`openapi()`]
+//! - `GET /metrics` - Engine and request metrics in the Prometheus text format
+//!   (operator keys only)
+//!
+//! Every error, including a malformed body, path or query, is answered with
+//! an [`ErrorResponse`].
+//!
+//! ## Authentication
+//!
+//! By default the API is open. Pass [`ApiKeys`] in [`ServerOptions`] to
+//! require an API key on every request; operator keys may do everything,
+//! client keys only read their own client's data and submit its deposits and
+//! withdrawals. See the [`auth`] module for the keys file.
+//!
+//! ## Rate Limits
+//!
+//! A [`RateLimiter`] in [`ServerOptions`] caps how fast each client ID and
+//! each API key may submit transactions; submissions over the limit are answered
+//! with `429 RATE_LIMITED` and a `Retry-After` header. See the [`limits`]
+//! module for the limits file and how to reload it.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use ledger_demo_rs::Engine;
+//! use std::sync::Arc;
+//!
+//! # async fn run() -> std::io::Result<()> {
+//! let listener = tokio::net::TcpListener::bind("127.0.0.1:3000").await?;
+//! ledger_demo_rs::server::serve(listener, Arc::new(Engine::new())).await
+//! # }
+//! ```
+
+pub mod auth;
+mod events;
+mod extract;
+pub mod limits;
+mod metrics;
+pub mod openapi;
+
+pub use auth::{ApiKeys, KeyScope};
+pub use events::{Event, EventData, REPLAY_CAPACITY};
+pub use limits::{RateLimit, RateLimiter, RateLimits};
+pub use openapi::error_codes;
+
+use crate::account::AccountSnapshot;
+use crate::base::{ClientId, TransactionId};
+use crate::batch::{BatchMode, BatchReport};
+use crate::engine::Engine;
+use crate::error::TransactionError;
+use crate::history::{HistoryPage, HistoryQuery, StoredTransaction};
+use crate::input::{JsonLines, TransactionRequest};
+use crate::transaction::TransactionStatus;
+use auth::Caller;
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::extract::ws::rejection::WebSocketUpgradeRejection;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::sse::{self, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::{Json, Router};
+use events::EventHub;
+use extract::{ApiJson, ApiPath, ApiQuery};
+use metrics::HttpMetrics;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::io;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio_stream::{Stream, StreamExt};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use utoipa_axum::router::OpenApiRouter;
+use utoipa_axum::routes;
+
+/// Response body for account information.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct AccountResponse {
+    /// Client ID.
+    pub client: u16,
+    /// Funds available for withdrawal.
+    #[schema(value_type = String, example = "75.5")]
+    pub available: Decimal,
+    /// Funds held due to disputes.
+    #[schema(value_type = String, example = "0")]
+    pub held: Decimal,
+    /// Total funds (available + held).
+    #[schema(value_type = String, example = "75.5")]
+    pub total: Decimal,
+    /// Whether the account is frozen after a chargeback.
+    pub locked: bool,
+    /// Number of transactions applied to the account.
+    pub version: u64,
+}
+
+impl From<AccountSnapshot> for AccountResponse {
+    fn from(account: AccountSnapshot) -> Self {
+        Self {
+            client: account.client_id.0,
+            available: account.available,
+            held: account.held,
+            total: account.total,
+            locked: account.locked,
+            version: account.version,
+        }
+    }
+}
+
+/// Request body of `POST /transactions`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct TransactionSubmission {
+    /// The transaction to process.
+    #[serde(flatten)]
+    pub transaction: TransactionRequest,
+    /// Process the transaction only if the client's account is at this
+    /// version (0 for a client without an account); `VERSION_CONFLICT`
+    /// otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_version: Option<u64>,
+}
+
+/// Response body for a stored deposit or withdrawal.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct TransactionResponse {
+    /// Transaction ID.
+    pub transaction_id: u32,
+    /// Owning client ID.
+    pub client: u16,
+    /// Dispute status: `Applied`, `Inflight`, `Resolved` or `Voided`.
+    pub status: TransactionStatus,
+    /// The transaction as requested, with the amount the engine applied.
+    pub request: TransactionRequest,
+}
+
+impl From<StoredTransaction> for TransactionResponse {
+    fn from(stored: StoredTransaction) -> Self {
+        Self {
+            transaction_id: stored.transaction.id().0,
+            client: stored.transaction.client_id().0,
+            status: stored.status,
+            request: stored.transaction.into(),
+        }
+    }
+}
+
+/// Response body for a page of transaction history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct HistoryResponse {
+    /// Matching transactions, oldest first.
+    pub transactions: Vec<TransactionResponse>,
+    /// Opaque cursor for the next page, or `null` on the last page.
+    pub next_cursor: Option<String>,
+}
+
+impl From<HistoryPage> for HistoryResponse {
+    fn from(page: HistoryPage) -> Self {
+        Self {
+            transactions: page.transactions.into_iter().map(Into::into).collect(),
+            next_cursor: page.next_cursor.map(|cursor| cursor.to_string()),
+        }
+    }
+}
+
+/// Outcome of one transaction of a batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    /// The transaction took effect.
+    Applied,
+    /// The transaction was rejected; see the item's `error`.
+    Rejected,
+    /// The transaction would have succeeded, but its atomic batch was
+    /// rejected.
+    RolledBack,
+}
+
+/// Result of one transaction of a batch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct BatchResult {
+    /// Outcome.
+    pub status: BatchStatus,
+    /// Why a [`BatchStatus::Rejected`] transaction was rejected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorResponse>,
+}
+
+impl BatchResult {
+    /// Describes a transaction's result; `committed` is `false` if its batch
+    /// was rolled back.
+    fn new(result: Result<(), TransactionError>, committed: bool) -> Self {
+        match result {
+            Ok(()) if committed => Self {
+                status: BatchStatus::Applied,
+                error: None,
+            },
+            Ok(()) => Self {
+                status: BatchStatus::RolledBack,
+                error: None,
+            },
+            Err(e) => Self {
+                status: BatchStatus::Rejected,
+                error: Some(ApiError::from(e).body),
+            },
+        }
+    }
+}
+
+/// Response body for a batch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct BatchResponse {
+    /// Whether the applied transactions took effect; `false` only for a
+    /// rejected atomic batch.
+    pub committed: bool,
+    /// One result per submitted transaction, in submission order.
+    pub results: Vec<BatchResult>,
+}
+
+impl From<BatchReport> for BatchResponse {
+    fn from(report: BatchReport) -> Self {
+        let committed = report.committed;
+        let results = report
+            .results
+            .into_iter()
+            .map(|result| BatchResult::new(result, committed))
+            .collect();
+        Self { committed, results }
+    }
+}
+
+/// Response body for errors.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ErrorResponse {
+    /// Human-readable message.
+    pub error: String,
+    /// Stable machine-readable code, e.g. `INSUFFICIENT_FUNDS`; see
+    /// [`openapi::error_codes`].
+    #[schema(schema_with = openapi::code_schema)]
+    pub code: String,
+}
+
+/// An API error: an HTTP status with an [`ErrorResponse`] body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiError {
+    /// Response status.
+    pub status: StatusCode,
+    /// Response body.
+    pub body: ErrorResponse,
+    /// Seconds for the `Retry-After` header of a `429`.
+    retry_after: Option<u64>,
+}
+
+impl ApiError {
+    /// Creates an error with the given status, code and message.
+    pub fn new(status: StatusCode, code: &str, error: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: ErrorResponse {
+                error: error.into(),
+                code: code.to_string(),
+            },
+            retry_after: None,
+        }
+    }
+
+    fn account_not_found() -> Self {
+        Self::new(
+            StatusCode::NOT_FOUND,
+            "ACCOUNT_NOT_FOUND",
+            "Account not found",
+        )
+    }
+
+    fn invalid_body(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "INVALID_BODY", message)
+    }
+
+    fn invalid_path(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "INVALID_PATH", message)
+    }
+
+    fn invalid_query(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "INVALID_QUERY", message)
+    }
+}
+
+impl From<TransactionError> for ApiError {
+    fn from(err: TransactionError) -> Self {
+        Self::new(status_of(&err), err.code(), err.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let mut response = (self.status, Json(self.body)).into_response();
+        if self.status == StatusCode::UNAUTHORIZED {
+            response.headers_mut().insert(
+                header::WWW_AUTHENTICATE,
+                header::HeaderValue::from_static("Bearer"),
+            );
+        }
+        if let Some(seconds) = self.retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, header::HeaderValue::from(seconds));
+        }
+        response
+    }
+}
+
+/// Returns the HTTP status for a rejected transaction.
+///
+/// | Status | Errors |
+/// |--------|--------|
+/// | 400 Bad Request | malformed amounts, client mismatch, not disputable |
+/// | 403 Forbidden | account locked |
+/// | 404 Not Found | transaction not found |
+/// | 409 Conflict | duplicate transaction, dispute state conflicts, version conflict |
+/// | 422 Unprocessable Entity | insufficient funds, overflow |
+pub fn status_of(error: &TransactionError) -> StatusCode {
+    match error {
+        TransactionError::MissingAmount
+        | TransactionError::InvalidAmount
+        | TransactionError::ExcessPrecision
+        | TransactionError::ClientMismatch
+        | TransactionError::NotDisputable => StatusCode::BAD_REQUEST,
+        TransactionError::AccountLocked => StatusCode::FORBIDDEN,
+        TransactionError::TransactionNotFound => StatusCode::NOT_FOUND,
+        TransactionError::AlreadyDisputed
+        | TransactionError::NotDisputed
+        | TransactionError::DuplicateTransaction
+        | TransactionError::VersionConflict => StatusCode::CONFLICT,
+        TransactionError::InsufficientFunds | TransactionError::Overflow => {
+            StatusCode::UNPROCESSABLE_ENTITY
+        }
+    }
+}
+
+/// Shared handler state.
+#[derive(Clone)]
+struct AppState {
+    engine: Arc<Engine>,
+    events: Arc<EventHub>,
+    /// Accepted API keys; `None` leaves the API open.
+    keys: Option<Arc<ApiKeys>>,
+    /// The document served at `GET /openapi.json`.
+    spec: Arc<utoipa::openapi::OpenApi>,
+    /// Request counts for `GET /metrics`.
+    http_metrics: Arc<HttpMetrics>,
+    /// Transaction submission limits; `None` leaves submission unlimited.
+    limiter: Option<Arc<RateLimiter>>,
+}
+
+impl AppState {
+    /// Applies the rate limits, if any, to transactions `caller` submits.
+    fn admit<'a>(
+        &self,
+        caller: &Caller,
+        requests: impl IntoIterator<Item = &'a TransactionRequest>,
+    ) -> Result<(), ApiError> {
+        match &self.limiter {
+            Some(limiter) => limiter.admit(caller, requests),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Server settings beyond the engine.
+#[derive(Debug, Clone, Default)]
+pub struct ServerOptions {
+    /// API keys to require; `None` (the default) leaves the API open.
+    pub keys: Option<ApiKeys>,
+    /// Rate limits on transaction submission; `None` (the default) leaves
+    /// it unlimited. Shared so the limits can be reloaded while serving.
+    pub limits: Option<Arc<RateLimiter>>,
+}
+
+/// Builds the API router over `engine` with default [`ServerOptions`].
+pub fn create_router(engine: Arc<Engine>) -> Router {
+    create_router_with(engine, ServerOptions::default())
+}
+
+/// Builds the API router over `engine`.
+pub fn create_router_with(engine: Arc<Engine>, options: ServerOptions) -> Router {
+    let (router, spec) = routes().split_for_parts();
+    let state = AppState {
+        engine,
+        events: Arc::new(EventHub::new()),
+        keys: options.keys.map(Arc::new),
+        spec: Arc::new(spec),
+        http_metrics: Arc::new(HttpMetrics::default()),
+        limiter: options.limits,
+    };
+    router
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            metrics::track,
+        ))
+        .with_state(state)
+}
+
+/// Returns the OpenAPI document of the API, as served at `GET /openapi.json`.
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    routes().split_for_parts().1
+}
+
+/// Every route with its OpenAPI operation.
+fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::with_openapi(openapi::ApiDoc::openapi())
+        .routes(routes!(create_transaction))
+        .routes(routes!(create_batch))
+        .routes(routes!(get_transaction))
+        .routes(routes!(list_accounts))
+        .routes(routes!(get_account))
+        .routes(routes!(account_transactions))
+        .routes(routes!(stream_events))
+        .routes(routes!(websocket_events))
+        .routes(routes!(openapi_json))
+        .routes(routes!(metrics::metrics))
+}
+
+/// Serves the API on `listener` with default [`ServerOptions`] until Ctrl-C
+/// is received.
+///
+/// # Errors
+///
+/// Returns an error if accepting connections fails.
+pub async fn serve(listener: TcpListener, engine: Arc<Engine>) -> io::Result<()> {
+    serve_with(listener, engine, ServerOptions::default()).await
+}
+
+/// Serves the API on `listener` until Ctrl-C is received.
+///
+/// # Errors
+///
+/// Returns an error if accepting connections fails.
+pub async fn serve_with(
+    listener: TcpListener,
+    engine: Arc<Engine>,
+    options: ServerOptions,
+) -> io::Result<()> {
+    if let Ok(addr) = listener.local_addr() {
+        tracing::info!(%addr, keys = options.keys.is_some(), "serving HTTP API");
+    }
+    axum::serve(listener, create_router_with(engine, options))
+        .with_graceful_shutdown(async {
+            // If the handler cannot be installed, run until killed.
+            if tokio::signal::ctrl_c().await.is_err() {
+                std::future::pending::<()>().await;
+            }
+            tracing::info!("shutting down");
+        })
+        .await
+}
+
+/// POST /transactions - Process a transaction.
+#[utoipa::path(
+    post,
+    path = "/transactions",
+    tag = "transactions",
+    request_body = TransactionSubmission,
+    responses(
+        (status = 201, description = "Transaction applied"),
+        (status = 400, description = "Malformed body or invalid transaction", body = ErrorResponse),
+        (status = 401, description = "Missing or unknown API key", body = ErrorResponse),
+        (status = 403, description = "Account locked, or the key may not act for this client", body = ErrorResponse),
+        (status = 404, description = "Disputed transaction not found", body = ErrorResponse),
+        (status = 409, description = "Duplicate transaction ID, or the account is not at the expected version", body = ErrorResponse),
+        (status = 415, description = "Body is not JSON", body = ErrorResponse),
+        (status = 422, description = "Insufficient funds, overflow, or a body of the wrong shape", body = ErrorResponse),
+        (status = 429, description = "Rate limit of the client or key exceeded", body = ErrorResponse,
+            headers(("Retry-After" = u64, description = "Seconds to wait before retrying"))),
+    )
+)]
+async fn create_transaction(
+    State(state): State<AppState>,
+    caller: Caller,
+    ApiJson(submission): ApiJson<TransactionSubmission>,
+) -> Result<StatusCode, ApiError> {
+    let request = submission.transaction;
+    caller.authorize(&request)?;
+    state.admit(&caller, [&request])?;
+    let transaction = request.clone().into();
+    let result = match submission.expected_version {
+        Some(version) => state.engine.process_expecting(transaction, version),
+        None => state.engine.process(transaction),
+    };
+    let outcome = BatchResult::new(result.clone(), true);
+    state.events.publish(&state.engine, [(request, outcome)]);
+    result?;
+    Ok(StatusCode::CREATED)
+}
+
+/// Query parameters of `POST /transactions/batch`.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct BatchParams {
+    /// Apply all transactions or none.
+    #[serde(default)]
+    atomic: bool,
+}
+
+/// POST /transactions/batch - Process transactions in order.
+#[utoipa::path(
+    post,
+    path = "/transactions/batch",
+    tag = "transactions",
+    params(BatchParams),
+    request_body(
+        description = "A JSON array, or one transaction per line with a JSON Lines content type",
+        content(
+            (Vec<TransactionRequest> = "application/json"),
+            (TransactionRequest = "application/x-ndjson"),
+        )
+    ),
+    responses(
+        (status = 200, description = "Outcome of each transaction", body = BatchResponse),
+        (status = 400, description = "Malformed body or query", body = ErrorResponse),
+        (status = 401, description = "Missing or unknown API key", body = ErrorResponse),
+        (status = 403, description = "The key may not act for one of the clients", body = ErrorResponse),
+        (status = 422, description = "Atomic batch rolled back", body = BatchResponse),
+        (status = 429, description = "Rate limit of a client or the key exceeded; nothing was processed", body = ErrorResponse,
+            headers(("Retry-After" = u64, description = "Seconds to wait before retrying"))),
+    )
+)]
+async fn create_batch(
+    State(state): State<AppState>,
+    caller: Caller,
+    ApiQuery(params): ApiQuery<BatchParams>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<(StatusCode, Json<BatchResponse>), ApiError> {
+    let requests = parse_batch(&headers, &body)?;
+    // Nothing is processed unless the key may submit every transaction.
+    for request in &requests {
+        caller.authorize(request)?;
+    }
+    state.admit(&caller, &requests)?;
+    let mode = if params.atomic {
+        BatchMode::Atomic
+    } else {
+        BatchMode::Independent
+    };
+    let report = state
+        .engine
+        .process_batch(requests.iter().cloned().map(Into::into), mode);
+    let status = if report.committed {
+        StatusCode::OK
+    } else {
+        StatusCode::UNPROCESSABLE_ENTITY
+    };
+    let response = BatchResponse::from(report);
+    state.events.publish(
+        &state.engine,
+        requests.into_iter().zip(response.results.iter().cloned()),
+    );
+    Ok((status, Json(response)))
+}
+
+/// Decodes a batch body: JSON Lines if the content type says so, otherwise a
+/// JSON array.
+fn parse_batch(headers: &HeaderMap, body: &[u8]) -> Result<Vec<TransactionRequest>, ApiError> {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+    if matches!(
+        essence,
+        "application/x-ndjson" | "application/jsonl" | "application/jsonlines"
+    ) {
+        JsonLines::new(body)
+            .map(|line| {
+                let line = line.map_err(|e| ApiError::invalid_body(e.to_string()))?;
+                line.request
+                    .map_err(|e| ApiError::invalid_body(format!("line {}: {e}", line.line)))
+            })
+            .collect()
+    } else {
+        serde_json::from_slice(body).map_err(|e| ApiError::invalid_body(e.to_string()))
+    }
+}
+
+/// Query parameters of the event streams.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct StreamParams {
+    /// Only events of this client; required for client keys.
+    client: Option<u16>,
+    /// Resume after this event; overrides the `Last-Event-ID` header.
+    last_event_id: Option<u64>,
+}
+
+impl StreamParams {
+    /// Subscribes to the hub, resuming after the query's `last_event_id` or,
+    /// failing that, the `Last-Event-ID` header. Client keys only see their
+    /// own client's events.
+    fn subscribe(
+        &self,
+        state: &AppState,
+        caller: Caller,
+        headers: &HeaderMap,
+    ) -> Result<impl Stream<Item = Arc<Event>> + Send + use<>, ApiError> {
+        let client = caller.limit_to(self.client)?;
+        let header = match headers.get("last-event-id") {
+            Some(value) => Some(
+                value
+                    .to_str()
+                    .ok()
+                    .and_then(|value| value.trim().parse().ok())
+                    .ok_or_else(|| ApiError::invalid_query("invalid Last-Event-ID header"))?,
+            ),
+            None => None,
+        };
+        Ok(state
+            .events
+            .subscribe(self.last_event_id.or(header), client))
+    }
+}
+
+/// GET /accounts/stream - Server-Sent Events of transactions and accounts.
+#[utoipa::path(
+    get,
+    path = "/accounts/stream",
+    tag = "events",
+    params(
+        StreamParams,
+        ("Last-Event-ID" = Option<u64>, Header, description = "Resume after this event"),
+    ),
+    responses(
+        (status = 200, description = "Event stream; each `data` field is an event", body = Event, content_type = "text/event-stream"),
+        (status = 400, description = "Malformed query or Last-Event-ID", body = ErrorResponse),
+        (status = 401, description = "Missing or unknown API key", body = ErrorResponse),
+        (status = 403, description = "The key may not see this client", body = ErrorResponse),
+    )
+)]
+async fn stream_events(
+    State(state): State<AppState>,
+    caller: Caller,
+    ApiQuery(params): ApiQuery<StreamParams>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<sse::Event, Infallible>>>, ApiError> {
+    let events = params.subscribe(&state, caller, &headers)?.map(|event| {
+        let mut message = sse::Event::default().event(event.name());
+        if let Some(id) = event.id {
+            message = message.id(id.to_string());
+        }
+        Ok(message
+            .json_data(&*event)
+            .expect("events serialize to JSON"))
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// GET /accounts/ws - The event stream over a WebSocket.
+#[utoipa::path(
+    get,
+    path = "/accounts/ws",
+    tag = "events",
+    params(
+        StreamParams,
+        ("Last-Event-ID" = Option<u64>, Header, description = "Resume after this event"),
+    ),
+    responses(
+        (status = 101, description = "WebSocket; each text message is an event", body = Event),
+        (status = 400, description = "Malformed query or not a WebSocket upgrade", body = ErrorResponse),
+        (status = 401, description = "Missing or unknown API key", body = ErrorResponse),
+        (status = 403, description = "The key may not see this client", body = ErrorResponse),
+        (status = 426, description = "The connection cannot be upgraded", body = ErrorResponse),
+    )
+)]
+async fn websocket_events(
+    State(state): State<AppState>,
+    caller: Caller,
+    ApiQuery(params): ApiQuery<StreamParams>,
+    headers: HeaderMap,
+    upgrade: Result<WebSocketUpgrade, WebSocketUpgradeRejection>,
+) -> Result<Response, ApiError> {
+    let events = params.subscribe(&state, caller, &headers)?;
+    let upgrade = upgrade.map_err(|rejection| {
+        ApiError::new(rejection.status(), "INVALID_UPGRADE", rejection.body_text())
+    })?;
+    Ok(upgrade.on_upgrade(move |socket| forward_events(socket, events)))
+}
+
+/// Sends events to a WebSocket until either side closes.
+async fn forward_events(mut socket: WebSocket, events: impl Stream<Item = Arc<Event>>) {
+    let mut events = std::pin::pin!(events);
+    loop {
+        tokio::select! {
+            event = events.next() => {
+                let Some(event) = event else { break };
+                let text = serde_json::to_string(&*event).expect("events serialize to JSON");
+                if socket.send(Message::Text(text.into())).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => match message {
+                // Incoming messages other than close are ignored.
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// GET /accounts/{id} - Get an account by client ID.
+#[utoipa::path(
+    get,
+    path = "/accounts/{id}",
+    tag = "accounts",
+    params(
+        ("id" = u16, Path, description = "Client ID"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag of a previously fetched version"),
+    ),
+    responses(
+        (status = 200, description = "The account", body = AccountResponse,
+            headers(("ETag" = String, description = "Account version, e.g. `\"3\"`"))),
+        (status = 304, description = "The account is still at the version in `If-None-Match`",
+            headers(("ETag" = String, description = "Account version"))),
+        (status = 400, description = "Malformed client ID", body = ErrorResponse),
+        (status = 401, description = "Missing or unknown API key", body = ErrorResponse),
+        (status = 403, description = "The key may not see this client", body = ErrorResponse),
+        (status = 404, description = "No such account", body = ErrorResponse),
+    )
+)]
+async fn get_account(
+    State(state): State<AppState>,
+    caller: Caller,
+    ApiPath(id): ApiPath<u16>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    caller.require_client(id)?;
+    let account = state
+        .engine
+        .get_account(&ClientId(id))
+        .ok_or_else(ApiError::account_not_found)?;
+    let etag = format!("\"{}\"", account.version);
+    let etag_header = [(header::ETAG, etag.clone())];
+    if none_match(&headers, &etag) {
+        return Ok((StatusCode::NOT_MODIFIED, etag_header).into_response());
+    }
+    Ok((etag_header, Json(AccountResponse::from(account))).into_response())
+}
+
+/// Whether an `If-None-Match` header lists `etag` (compared weakly) or `*`.
+fn none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// GET /accounts - List all accounts in ascending client ID order.
+#[utoipa::path(
+    get,
+    path = "/accounts",
+    tag = "accounts",
+    responses(
+        (status = 200, description = "All accounts", body = Vec<AccountResponse>),
+        (status = 401, description = "Missing or unknown API key", body = ErrorResponse),
+        (status = 403, description = "Requires an operator key", body = ErrorResponse),
+    )
+)]
+async fn list_accounts(
+    State(state): State<AppState>,
+    caller: Caller,
+) -> Result<Json<Vec<AccountResponse>>, ApiError> {
+    caller.require_operator()?;
+    Ok(Json(
+        state
+            .engine
+            .accounts()
+            .into_iter()
+            .map(AccountResponse::from)
+            .collect(),
+    ))
+}
+
+/// Query parameters of `GET /transactions/{id}`.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct TransactionParams {
+    /// Owning client, when transaction IDs are unique only per client.
+    client: Option<u16>,
+}
+
+/// GET /transactions/{id} - Get a deposit or withdrawal by ID.
+#[utoipa::path(
+    get,
+    path = "/transactions/{id}",
+    tag = "transactions",
+    params(("id" = u32, Path, description = "Transaction ID"), TransactionParams),
+    responses(
+        (status = 200, description = "The transaction", body = TransactionResponse),
+        (status = 400, description = "Malformed ID or query", body = ErrorResponse),
+        (status = 401, description = "Missing or unknown API key", body = ErrorResponse),
+        (status = 403, description = "The key may not see this client", body = ErrorResponse),
+        (status = 404, description = "No such deposit or withdrawal", body = ErrorResponse),
+        (status = 409, description = "Several clients use this ID", body = ErrorResponse),
+    )
+)]
+async fn get_transaction(
+    State(state): State<AppState>,
+    caller: Caller,
+    ApiPath(id): ApiPath<u32>,
+    ApiQuery(params): ApiQuery<TransactionParams>,
+) -> Result<Json<TransactionResponse>, ApiError> {
+    let id = TransactionId(id);
+    let mut matches = match caller.limit_to(params.client)? {
+        Some(client) => state
+            .engine
+            .transaction(ClientId(client), id)
+            .into_iter()
+            .collect(),
+        None => state.engine.find_transaction(id),
+    };
+    match matches.len() {
+        0 => Err(TransactionError::TransactionNotFound.into()),
+        1 => Ok(Json(matches.remove(0).into())),
+        _ => Err(ApiError::new(
+            StatusCode::CONFLICT,
+            "AMBIGUOUS_TRANSACTION",
+            "Several clients use this transaction ID; pass ?client=",
+        )),
+    }
+}
+
+/// Query parameters of `GET /accounts/{id}/transactions`.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct HistoryParams {
+    /// `next_cursor` of the previous page.
+    cursor: Option<String>,
+    /// Page size, 1 to 500; defaults to 50.
+    #[param(value_type = Option<u32>, minimum = 1, maximum = 500)]
+    limit: Option<String>,
+    /// Only `deposit` or `withdrawal`.
+    #[serde(rename = "type")]
+    #[param(pattern = "^(deposit|withdrawal)$")]
+    kind: Option<String>,
+    /// Only this status, ignoring case.
+    #[param(value_type = Option<TransactionStatus>)]
+    status: Option<String>,
+}
+
+impl HistoryParams {
+    /// Largest page size a client may request.
+    const MAX_LIMIT: usize = 500;
+
+    fn into_query(self) -> Result<HistoryQuery, ApiError> {
+        let cursor = match self.cursor {
+            Some(cursor) => cursor
+                .parse()
+                .map_err(|_| ApiError::invalid_query(format!("invalid cursor '{cursor}'")))?,
+            None => 0,
+        };
+        let limit = match self.limit {
+            Some(limit) => limit
+                .parse()
+                .ok()
+                .filter(|limit| (1..=Self::MAX_LIMIT).contains(limit))
+                .ok_or_else(|| {
+                    ApiError::invalid_query(format!(
+                        "limit must be 1 to {}, got '{limit}'",
+                        Self::MAX_LIMIT
+                    ))
+                })?,
+            None => HistoryQuery::DEFAULT_LIMIT,
+        };
+        let kind = match self.kind.as_deref() {
+            None => None,
+            Some(kind @ ("deposit" | "withdrawal")) => Some(kind.to_string()),
+            Some(other) => {
+                return Err(ApiError::invalid_query(format!(
+                    "unknown type '{other}' (expected deposit or withdrawal)"
+                )));
+            }
+        };
+        let status = self
+            .status
+            .map(|status| status.parse())
+            .transpose()
+            .map_err(ApiError::invalid_query)?;
+        Ok(HistoryQuery {
+            cursor,
+            limit,
+            kind,
+            status,
+        })
+    }
+}
+
+/// GET /accounts/{id}/transactions - Page through a client's history.
+#[utoipa::path(
+    get,
+    path = "/accounts/{id}/transactions",
+    tag = "accounts",
+    params(("id" = u16, Path, description = "Client ID"), HistoryParams),
+    responses(
+        (status = 200, description = "A page of deposits and withdrawals, oldest first", body = HistoryResponse),
+        (status = 400, description = "Malformed client ID or query", body = ErrorResponse),
+        (status = 401, description = "Missing or unknown API key", body = ErrorResponse),
+        (status = 403, description = "The key may not see this client", body = ErrorResponse),
+        (status = 404, description = "No such account", body = ErrorResponse),
+    )
+)]
+async fn account_transactions(
+    State(state): State<AppState>,
+    caller: Caller,
+    ApiPath(id): ApiPath<u16>,
+    ApiQuery(params): ApiQuery<HistoryParams>,
+) -> Result<Json<HistoryResponse>, ApiError> {
+    caller.require_client(id)?;
+    let query = params.into_query()?;
+    state
+        .engine
+        .transaction_history(ClientId(id), &query)
+        .map(|page| Json(page.into()))
+        .ok_or_else(ApiError::account_not_found)
+}
+
+/// GET /openapi.json - This API as an OpenAPI document.
+#[utoipa::path(
+    get,
+    path = "/openapi.json",
+    tag = "meta",
+    security(()),
+    responses((status = 200, description = "OpenAPI 3.1 document", content_type = "application/json"))
+)]
+async fn openapi_json(State(state): State<AppState>) -> Json<utoipa::openapi::OpenApi> {
+    Json(utoipa::openapi::OpenApi::clone(&state.spec))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_map_to_status_and_code() {
+        let err = ApiError::from(TransactionError::InsufficientFunds);
+        assert_eq!(err.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(err.body.code, "INSUFFICIENT_FUNDS");
+        assert_eq!(err.body.error, "insufficient available funds");
+
+        assert_eq!(
+            status_of(&TransactionError::DuplicateTransaction),
+            StatusCode::CONFLICT
+        );
+        assert_eq!(
+            status_of(&TransactionError::AccountLocked),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            status_of(&TransactionError::TransactionNotFound),
+            StatusCode::NOT_FOUND
+        );
+    }
+}
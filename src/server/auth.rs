@@ -0,0 +1,373 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! API key authentication and per-client authorization.
+//!
+//! Keys are loaded from a `.toml` or `.json` file. Each key has a scope:
+//!
+//! ```toml
+//! [[keys]]
+//! name = "back office"      # optional; names rate limit overrides
+//! key = "3f9c1d..."
+//! scope = "operator"        # every endpoint and transaction type
+//!
+//! [[keys]]
+//! key = "a71be0..."
+//! scope = "client"          # only client 7's own account, history and
+//! client = 7                # deposits and withdrawals
+//! ```
+//!
+//! Requests present a key as `Authorization: Bearer <key>` or
+//! `X-API-Key: <key>`. A missing or unknown key is answered with `401
+//! UNAUTHORIZED`, a key without the required scope with `403 FORBIDDEN`.
+
+use super::{ApiError, AppState};
+use crate::config::load_file;
+use crate::error::ConfigError;
+use crate::input::TransactionRequest;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::{StatusCode, header};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+/// What an API key may do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyScope {
+    /// Any endpoint, including disputes, resolves and chargebacks and the
+    /// account list.
+    Operator,
+    /// Only the given client's account, history and events, and deposits
+    /// and withdrawals for that client.
+    Client(u16),
+}
+
+/// The API keys a server accepts.
+///
+/// `Debug` output lists only the number of keys, never the keys themselves.
+#[derive(Clone, Default)]
+pub struct ApiKeys {
+    keys: HashMap<String, KeyInfo>,
+}
+
+/// What is known about an accepted key.
+#[derive(Clone)]
+struct KeyInfo {
+    scope: KeyScope,
+    /// The key's `name`, or `#n` for the n-th key; identifies it in rate
+    /// limits without exposing the key.
+    name: Arc<str>,
+}
+
+/// Keys file document.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeysFile {
+    keys: Vec<KeyEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyEntry {
+    #[serde(default)]
+    name: Option<String>,
+    key: String,
+    scope: ScopeName,
+    #[serde(default)]
+    client: Option<u16>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ScopeName {
+    Operator,
+    Client,
+}
+
+impl ApiKeys {
+    /// Creates an empty key set, which rejects every request.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads keys from a `.toml` or `.json` file.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ConfigError`] if the file cannot be read or parsed, lists
+    /// no keys, or a key is empty, duplicated, or has a scope without the
+    /// matching `client`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let file: KeysFile = load_file(path.as_ref())?;
+        let mut keys = Self::new();
+        for (index, entry) in file.keys.into_iter().enumerate() {
+            let label = entry.name.unwrap_or_else(|| format!("#{}", index + 1));
+            if keys.keys.values().any(|info| *info.name == label) {
+                return Err(ConfigError::Invalid(format!("key {label}: duplicate name")));
+            }
+            let scope = match (entry.scope, entry.client) {
+                (ScopeName::Operator, None) => KeyScope::Operator,
+                (ScopeName::Client, Some(client)) => KeyScope::Client(client),
+                (ScopeName::Operator, Some(_)) => {
+                    return Err(ConfigError::Invalid(format!(
+                        "key {label}: operator keys apply to every client; remove `client`"
+                    )));
+                }
+                (ScopeName::Client, None) => {
+                    return Err(ConfigError::Invalid(format!(
+                        "key {label}: client keys need a `client`"
+                    )));
+                }
+            };
+            keys.insert_named(entry.key, scope, label.clone())
+                .map_err(|reason| ConfigError::Invalid(format!("key {label}: {reason}")))?;
+        }
+        if keys.is_empty() {
+            return Err(ConfigError::Invalid("no keys defined".to_string()));
+        }
+        Ok(keys)
+    }
+
+    /// Adds a key, named `#n` if it is the n-th key.
+    ///
+    /// # Errors
+    ///
+    /// Returns a reason if the key is empty, contains whitespace, or is
+    /// already present.
+    pub fn insert(&mut self, key: impl Into<String>, scope: KeyScope) -> Result<(), String> {
+        let name = format!("#{}", self.keys.len() + 1);
+        self.insert_named(key, scope, name)
+    }
+
+    /// Adds a key with a name, which rate limits refer to it by.
+    ///
+    /// # Errors
+    ///
+    /// As for [`insert`](Self::insert).
+    pub fn insert_named(
+        &mut self,
+        key: impl Into<String>,
+        scope: KeyScope,
+        name: impl Into<String>,
+    ) -> Result<(), String> {
+        let key = key.into();
+        if key.is_empty() || key.chars().any(char::is_whitespace) {
+            return Err("keys must be non-empty and contain no whitespace".to_string());
+        }
+        if self.keys.contains_key(&key) {
+            return Err("duplicate key".to_string());
+        }
+        let name = name.into().into();
+        self.keys.insert(key, KeyInfo { scope, name });
+        Ok(())
+    }
+
+    /// Returns the scope of `key`, if it is known.
+    pub fn scope(&self, key: &str) -> Option<KeyScope> {
+        self.keys.get(key).map(|info| info.scope)
+    }
+
+    /// Returns the name of `key`, if it is known.
+    pub fn name(&self, key: &str) -> Option<&str> {
+        self.keys.get(key).map(|info| &*info.name)
+    }
+
+    /// Number of keys.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns `true` if there are no keys.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+impl fmt::Debug for ApiKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiKeys")
+            .field("len", &self.keys.len())
+            .finish()
+    }
+}
+
+/// The authenticated caller of a request.
+///
+/// Without configured keys every caller is an operator without a key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Caller {
+    scope: KeyScope,
+    /// Name of the key presented, if keys are configured.
+    key: Option<Arc<str>>,
+}
+
+impl Caller {
+    /// Returns the name of the caller's key; see [`ApiKeys::name`].
+    pub(crate) fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    /// Requires an operator key.
+    pub(crate) fn require_operator(&self) -> Result<(), ApiError> {
+        match self.scope {
+            KeyScope::Operator => Ok(()),
+            KeyScope::Client(_) => Err(ApiError::forbidden("requires an operator key")),
+        }
+    }
+
+    /// Requires an operator key or a key for `client`.
+    pub(crate) fn require_client(&self, client: u16) -> Result<(), ApiError> {
+        match self.scope {
+            KeyScope::Client(own) if own != client => Err(ApiError::forbidden(format!(
+                "key is not authorized for client {client}"
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Requires the scope to submit `request`: clients may only deposit and
+    /// withdraw for themselves.
+    pub(crate) fn authorize(&self, request: &TransactionRequest) -> Result<(), ApiError> {
+        match *request {
+            TransactionRequest::Deposit { client_id, .. }
+            | TransactionRequest::Withdrawal { client_id, .. } => self.require_client(client_id),
+            TransactionRequest::Dispute { .. }
+            | TransactionRequest::Resolve { .. }
+            | TransactionRequest::Chargeback { .. } => self.require_operator(),
+        }
+    }
+
+    /// Resolves the client a query is limited to: whatever the operator asked
+    /// for, or always the key's own client.
+    pub(crate) fn limit_to(&self, requested: Option<u16>) -> Result<Option<u16>, ApiError> {
+        match (self.scope, requested) {
+            (KeyScope::Operator, requested) => Ok(requested),
+            (KeyScope::Client(own), None) => Ok(Some(own)),
+            (KeyScope::Client(_), Some(client)) => {
+                self.require_client(client)?;
+                Ok(Some(client))
+            }
+        }
+    }
+}
+
+impl FromRequestParts<AppState> for Caller {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, ApiError> {
+        let Some(keys) = &state.keys else {
+            return Ok(Self {
+                scope: KeyScope::Operator,
+                key: None,
+            });
+        };
+        let header = |name: &str| {
+            parts
+                .headers
+                .get(name)
+                .map(|value| value.to_str().unwrap_or_default())
+        };
+        let key = match (header(header::AUTHORIZATION.as_str()), header("x-api-key")) {
+            (Some(authorization), _) => authorization
+                .split_once(' ')
+                .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+                .map(|(_, token)| token.trim()),
+            (None, Some(key)) => Some(key.trim()),
+            (None, None) => None,
+        };
+        let key = key.ok_or_else(|| ApiError::unauthorized("missing API key"))?;
+        keys.keys
+            .get(key)
+            .map(|info| Self {
+                scope: info.scope,
+                key: Some(Arc::clone(&info.name)),
+            })
+            .ok_or_else(|| ApiError::unauthorized("unknown API key"))
+    }
+}
+
+impl ApiError {
+    fn unauthorized(message: &str) -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", message)
+    }
+
+    fn forbidden(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::FORBIDDEN, "FORBIDDEN", message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    const KEYS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/keys.toml");
+
+    #[test]
+    fn loads_keys_file() {
+        let keys = ApiKeys::from_file(KEYS).unwrap();
+        assert_eq!(keys.len(), 3);
+        assert_eq!(keys.scope("operator-test-key"), Some(KeyScope::Operator));
+        assert_eq!(keys.scope("client-2-test-key"), Some(KeyScope::Client(2)));
+        assert_eq!(keys.scope("guess"), None);
+        assert_eq!(keys.name("operator-test-key"), Some("back office"));
+        assert_eq!(keys.name("client-2-test-key"), Some("#3"));
+        assert_eq!(format!("{keys:?}"), "ApiKeys { len: 3 }");
+    }
+
+    #[test]
+    fn rejects_invalid_keys() {
+        let mut keys = ApiKeys::new();
+        keys.insert("k1", KeyScope::Operator).unwrap();
+        assert!(keys.insert("k1", KeyScope::Client(1)).is_err());
+        assert!(keys.insert("", KeyScope::Operator).is_err());
+        assert!(keys.insert("a b", KeyScope::Operator).is_err());
+    }
+
+    #[test]
+    fn client_keys_are_limited_to_their_client() {
+        let caller = |scope| Caller { scope, key: None };
+        let (operator, client) = (caller(KeyScope::Operator), caller(KeyScope::Client(1)));
+        let deposit = |client_id| TransactionRequest::Deposit {
+            client_id,
+            transaction_id: 1,
+            amount: dec!(1),
+        };
+        let dispute = TransactionRequest::Dispute {
+            client_id: 1,
+            transaction_id: 1,
+        };
+
+        assert!(operator.authorize(&dispute).is_ok());
+        assert!(operator.require_client(9).is_ok());
+        assert!(client.authorize(&deposit(1)).is_ok());
+        assert_eq!(
+            client.authorize(&deposit(2)).unwrap_err().status,
+            StatusCode::FORBIDDEN
+        );
+        assert!(client.authorize(&dispute).is_err());
+        assert!(client.require_operator().is_err());
+
+        assert_eq!(operator.limit_to(None), Ok(None));
+        assert_eq!(client.limit_to(None), Ok(Some(1)));
+        assert_eq!(client.limit_to(Some(1)), Ok(Some(1)));
+        assert!(client.limit_to(Some(2)).is_err());
+    }
+}
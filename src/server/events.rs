@@ -0,0 +1,293 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Live account and transaction events for `GET /accounts/stream` (SSE) and
+//! `GET /accounts/ws` (WebSocket).
+//!
+//! Every transaction submitted over the API publishes a `transaction` event
+//! with its outcome and, if it was applied, an `account` event with the
+//! client's balances afterwards. Events carry increasing IDs. The most recent
+//! [`REPLAY_CAPACITY`] are kept so that a reconnecting subscriber can pass the
+//! last ID it saw and receive what it missed; if that is no longer possible it
+//! receives a `resync` event and should reload state with `GET /accounts`.
+
+use super::{AccountResponse, BatchResult, BatchStatus};
+use crate::engine::Engine;
+use crate::input::TransactionRequest;
+use crate::transaction::TransactionType;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::{Stream, StreamExt};
+use utoipa::ToSchema;
+
+/// Number of recent events kept for resuming subscribers.
+pub const REPLAY_CAPACITY: usize = 1024;
+
+/// An event pushed to stream subscribers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Event {
+    /// Event ID, increasing from 1; `None` for `resync` events.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    /// Payload.
+    #[serde(flatten)]
+    pub data: EventData,
+}
+
+/// Payload of an [`Event`], tagged by `type`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EventData {
+    /// A submitted transaction and its outcome.
+    Transaction {
+        /// The transaction as submitted.
+        request: TransactionRequest,
+        /// Outcome, as in a batch response.
+        #[serde(flatten)]
+        result: BatchResult,
+    },
+    /// A client's balances after an applied transaction.
+    Account(AccountResponse),
+    /// Events were missed; reload state before relying on further events.
+    Resync,
+}
+
+impl Event {
+    /// Event name for SSE: `transaction`, `account` or `resync`.
+    pub fn name(&self) -> &'static str {
+        match self.data {
+            EventData::Transaction { .. } => "transaction",
+            EventData::Account(_) => "account",
+            EventData::Resync => "resync",
+        }
+    }
+
+    /// Client the event concerns; `None` for `resync`.
+    pub fn client(&self) -> Option<u16> {
+        match &self.data {
+            EventData::Transaction { request, .. } => {
+                Some(TransactionType::from(request.clone()).client_id().0)
+            }
+            EventData::Account(account) => Some(account.client),
+            EventData::Resync => None,
+        }
+    }
+
+    fn resync() -> Arc<Self> {
+        Arc::new(Self {
+            id: None,
+            data: EventData::Resync,
+        })
+    }
+}
+
+/// Fan-out of events to subscribers, with a replay buffer.
+#[derive(Debug)]
+pub(crate) struct EventHub {
+    state: Mutex<HubState>,
+    sender: broadcast::Sender<Arc<Event>>,
+}
+
+#[derive(Debug, Default)]
+struct HubState {
+    last_id: u64,
+    recent: VecDeque<Arc<Event>>,
+}
+
+impl HubState {
+    fn push(&mut self, sender: &broadcast::Sender<Arc<Event>>, data: EventData) {
+        self.last_id += 1;
+        let event = Arc::new(Event {
+            id: Some(self.last_id),
+            data,
+        });
+        if self.recent.len() == REPLAY_CAPACITY {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(Arc::clone(&event));
+        // No receivers is not an error: nobody is listening right now.
+        let _ = sender.send(event);
+    }
+}
+
+impl EventHub {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: Mutex::new(HubState::default()),
+            sender: broadcast::channel(REPLAY_CAPACITY).0,
+        }
+    }
+
+    /// Publishes the outcomes of submitted transactions, in order.
+    ///
+    /// Account balances are read while the hub is locked, so each client's
+    /// `account` events appear in the order the balances were observed.
+    pub(crate) fn publish(
+        &self,
+        engine: &Engine,
+        outcomes: impl IntoIterator<Item = (TransactionRequest, BatchResult)>,
+    ) {
+        let mut state = self.state.lock();
+        for (request, result) in outcomes {
+            let client = TransactionType::from(request.clone()).client_id();
+            let applied = result.status == BatchStatus::Applied;
+            state.push(&self.sender, EventData::Transaction { request, result });
+            if applied && let Some(account) = engine.get_account(&client) {
+                state.push(&self.sender, EventData::Account(account.into()));
+            }
+        }
+    }
+
+    /// Subscribes to events for `client` (or all clients), first replaying
+    /// those after `last_id` if given.
+    pub(crate) fn subscribe(
+        &self,
+        last_id: Option<u64>,
+        client: Option<u16>,
+    ) -> impl Stream<Item = Arc<Event>> + Send + use<> {
+        // Subscribing under the lock means no event is both replayed and
+        // received live, and none falls between the two.
+        let state = self.state.lock();
+        let receiver = self.sender.subscribe();
+        let mut replay = Vec::new();
+        if let Some(last_id) = last_id {
+            let oldest = state.recent.front().and_then(|event| event.id);
+            // IDs restart with the server, so an ID from the future is stale too.
+            let missed =
+                last_id > state.last_id || oldest.is_some_and(|oldest| last_id + 1 < oldest);
+            let after = if missed {
+                replay.push(Event::resync());
+                0
+            } else {
+                last_id
+            };
+            replay.extend(
+                state
+                    .recent
+                    .iter()
+                    .filter(|event| event.id.is_some_and(|id| id > after))
+                    .cloned(),
+            );
+        }
+        drop(state);
+
+        let live = BroadcastStream::new(receiver).map(|item| match item {
+            Ok(event) => event,
+            Err(BroadcastStreamRecvError::Lagged(_)) => Event::resync(),
+        });
+        tokio_stream::iter(replay).chain(live).filter(move |event| {
+            client.is_none_or(|client| event.client().is_none_or(|c| c == client))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::{BatchStatus, ErrorResponse};
+    use rust_decimal_macros::dec;
+
+    fn deposit(client_id: u16, transaction_id: u32) -> TransactionRequest {
+        TransactionRequest::Deposit {
+            client_id,
+            transaction_id,
+            amount: dec!(1),
+        }
+    }
+
+    fn publish(hub: &EventHub, engine: &Engine, request: TransactionRequest) {
+        let result = engine.process(request.clone().into());
+        hub.publish(engine, [(request, BatchResult::new(result, true))]);
+    }
+
+    async fn replayed(hub: &EventHub, last_id: Option<u64>, client: Option<u16>) -> Vec<String> {
+        let stream = hub.subscribe(last_id, client);
+        let stream = tokio_stream::StreamExt::timeout(stream, std::time::Duration::from_millis(20));
+        let mut stream = std::pin::pin!(stream);
+        let mut seen = Vec::new();
+        while let Some(Ok(event)) = stream.next().await {
+            seen.push(match event.id {
+                Some(id) => format!("{id}:{}", event.name()),
+                None => event.name().to_string(),
+            });
+        }
+        seen
+    }
+
+    #[tokio::test]
+    async fn resumes_after_last_event_id() {
+        let (hub, engine) = (EventHub::new(), Engine::new());
+        publish(&hub, &engine, deposit(1, 1));
+        publish(&hub, &engine, deposit(2, 2));
+        publish(&hub, &engine, deposit(2, 1));
+
+        assert!(replayed(&hub, None, None).await.is_empty());
+        assert_eq!(
+            replayed(&hub, Some(2), None).await,
+            ["3:transaction", "4:account", "5:transaction"]
+        );
+        assert_eq!(
+            replayed(&hub, Some(0), Some(2)).await,
+            ["3:transaction", "4:account", "5:transaction"]
+        );
+        // An ID from before a restart cannot be resumed
+        assert_eq!(
+            replayed(&hub, Some(99), Some(1)).await,
+            ["resync", "1:transaction", "2:account"]
+        );
+    }
+
+    #[tokio::test]
+    async fn resyncs_when_replay_buffer_has_moved_on() {
+        let (hub, engine) = (EventHub::new(), Engine::new());
+        for id in 0..REPLAY_CAPACITY as u32 {
+            publish(&hub, &engine, deposit(1, id));
+        }
+        let seen = replayed(&hub, Some(1), None).await;
+        assert_eq!(seen[0], "resync");
+        assert_eq!(seen.len(), REPLAY_CAPACITY + 1);
+    }
+
+    #[test]
+    fn events_round_trip_through_json() {
+        let event = Event {
+            id: Some(7),
+            data: EventData::Transaction {
+                request: deposit(1, 2),
+                result: BatchResult {
+                    status: BatchStatus::Rejected,
+                    error: Some(ErrorResponse {
+                        error: "duplicate transaction ID".to_string(),
+                        code: "DUPLICATE_TRANSACTION".to_string(),
+                    }),
+                },
+            },
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "transaction");
+        assert_eq!(json["status"], "rejected");
+        assert_eq!(json["request"]["type"], "deposit");
+        assert_eq!(serde_json::from_value::<Event>(json).unwrap(), event);
+        let resync = serde_json::to_string(&*Event::resync()).unwrap();
+        assert_eq!(resync, r#"{"type":"resync"}"#);
+    }
+}
@@ -0,0 +1,87 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Extractors that reject malformed requests with an [`ErrorResponse`].
+//!
+//! axum's own [`Json`], [`Path`] and [`Query`] answer a malformed request
+//! with a plain-text body; these wrappers keep every error response in the
+//! documented shape.
+//!
+//! [`ErrorResponse`]: super::ErrorResponse
+
+use super::ApiError;
+use axum::Json;
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+use axum::extract::{FromRequest, FromRequestParts, Path, Query, Request};
+use axum::http::request::Parts;
+use serde::de::DeserializeOwned;
+
+/// JSON request body; rejected with `INVALID_BODY`.
+#[derive(Debug)]
+pub(crate) struct ApiJson<T>(pub(crate) T);
+
+/// Path parameters; rejected with `400 INVALID_PATH`.
+#[derive(Debug)]
+pub(crate) struct ApiPath<T>(pub(crate) T);
+
+/// Query parameters; rejected with `400 INVALID_QUERY`.
+#[derive(Debug)]
+pub(crate) struct ApiQuery<T>(pub(crate) T);
+
+impl<T: DeserializeOwned, S: Send + Sync> FromRequest<S> for ApiJson<T> {
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, ApiError> {
+        match Json::from_request(request, state).await {
+            Ok(Json(value)) => Ok(Self(value)),
+            // Keep axum's status: 415 without a JSON content type, 422 for
+            // well-formed JSON of the wrong shape, 400 otherwise.
+            Err(rejection @ JsonRejection::MissingJsonContentType(_)) => Err(ApiError::new(
+                rejection.status(),
+                "UNSUPPORTED_MEDIA_TYPE",
+                rejection.body_text(),
+            )),
+            Err(rejection) => Err(ApiError::new(
+                rejection.status(),
+                "INVALID_BODY",
+                rejection.body_text(),
+            )),
+        }
+    }
+}
+
+impl<T: DeserializeOwned + Send, S: Send + Sync> FromRequestParts<S> for ApiPath<T> {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, ApiError> {
+        Path::from_request_parts(parts, state)
+            .await
+            .map(|Path(value)| Self(value))
+            .map_err(|rejection: PathRejection| ApiError::invalid_path(rejection.body_text()))
+    }
+}
+
+impl<T: DeserializeOwned, S: Send + Sync> FromRequestParts<S> for ApiQuery<T> {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, ApiError> {
+        Query::from_request_parts(parts, state)
+            .await
+            .map(|Query(value)| Self(value))
+            .map_err(|rejection: QueryRejection| ApiError::invalid_query(rejection.body_text()))
+    }
+}
@@ -0,0 +1,467 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Token-bucket rate limits on transaction submission.
+//!
+//! Limits are loaded from a `.toml` or `.json` file:
+//!
+//! ```toml
+//! [client]              # every client ID: 20 transactions a second,
+//! rate = 20             # bursts of up to 50
+//! burst = 50
+//!
+//! [key]                 # every API key
+//! rate = 100
+//! burst = 200
+//!
+//! [clients.7]           # client 7 instead of [client]
+//! rate = 1
+//! burst = 5
+//!
+//! [keys."back office"]  # the key named "back office" instead of [key]
+//! rate = 1000
+//! burst = 1000
+//! ```
+//!
+//! Each client ID and each key has a bucket holding up to `burst` tokens,
+//! refilled at `rate` tokens a second. A transaction takes a token from its
+//! client's bucket and from the bucket of the key that submitted it; a batch
+//! takes one per transaction. A submission is admitted when every bucket it
+//! draws from holds enough tokens (a full bucket is enough for a batch larger
+//! than `burst`, which then leaves the bucket in debt). Otherwise nothing is
+//! taken or processed, and the request is answered with `429 RATE_LIMITED`
+//! and a `Retry-After` header. Without a matching section a bucket is
+//! unlimited; per-key limits apply only when API keys are configured.
+
+use super::ApiError;
+use super::auth::Caller;
+use crate::TransactionType;
+use crate::config::load_file;
+use crate::error::ConfigError;
+use crate::input::TransactionRequest;
+use axum::http::StatusCode;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+/// A token bucket's refill rate and capacity.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimit {
+    /// Tokens added per second.
+    pub rate: f64,
+    /// Bucket capacity: the most transactions accepted at once.
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Creates a limit of `rate` transactions a second in bursts of `burst`.
+    pub fn new(rate: f64, burst: u32) -> Self {
+        Self { rate, burst }
+    }
+
+    fn validate(&self, name: &str) -> Result<(), ConfigError> {
+        if !(self.rate.is_finite() && self.rate > 0.0) {
+            return Err(ConfigError::Invalid(format!(
+                "{name}: rate must be a positive number"
+            )));
+        }
+        if self.burst == 0 {
+            return Err(ConfigError::Invalid(format!(
+                "{name}: burst must be at least 1"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Rate limits per client ID and per API key; see the [module
+/// documentation](self) for the file format.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct RateLimits {
+    /// Limit for each client without an entry in `clients`.
+    pub client: Option<RateLimit>,
+    /// Limit for each API key without an entry in `keys`.
+    pub key: Option<RateLimit>,
+    /// Limits for specific clients.
+    pub clients: HashMap<u16, RateLimit>,
+    /// Limits for specific API keys, by key name.
+    pub keys: HashMap<String, RateLimit>,
+}
+
+impl RateLimits {
+    /// Loads limits from a `.toml` or `.json` file.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ConfigError`] if the file cannot be read or parsed, or a
+    /// limit has a rate that is not positive or a burst of 0.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let limits: Self = load_file(path.as_ref())?;
+        limits.validate()?;
+        Ok(limits)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let defaults = [("client", self.client), ("key", self.key)];
+        for (name, limit) in defaults {
+            limit.map(|limit| limit.validate(name)).transpose()?;
+        }
+        for (client, limit) in &self.clients {
+            limit.validate(&format!("client {client}"))?;
+        }
+        for (key, limit) in &self.keys {
+            limit.validate(&format!("key {key}"))?;
+        }
+        Ok(())
+    }
+
+    fn limit(&self, bucket: &Bucket) -> Option<RateLimit> {
+        match bucket {
+            Bucket::Client(client) => self.clients.get(client).copied().or(self.client),
+            Bucket::Key(key) => self.keys.get(&**key).copied().or(self.key),
+        }
+    }
+}
+
+/// Whose tokens a bucket holds.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Bucket {
+    Client(u16),
+    Key(Arc<str>),
+}
+
+/// Tokens left in a bucket as of `updated`; negative after an oversized batch.
+#[derive(Debug, Clone, Copy)]
+struct Tokens {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Enforces [`RateLimits`], which can be replaced while the server runs.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    limits: RwLock<RateLimits>,
+    buckets: Mutex<HashMap<Bucket, Tokens>>,
+}
+
+impl RateLimiter {
+    /// Creates a limiter enforcing `limits`, with every bucket full.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Invalid`] if a limit has a rate that is not
+    /// positive or a burst of 0.
+    pub fn new(limits: RateLimits) -> Result<Self, ConfigError> {
+        limits.validate()?;
+        Ok(Self {
+            limits: RwLock::new(limits),
+            buckets: Mutex::default(),
+        })
+    }
+
+    /// Returns the limits in effect.
+    pub fn limits(&self) -> RateLimits {
+        self.limits.read().clone()
+    }
+
+    /// Replaces the limits.
+    ///
+    /// Buckets keep their tokens, capped at the new bursts, so a reload does
+    /// not hand out a fresh burst.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Invalid`], keeping the previous limits, if a
+    /// limit has a rate that is not positive or a burst of 0.
+    pub fn reload(&self, limits: RateLimits) -> Result<(), ConfigError> {
+        limits.validate()?;
+        *self.limits.write() = limits;
+        Ok(())
+    }
+
+    /// Reloads limits from `path` whenever the file changes, checking every
+    /// `interval`. A file that fails to load is logged and the previous limits
+    /// stay in effect.
+    pub async fn watch(self: Arc<Self>, path: PathBuf, interval: Duration) {
+        let stamp = |path: &Path| {
+            let metadata = std::fs::metadata(path).ok()?;
+            Some((metadata.modified().ok()?, metadata.len()))
+        };
+        let mut seen: Option<(SystemTime, u64)> = stamp(&path);
+        loop {
+            tokio::time::sleep(interval).await;
+            let current = stamp(&path);
+            if current == seen {
+                continue;
+            }
+            seen = current;
+            match RateLimits::from_file(&path).and_then(|limits| self.reload(limits)) {
+                Ok(()) => {
+                    tracing::info!(path = %path.display(), "rate limits reloaded");
+                }
+                Err(error) => tracing::warn!(
+                    path = %path.display(),
+                    %error,
+                    "keeping previous rate limits"
+                ),
+            }
+        }
+    }
+
+    /// Takes tokens for `requests` submitted by `caller`, or answers
+    /// `429 RATE_LIMITED` without taking any.
+    pub(crate) fn admit<'a>(
+        &self,
+        caller: &Caller,
+        requests: impl IntoIterator<Item = &'a TransactionRequest>,
+    ) -> Result<(), ApiError> {
+        let mut costs: HashMap<Bucket, u32> = HashMap::new();
+        for request in requests {
+            let client = TransactionType::from(request.clone()).client_id().0;
+            *costs.entry(Bucket::Client(client)).or_default() += 1;
+            if let Some(key) = caller.key() {
+                *costs.entry(Bucket::Key(key.into())).or_default() += 1;
+            }
+        }
+        self.take(&costs, Instant::now()).map_err(|retry_after| {
+            tracing::info!(
+                retry_after = retry_after.as_secs_f64(),
+                "transaction submission rate limited"
+            );
+            ApiError::rate_limited(retry_after)
+        })
+    }
+
+    /// Takes `costs` tokens from their buckets if every bucket can afford
+    /// them, or returns how long until they all can.
+    fn take(&self, costs: &HashMap<Bucket, u32>, now: Instant) -> Result<(), Duration> {
+        let limits = self.limits.read();
+        let mut buckets = self.buckets.lock();
+        let mut wait = Duration::ZERO;
+        let mut admitted = Vec::with_capacity(costs.len());
+        for (bucket, &cost) in costs {
+            let Some(limit) = limits.limit(bucket) else {
+                continue;
+            };
+            let burst = f64::from(limit.burst);
+            let tokens = buckets.entry(bucket.clone()).or_insert(Tokens {
+                tokens: burst,
+                updated: now,
+            });
+            let elapsed = now.saturating_duration_since(tokens.updated).as_secs_f64();
+            tokens.tokens = (tokens.tokens + elapsed * limit.rate).min(burst);
+            tokens.updated = now;
+            let needed = f64::from(cost).min(burst);
+            if tokens.tokens >= needed {
+                admitted.push((bucket, cost));
+            } else {
+                // A tiny rate can put the wait beyond what a Duration holds
+                let refill = (needed - tokens.tokens) / limit.rate;
+                wait = wait.max(Duration::try_from_secs_f64(refill).unwrap_or(Duration::MAX));
+            }
+        }
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+        for (bucket, cost) in admitted {
+            if let Some(tokens) = buckets.get_mut(bucket) {
+                tokens.tokens -= f64::from(cost);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ApiError {
+    fn rate_limited(retry_after: Duration) -> Self {
+        let mut error = Self::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            "RATE_LIMITED",
+            "too many transactions; retry later",
+        );
+        // Whole seconds, rounded up so that retrying on time succeeds; the
+        // cast saturates for waits that do not fit.
+        error.retry_after = Some(retry_after.as_secs_f64().ceil().max(1.0) as u64);
+        error
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(client: RateLimit) -> RateLimiter {
+        RateLimiter::new(RateLimits {
+            client: Some(client),
+            ..RateLimits::default()
+        })
+        .unwrap()
+    }
+
+    fn cost(client: u16, transactions: u32) -> HashMap<Bucket, u32> {
+        HashMap::from([(Bucket::Client(client), transactions)])
+    }
+
+    #[test]
+    fn buckets_allow_bursts_then_refill() {
+        let limiter = limiter(RateLimit::new(2.0, 3));
+        let start = Instant::now();
+        for _ in 0..3 {
+            assert_eq!(limiter.take(&cost(1, 1), start), Ok(()));
+        }
+        assert_eq!(
+            limiter.take(&cost(1, 1), start),
+            Err(Duration::from_millis(500))
+        );
+        // Other clients have buckets of their own
+        assert_eq!(limiter.take(&cost(2, 1), start), Ok(()));
+
+        let later = start + Duration::from_millis(500);
+        assert_eq!(limiter.take(&cost(1, 1), later), Ok(()));
+        assert!(limiter.take(&cost(1, 1), later).is_err());
+        // Refills stop at the burst
+        let much_later = start + Duration::from_secs(60);
+        assert_eq!(limiter.take(&cost(1, 3), much_later), Ok(()));
+        assert!(limiter.take(&cost(1, 1), much_later).is_err());
+    }
+
+    #[test]
+    fn oversized_batches_need_a_full_bucket_and_leave_debt() {
+        let limiter = limiter(RateLimit::new(1.0, 2));
+        let start = Instant::now();
+        assert_eq!(limiter.take(&cost(1, 5), start), Ok(()));
+        let after = start + Duration::from_secs(2);
+        assert_eq!(
+            limiter.take(&cost(1, 1), after),
+            Err(Duration::from_secs(2))
+        );
+    }
+
+    #[test]
+    fn rejected_submissions_take_no_tokens() {
+        let limiter = RateLimiter::new(RateLimits {
+            client: Some(RateLimit::new(1.0, 5)),
+            key: Some(RateLimit::new(1.0, 1)),
+            ..RateLimits::default()
+        })
+        .unwrap();
+        let start = Instant::now();
+        let mut costs = cost(1, 2);
+        costs.insert(Bucket::Key("k".into()), 2);
+        assert!(limiter.take(&costs, start).is_ok());
+        assert!(limiter.take(&costs, start).is_err());
+        // The failed attempt left client 1 with its 3 remaining tokens
+        assert_eq!(limiter.take(&cost(1, 3), start), Ok(()));
+    }
+
+    #[test]
+    fn overrides_and_reloads_apply_to_existing_buckets() {
+        let limiter = RateLimiter::new(RateLimits {
+            client: Some(RateLimit::new(1.0, 1)),
+            clients: HashMap::from([(7, RateLimit::new(1.0, 10))]),
+            ..RateLimits::default()
+        })
+        .unwrap();
+        let start = Instant::now();
+        assert_eq!(limiter.take(&cost(7, 10), start), Ok(()));
+        assert_eq!(limiter.take(&cost(1, 1), start), Ok(()));
+        assert!(limiter.take(&cost(1, 1), start).is_err());
+
+        limiter.reload(RateLimits::default()).unwrap();
+        assert_eq!(limiter.take(&cost(1, 100), start), Ok(()));
+        limiter
+            .reload(RateLimits {
+                client: Some(RateLimit::new(1.0, 1)),
+                ..RateLimits::default()
+            })
+            .unwrap();
+        // Client 7's bucket is still drained
+        assert!(limiter.take(&cost(7, 1), start).is_err());
+    }
+
+    #[test]
+    fn tiny_rates_saturate_the_wait() {
+        let limiter = limiter(RateLimit::new(1e-20, 1));
+        let start = Instant::now();
+        assert_eq!(limiter.take(&cost(1, 1), start), Ok(()));
+        assert_eq!(limiter.take(&cost(1, 1), start), Err(Duration::MAX));
+        assert_eq!(
+            ApiError::rate_limited(Duration::MAX).retry_after,
+            Some(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn limiters_reject_invalid_limits() {
+        let client_3 = |limit| RateLimits {
+            clients: HashMap::from([(3, limit)]),
+            ..RateLimits::default()
+        };
+        for limit in [
+            RateLimit::new(0.0, 1),
+            RateLimit::new(-1.0, 1),
+            RateLimit::new(f64::NAN, 1),
+            RateLimit::new(f64::INFINITY, 1),
+            RateLimit::new(1.0, 0),
+        ] {
+            assert!(RateLimiter::new(client_3(limit)).is_err(), "{limit:?}");
+        }
+
+        let limiter = limiter(RateLimit::new(1.0, 1));
+        let error = limiter
+            .reload(client_3(RateLimit::new(0.0, 1)))
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("client 3: rate must be a positive number"),
+            "{error}"
+        );
+        // The previous limits stay in effect
+        assert_eq!(limiter.limits().client, Some(RateLimit::new(1.0, 1)));
+        assert!(limiter.limits().clients.is_empty());
+    }
+
+    #[test]
+    fn loads_and_validates_files() {
+        let path = std::env::temp_dir().join(format!("ledger-limits-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            "[client]\nrate = 20\nburst = 50\n\n[keys.\"back office\"]\nrate = 0.5\nburst = 1\n",
+        )
+        .unwrap();
+        let limits = RateLimits::from_file(&path).unwrap();
+        assert_eq!(limits.client, Some(RateLimit::new(20.0, 50)));
+        assert_eq!(limits.keys["back office"], RateLimit::new(0.5, 1));
+        assert_eq!(limits.key, None);
+
+        std::fs::write(&path, "[clients.3]\nrate = 0\nburst = 1\n").unwrap();
+        let error = RateLimits::from_file(&path).unwrap_err().to_string();
+        assert!(
+            error.contains("client 3: rate must be a positive number"),
+            "{error}"
+        );
+        std::fs::write(&path, "[client]\nrate = 1\nburst = 1\nwindow = 2\n").unwrap();
+        assert!(RateLimits::from_file(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
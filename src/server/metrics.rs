@@ -0,0 +1,141 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! `GET /metrics`: engine metrics plus HTTP request counts, in the Prometheus
+//! text format, and the middleware that counts and traces requests.
+
+use super::auth::Caller;
+use super::{ApiError, AppState, ErrorResponse};
+use crate::metrics::{Histogram, header};
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::Method;
+use axum::http::header::CONTENT_TYPE;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Instant;
+use tracing::Instrument;
+
+/// Content type of the Prometheus text exposition format.
+const TEXT_FORMAT: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Request counts and latency of the HTTP API.
+#[derive(Debug, Default)]
+pub(crate) struct HttpMetrics {
+    /// Requests per method, route template and status code.
+    requests: Mutex<BTreeMap<(&'static str, String, u16), u64>>,
+    latency: Histogram,
+}
+
+impl HttpMetrics {
+    fn render(&self, out: &mut String) -> std::fmt::Result {
+        header(
+            out,
+            "ledger_http_requests_total",
+            "counter",
+            "HTTP requests, by method, route and status.",
+        )?;
+        for ((method, route, status), count) in self.requests.lock().iter() {
+            writeln!(
+                out,
+                "ledger_http_requests_total{{method=\"{method}\",route=\"{route}\",status=\"{status}\"}} {count}"
+            )?;
+        }
+        header(
+            out,
+            "ledger_http_request_duration_seconds",
+            "histogram",
+            "Time to produce a response; streams count until their headers are sent.",
+        )?;
+        self.latency
+            .write(out, "ledger_http_request_duration_seconds")
+    }
+}
+
+/// Names a request method for the `method` label, lumping extension methods
+/// together as `other` so that callers cannot mint new series at will.
+fn method_label(method: &Method) -> &'static str {
+    match *method {
+        Method::GET => "GET",
+        Method::HEAD => "HEAD",
+        Method::POST => "POST",
+        Method::PUT => "PUT",
+        Method::DELETE => "DELETE",
+        Method::CONNECT => "CONNECT",
+        Method::OPTIONS => "OPTIONS",
+        Method::TRACE => "TRACE",
+        Method::PATCH => "PATCH",
+        _ => "other",
+    }
+}
+
+/// Middleware counting every request by its route template, so that client
+/// and transaction IDs do not multiply the series, and running it in an
+/// `INFO` `request` span that the engine's spans nest under.
+pub(super) async fn track(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let method = method_label(request.method());
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched", MatchedPath::as_str)
+        .to_string();
+    let span = tracing::info_span!("request", method, route = %route);
+    let started = Instant::now();
+    let response = next.run(request).instrument(span.clone()).await;
+    let elapsed = started.elapsed();
+    span.in_scope(|| {
+        tracing::info!(
+            status = response.status().as_u16(),
+            micros = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX),
+            "request finished"
+        )
+    });
+    state.http_metrics.latency.observe(elapsed);
+    *state
+        .http_metrics
+        .requests
+        .lock()
+        .entry((method, route, response.status().as_u16()))
+        .or_default() += 1;
+    response
+}
+
+/// GET /metrics - Engine and HTTP metrics for Prometheus.
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "meta",
+    responses(
+        (status = 200, description = "Prometheus text exposition format", body = String, content_type = "text/plain"),
+        (status = 401, description = "Missing or unknown API key", body = ErrorResponse),
+        (status = 403, description = "Requires an operator key", body = ErrorResponse),
+    )
+)]
+pub(super) async fn metrics(
+    State(state): State<AppState>,
+    caller: Caller,
+) -> Result<Response, ApiError> {
+    caller.require_operator()?;
+    let mut text = state.engine.metrics_text();
+    state
+        .http_metrics
+        .render(&mut text)
+        .expect("writing to a String cannot fail");
+    Ok(([(CONTENT_TYPE, TEXT_FORMAT)], text).into_response())
+}
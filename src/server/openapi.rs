@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! OpenAPI 3.1 description of the API, served at `GET /openapi.json`.
+//!
+//! Paths come from the `#[utoipa::path]` attributes of the handlers, which
+//! are also what the router is built from, and schemas from the DTO types, so
+//! the document cannot list a route the server does not have. The error
+//! `code` enum is generated from [`TransactionError::code`] and the codes the
+//! API adds.
+
+use crate::error::TransactionError;
+use utoipa::Modify;
+use utoipa::openapi::OpenApi;
+use utoipa::openapi::schema::{Object, ObjectBuilder, Type};
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+
+/// Error codes the API adds to those of [`TransactionError`].
+pub const API_ERROR_CODES: &[&str] = &[
+    "ACCOUNT_NOT_FOUND",
+    "AMBIGUOUS_TRANSACTION",
+    "INVALID_BODY",
+    "INVALID_PATH",
+    "INVALID_QUERY",
+    "INVALID_UPGRADE",
+    "UNSUPPORTED_MEDIA_TYPE",
+    "UNAUTHORIZED",
+    "FORBIDDEN",
+    "RATE_LIMITED",
+];
+
+/// Returns every `code` an [`ErrorResponse`](super::ErrorResponse) can carry.
+pub fn error_codes() -> Vec<&'static str> {
+    TransactionError::ALL
+        .iter()
+        .map(TransactionError::code)
+        .chain(API_ERROR_CODES.iter().copied())
+        .collect()
+}
+
+/// Schema of [`ErrorResponse::code`](super::ErrorResponse::code).
+pub(super) fn code_schema() -> Object {
+    ObjectBuilder::new()
+        .schema_type(Type::String)
+        .description(Some("Stable machine-readable code"))
+        .enum_values(Some(error_codes()))
+        .build()
+}
+
+/// The parts of the document not derived from handlers.
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    info(
+        title = "Ledger API",
+        description = "Deposits, withdrawals and the dispute lifecycle over HTTP. \
+            When the server is started with API keys, every request except \
+            `GET /openapi.json` needs one, as a bearer token or `X-API-Key` header."
+    ),
+    modifiers(&SecuritySchemes),
+    security(("bearer" = []), ("api_key" = [])),
+    tags(
+        (name = "transactions", description = "Submitting and looking up transactions"),
+        (name = "accounts", description = "Balances and history"),
+        (name = "events", description = "Live updates"),
+        (name = "meta", description = "This document"),
+    )
+)]
+pub(super) struct ApiDoc;
+
+/// Adds the `bearer` and `api_key` security schemes.
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-API-Key"))),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_codes_are_unique() {
+        let mut codes = error_codes();
+        let count = codes.len();
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), count);
+    }
+}
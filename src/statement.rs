@@ -0,0 +1,367 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Bank statement import.
+//!
+//! Deposits and withdrawals often originate from bank statements rather than
+//! a transaction feed. This module parses ISO 20022 camt.053 XML and SWIFT
+//! MT940 statements into [`StatementEntry`] values, maps each entry to a
+//! client through a [`ClientLookup`] table keyed by the entry's reference
+//! fields, and turns matched entries into [`TransactionType::Deposit`] (for
+//! credits) or [`TransactionType::Withdrawal`] (for debits) with a
+//! [`StatementImporter`]. Entries without a matching reference are returned
+//! separately so they can be reported.
+//!
+//! ```
+//! use ledger_demo_rs::{ClientId, ClientLookup, StatementFormat, StatementImporter};
+//!
+//! let mt940 = ":20:STMT\n:25:DE89370400440532013000\n:28C:1/1\n\
+//!              :60F:C250101EUR0,00\n\
+//!              :61:2501020102C100,00NTRFNONREF\n:86:Invoice CUST-7\n\
+//!              :62F:C250102EUR100,00\n-";
+//! let entries = StatementFormat::Mt940.parse(mt940).unwrap();
+//!
+//! let mut lookup = ClientLookup::new();
+//! lookup.insert("CUST-7", ClientId(7));
+//! let import = StatementImporter::new(lookup, 1).import(entries);
+//! assert_eq!(import.transactions.len(), 1);
+//! assert!(import.unmatched.is_empty());
+//! ```
+
+mod camt053;
+mod mt940;
+
+use crate::base::{ClientId, TransactionId};
+use crate::error::StatementError;
+use crate::transaction::TransactionType;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Bank statement format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatementFormat {
+    /// ISO 20022 `BkToCstmrStmt` (camt.053) XML.
+    Camt053,
+    /// SWIFT MT940 customer statement.
+    Mt940,
+}
+
+impl StatementFormat {
+    /// Guesses the format from a file extension: `.xml` is camt.053 and
+    /// `.sta`, `.mt940` and `.940` are MT940.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "xml" => Some(Self::Camt053),
+            "sta" | "mt940" | "940" => Some(Self::Mt940),
+            _ => None,
+        }
+    }
+
+    /// Parses a whole statement file into its booked entries, in file order.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`StatementError`] if the document is malformed or an entry
+    /// lacks a valid amount or credit/debit mark.
+    pub fn parse(self, input: &str) -> Result<Vec<StatementEntry>, StatementError> {
+        match self {
+            Self::Camt053 => camt053::parse(input),
+            Self::Mt940 => mt940::parse(input),
+        }
+    }
+}
+
+impl FromStr for StatementFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "camt053" | "camt.053" => Ok(Self::Camt053),
+            "mt940" => Ok(Self::Mt940),
+            other => Err(format!(
+                "unknown statement format '{other}' (expected camt053 or mt940)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for StatementFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Camt053 => "camt053",
+            Self::Mt940 => "mt940",
+        })
+    }
+}
+
+/// Direction of a statement entry from the account holder's perspective.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Money received; imported as a deposit.
+    Credit,
+    /// Money paid out; imported as a withdrawal.
+    Debit,
+}
+
+/// One booked movement on a bank statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatementEntry {
+    /// 1-based line in the statement file where the entry starts.
+    pub line: u64,
+    /// Whether the account was credited or debited.
+    pub direction: Direction,
+    /// Positive entry amount.
+    pub amount: Decimal,
+    /// ISO 4217 currency code, if the format states one per entry.
+    pub currency: Option<String>,
+    /// Booking date as written in the statement.
+    pub booking_date: Option<String>,
+    /// Reference texts used for client lookup, most specific first (e.g.
+    /// structured creditor reference, end-to-end ID, then remittance text).
+    pub references: Vec<String>,
+}
+
+/// Maps statement references to clients.
+///
+/// Keys are compared case-insensitively. An entry matches a key if one of its
+/// references equals the key, or contains it as a token separated by
+/// whitespace, `/`, `?` or `+` (separators used in MT940 `:86:` fields).
+#[derive(Debug, Clone, Default)]
+pub struct ClientLookup {
+    clients: HashMap<String, ClientId>,
+}
+
+impl ClientLookup {
+    /// Creates an empty lookup table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a CSV table with `reference` and `client` columns.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StatementError::Lookup`] if the CSV is malformed or a client
+    /// ID is not a valid `u16`.
+    pub fn from_csv<R: Read>(reader: R) -> Result<Self, StatementError> {
+        #[derive(serde::Deserialize)]
+        struct Row {
+            reference: String,
+            client: u16,
+        }
+
+        let mut lookup = Self::new();
+        let mut rdr = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(reader);
+        for row in rdr.deserialize() {
+            let row: Row = row?;
+            lookup.insert(&row.reference, ClientId(row.client));
+        }
+        Ok(lookup)
+    }
+
+    /// Maps `reference` to `client`, replacing any previous mapping.
+    pub fn insert(&mut self, reference: &str, client: ClientId) {
+        self.clients.insert(reference.trim().to_uppercase(), client);
+    }
+
+    /// Returns the number of mapped references.
+    pub fn len(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Returns `true` if no references are mapped.
+    pub fn is_empty(&self) -> bool {
+        self.clients.is_empty()
+    }
+
+    /// Finds the client for an entry, trying its references in order.
+    pub fn resolve(&self, entry: &StatementEntry) -> Option<ClientId> {
+        entry.references.iter().find_map(|reference| {
+            let reference = reference.trim().to_uppercase();
+            self.clients.get(&reference).copied().or_else(|| {
+                reference
+                    .split(|c: char| c.is_whitespace() || matches!(c, '/' | '?' | '+'))
+                    .find_map(|token| self.clients.get(token).copied())
+            })
+        })
+    }
+}
+
+/// Result of importing statement entries.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StatementImport {
+    /// Transactions for matched entries, in statement order.
+    pub transactions: Vec<TransactionType>,
+    /// Entries whose references match no client.
+    pub unmatched: Vec<StatementEntry>,
+}
+
+/// Converts statement entries into transactions.
+///
+/// Statements carry no engine transaction IDs, so matched entries are
+/// numbered sequentially from a caller-chosen start. Keep that range apart
+/// from other feeds processed by the same engine, or the imported rows will be
+/// rejected as duplicates.
+#[derive(Debug, Clone)]
+pub struct StatementImporter {
+    lookup: ClientLookup,
+    next_id: u32,
+}
+
+impl StatementImporter {
+    /// Creates an importer numbering transactions from `first_id`.
+    pub fn new(lookup: ClientLookup, first_id: u32) -> Self {
+        Self {
+            lookup,
+            next_id: first_id,
+        }
+    }
+
+    /// Returns the ID the next matched entry will receive.
+    pub fn next_id(&self) -> u32 {
+        self.next_id
+    }
+
+    /// Imports entries, continuing the ID sequence of earlier calls.
+    ///
+    /// Matched entries that would overflow the `u32` ID space are reported
+    /// as unmatched.
+    pub fn import(&mut self, entries: Vec<StatementEntry>) -> StatementImport {
+        let mut import = StatementImport::default();
+        for entry in entries {
+            let client_id = match self.lookup.resolve(&entry) {
+                Some(client_id) => client_id,
+                None => {
+                    import.unmatched.push(entry);
+                    continue;
+                }
+            };
+            let Some(following) = self.next_id.checked_add(1) else {
+                import.unmatched.push(entry);
+                continue;
+            };
+            let transaction_id = TransactionId(std::mem::replace(&mut self.next_id, following));
+            import.transactions.push(match entry.direction {
+                Direction::Credit => TransactionType::Deposit {
+                    client_id,
+                    transaction_id,
+                    amount: entry.amount,
+                },
+                Direction::Debit => TransactionType::Withdrawal {
+                    client_id,
+                    transaction_id,
+                    amount: entry.amount,
+                },
+            });
+        }
+        import
+    }
+}
+
+/// Parses a statement amount, accepting a `,` decimal separator as used by
+/// MT940.
+fn parse_amount(raw: &str, line: u64) -> Result<Decimal, StatementError> {
+    raw.trim()
+        .replace(',', ".")
+        .parse::<Decimal>()
+        .ok()
+        .filter(|amount| *amount > Decimal::ZERO)
+        .ok_or_else(|| StatementError::Invalid {
+            line,
+            reason: format!("invalid amount '{}'", raw.trim()),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn entry(direction: Direction, references: &[&str]) -> StatementEntry {
+        StatementEntry {
+            line: 1,
+            direction,
+            amount: dec!(5),
+            currency: None,
+            booking_date: None,
+            references: references.iter().map(|r| r.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn lookup_matches_whole_reference_or_token() {
+        let lookup =
+            ClientLookup::from_csv("reference,client\ncust-1,1\nINV 2,2\n".as_bytes()).unwrap();
+        assert_eq!(lookup.len(), 2);
+
+        let resolve = |refs: &[&str]| lookup.resolve(&entry(Direction::Credit, refs));
+        assert_eq!(resolve(&["Cust-1"]), Some(ClientId(1)));
+        assert_eq!(resolve(&["/REMI/CUST-1/"]), Some(ClientId(1)));
+        assert_eq!(resolve(&["inv 2"]), Some(ClientId(2)));
+        assert_eq!(
+            resolve(&["NONREF", "payment for CUST-1"]),
+            Some(ClientId(1))
+        );
+        assert_eq!(resolve(&["CUST-10"]), None);
+    }
+
+    #[test]
+    fn importer_numbers_matched_entries_and_reports_the_rest() {
+        let mut lookup = ClientLookup::new();
+        lookup.insert("A", ClientId(1));
+        let mut importer = StatementImporter::new(lookup, 100);
+
+        let import = importer.import(vec![
+            entry(Direction::Credit, &["A"]),
+            entry(Direction::Credit, &["B"]),
+            entry(Direction::Debit, &["A"]),
+        ]);
+        assert_eq!(
+            import.transactions,
+            vec![
+                TransactionType::Deposit {
+                    client_id: ClientId(1),
+                    transaction_id: TransactionId(100),
+                    amount: dec!(5),
+                },
+                TransactionType::Withdrawal {
+                    client_id: ClientId(1),
+                    transaction_id: TransactionId(101),
+                    amount: dec!(5),
+                },
+            ]
+        );
+        assert_eq!(import.unmatched, vec![entry(Direction::Credit, &["B"])]);
+        assert_eq!(importer.next_id(), 102);
+    }
+
+    #[test]
+    fn format_from_name_and_extension() {
+        assert_eq!("camt.053".parse(), Ok(StatementFormat::Camt053));
+        assert_eq!(
+            StatementFormat::from_path(Path::new("2025-01.STA")),
+            Some(StatementFormat::Mt940)
+        );
+        assert_eq!(StatementFormat::from_path(Path::new("a.csv")), None);
+    }
+}
@@ -0,0 +1,196 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! ISO 20022 camt.053 (bank-to-customer statement) parsing.
+//!
+//! Elements are matched by local name, so any `camt.053.001.xx` namespace
+//! version is accepted. Each booked `Ntry` becomes one entry; an entry
+//! batching several `TxDtls` becomes one entry per transaction so each can be
+//! matched to its own client.
+
+use super::{Direction, StatementEntry, parse_amount};
+use crate::error::StatementError;
+use roxmltree::{Document, Node};
+
+/// `NtryDtls/TxDtls/Refs/EndToEndId` placeholder meaning "no reference".
+const NOT_PROVIDED: &str = "NOTPROVIDED";
+
+/// Parses a camt.053 document into its booked entries.
+pub(super) fn parse(xml: &str) -> Result<Vec<StatementEntry>, StatementError> {
+    let doc = Document::parse(xml)?;
+    let root = doc.root_element();
+    let statement = child(root, "BkToCstmrStmt").ok_or_else(|| StatementError::Invalid {
+        line: 1,
+        reason: "not a camt.053 document: missing BkToCstmrStmt".to_string(),
+    })?;
+
+    let mut entries = Vec::new();
+    for stmt in children(statement, "Stmt") {
+        for ntry in children(stmt, "Ntry").filter(|ntry| is_booked(*ntry)) {
+            parse_entry(&doc, ntry, &mut entries)?;
+        }
+    }
+    Ok(entries)
+}
+
+/// Appends the entries for one `Ntry` element.
+fn parse_entry(
+    doc: &Document,
+    ntry: Node,
+    entries: &mut Vec<StatementEntry>,
+) -> Result<(), StatementError> {
+    let line = line_of(doc, ntry);
+    let booking_date = child(ntry, "BookgDt")
+        .and_then(|date| child(date, "Dt").or_else(|| child(date, "DtTm")))
+        .and_then(|date| date.text())
+        .map(|date| date.trim().to_string());
+    let additional_info = text(ntry, "AddtlNtryInf");
+    let details: Vec<Node> = child(ntry, "NtryDtls")
+        .into_iter()
+        .flat_map(|d| children(d, "TxDtls"))
+        .collect();
+
+    let entry_direction = direction(ntry, line)?;
+    if details.len() <= 1 {
+        let (amount, currency) = amount(child(ntry, "Amt"), line)?;
+        let mut references = details
+            .first()
+            .map(|tx| references(*tx))
+            .unwrap_or_default();
+        references.extend(additional_info);
+        entries.push(StatementEntry {
+            line,
+            direction: entry_direction,
+            amount,
+            currency,
+            booking_date,
+            references,
+        });
+        return Ok(());
+    }
+
+    // Batch booking: one entry per transaction.
+    for tx in details {
+        let line = line_of(doc, tx);
+        let direction = match child(tx, "CdtDbtInd") {
+            Some(_) => direction(tx, line)?,
+            None => entry_direction,
+        };
+        let amt = child(tx, "Amt").or_else(|| {
+            child(tx, "AmtDtls")
+                .and_then(|a| child(a, "TxAmt"))
+                .and_then(|a| child(a, "Amt"))
+        });
+        let (amount, currency) = amount(amt, line)?;
+        entries.push(StatementEntry {
+            line,
+            direction,
+            amount,
+            currency,
+            booking_date: booking_date.clone(),
+            references: references(tx),
+        });
+    }
+    Ok(())
+}
+
+/// Returns `true` for booked entries; pending or informational entries are
+/// skipped. Older versions hold the status as text, newer ones in `Sts/Cd`.
+fn is_booked(ntry: Node) -> bool {
+    match child(ntry, "Sts") {
+        None => true,
+        Some(sts) => {
+            let code = child(sts, "Cd").unwrap_or(sts);
+            code.text().is_some_and(|s| s.trim() == "BOOK")
+        }
+    }
+}
+
+/// Reads the `CdtDbtInd` of an entry or transaction.
+fn direction(node: Node, line: u64) -> Result<Direction, StatementError> {
+    match text(node, "CdtDbtInd").as_deref() {
+        Some("CRDT") => Ok(Direction::Credit),
+        Some("DBIT") => Ok(Direction::Debit),
+        other => Err(StatementError::Invalid {
+            line,
+            reason: format!("invalid CdtDbtInd {:?}", other.unwrap_or("")),
+        }),
+    }
+}
+
+/// Reads an `Amt` element and its `Ccy` attribute.
+fn amount(
+    amt: Option<Node>,
+    line: u64,
+) -> Result<(rust_decimal::Decimal, Option<String>), StatementError> {
+    let amt = amt.ok_or_else(|| StatementError::Invalid {
+        line,
+        reason: "entry has no Amt".to_string(),
+    })?;
+    let value = parse_amount(amt.text().unwrap_or(""), line)?;
+    Ok((value, amt.attribute("Ccy").map(str::to_string)))
+}
+
+/// Collects the lookup references of a `TxDtls`: structured creditor
+/// references, unstructured remittance lines, then the end-to-end ID.
+fn references(tx: Node) -> Vec<String> {
+    let mut references = Vec::new();
+    if let Some(rmt) = child(tx, "RmtInf") {
+        references.extend(
+            children(rmt, "Strd")
+                .filter_map(|s| child(s, "CdtrRefInf"))
+                .filter_map(|c| text(c, "Ref")),
+        );
+        references
+            .extend(children(rmt, "Ustrd").filter_map(|u| u.text().map(|t| t.trim().to_string())));
+    }
+    references.extend(
+        child(tx, "Refs")
+            .and_then(|refs| text(refs, "EndToEndId"))
+            .filter(|id| id != NOT_PROVIDED),
+    );
+    references
+}
+
+/// Returns the first child element with the given local name.
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children()
+        .find(|n| n.is_element() && n.tag_name().name() == name)
+}
+
+/// Returns the child elements with the given local name.
+fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |n| n.is_element() && n.tag_name().name() == name)
+}
+
+/// Returns the trimmed, non-empty text of a child element.
+fn text(node: Node, name: &str) -> Option<String> {
+    child(node, name)
+        .and_then(|n| n.text())
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+}
+
+/// Returns the 1-based line on which `node` starts.
+fn line_of(doc: &Document, node: Node) -> u64 {
+    u64::from(doc.text_pos_at(node.range().start).row)
+}
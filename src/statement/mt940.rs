@@ -0,0 +1,207 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! SWIFT MT940 (customer statement message) parsing.
+//!
+//! A file may hold several statements, each a sequence of `:tag:` fields
+//! ending with a `-` line. Every `:61:` statement line becomes one entry; the
+//! `:86:` field that follows it supplies the remittance text used for client
+//! lookup. The currency comes from the statement's `:60F:`/`:60M:` opening
+//! balance.
+
+use super::{Direction, StatementEntry, parse_amount};
+use crate::error::StatementError;
+
+/// Customer reference meaning "no reference".
+const NONREF: &str = "NONREF";
+
+/// A field with its tag, content lines and starting line number.
+struct Field<'a> {
+    tag: &'a str,
+    lines: Vec<&'a str>,
+    line: u64,
+}
+
+/// Parses an MT940 file into its entries.
+pub(super) fn parse(text: &str) -> Result<Vec<StatementEntry>, StatementError> {
+    let mut entries: Vec<StatementEntry> = Vec::new();
+    let mut currency = None;
+    // Index of the entry the next `:86:` belongs to, if any.
+    let mut open_entry = None;
+
+    for field in fields(text) {
+        match field.tag {
+            "20" => {
+                currency = None;
+                open_entry = None;
+            }
+            "60F" | "60M" => currency = opening_currency(&field),
+            "61" => {
+                let mut entry = statement_line(&field)?;
+                entry.currency.clone_from(&currency);
+                open_entry = Some(entries.len());
+                entries.push(entry);
+            }
+            "86" => {
+                if let Some(index) = open_entry.take() {
+                    let info = field.lines.concat();
+                    let references = &mut entries[index].references;
+                    references.extend(remittance_subfields(&info));
+                    if !info.trim().is_empty() {
+                        references.push(info);
+                    }
+                }
+            }
+            _ => open_entry = None,
+        }
+    }
+    Ok(entries)
+}
+
+/// Splits the message into fields. Lines before the first tag (such as a
+/// `{1:...}` SWIFT header block) and `-` terminators are skipped.
+fn fields(text: &str) -> Vec<Field<'_>> {
+    let mut fields: Vec<Field> = Vec::new();
+    for (index, raw) in text.lines().enumerate() {
+        let line = raw.trim_end();
+        if let Some((tag, content)) = tag(line) {
+            fields.push(Field {
+                tag,
+                lines: vec![content],
+                line: index as u64 + 1,
+            });
+        } else if line.starts_with('-') {
+            fields.push(Field {
+                tag: "-",
+                lines: Vec::new(),
+                line: index as u64 + 1,
+            });
+        } else if let Some(field) = fields.last_mut().filter(|f| f.tag != "-") {
+            field.lines.push(line);
+        }
+    }
+    fields
+}
+
+/// Splits `:61:content` into its tag and content.
+fn tag(line: &str) -> Option<(&str, &str)> {
+    let rest = line.strip_prefix(':')?;
+    let (tag, content) = rest.split_once(':')?;
+    let valid = (2..=3).contains(&tag.len())
+        && tag.as_bytes()[..2].iter().all(u8::is_ascii_digit)
+        && tag[2..].chars().all(|c| c.is_ascii_uppercase());
+    valid.then_some((tag, content))
+}
+
+/// Extracts the remittance text of a structured `:86:` field such as
+/// `166?00SEPA-GUTSCHRIFT?20CUST-0002?21Invoice 7`, where `?20`-`?29` and
+/// `?60`-`?63` carry the purpose text (wrapped at 27 characters, so they are
+/// joined without separators). Unstructured fields yield nothing.
+fn remittance_subfields(info: &str) -> Option<String> {
+    let mut parts = info.split('?');
+    parts.next()?; // transaction code
+    let remittance: String = parts
+        .filter_map(|part| {
+            let code: u8 = part.get(..2)?.parse().ok()?;
+            let is_purpose = (20..=29).contains(&code) || (60..=63).contains(&code);
+            is_purpose.then(|| &part[2..])
+        })
+        .collect();
+    (!remittance.trim().is_empty()).then_some(remittance)
+}
+
+/// Reads the currency of an opening balance such as `C250101EUR1234,56`.
+fn opening_currency(field: &Field) -> Option<String> {
+    field
+        .lines
+        .first()
+        .and_then(|l| l.get(7..10))
+        .filter(|c| c.chars().all(|c| c.is_ascii_uppercase()))
+        .map(str::to_string)
+}
+
+/// Parses a `:61:` statement line:
+/// `YYMMDD[MMDD](C|D|RC|RD)[funds code]amount(N|F|S)xxx reference[//bank ref]`,
+/// optionally followed by a supplementary details line.
+fn statement_line(field: &Field) -> Result<StatementEntry, StatementError> {
+    let line = field.line;
+    let invalid = |reason: &str| StatementError::Invalid {
+        line,
+        reason: format!("invalid :61: statement line: {reason}"),
+    };
+    let first = field.lines.first().copied().unwrap_or("");
+
+    let value_date = first
+        .get(..6)
+        .filter(|d| d.bytes().all(|b| b.is_ascii_digit()))
+        .ok_or_else(|| invalid("missing value date"))?;
+    let mut rest = &first[6..];
+    if rest.len() >= 4 && rest.as_bytes()[..4].iter().all(u8::is_ascii_digit) {
+        rest = &rest[4..]; // entry date
+    }
+
+    // A reversal of a credit is a debit and vice versa.
+    let (direction, after_mark) = if let Some(r) = rest.strip_prefix("RC") {
+        (Direction::Debit, r)
+    } else if let Some(r) = rest.strip_prefix("RD") {
+        (Direction::Credit, r)
+    } else if let Some(r) = rest.strip_prefix('C') {
+        (Direction::Credit, r)
+    } else if let Some(r) = rest.strip_prefix('D') {
+        (Direction::Debit, r)
+    } else {
+        return Err(invalid("missing debit/credit mark"));
+    };
+    rest = after_mark;
+    if rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        rest = &rest[1..]; // funds code
+    }
+
+    let amount_len = rest
+        .find(|c: char| !(c.is_ascii_digit() || c == ','))
+        .unwrap_or(rest.len());
+    let amount = parse_amount(&rest[..amount_len], line)?;
+    rest = &rest[amount_len..];
+
+    // Transaction type identification code, e.g. NTRF.
+    rest = rest
+        .get(4..)
+        .filter(|_| rest.starts_with(['N', 'F', 'S']))
+        .ok_or_else(|| invalid("missing transaction type code"))?;
+
+    let customer_reference = rest.split("//").next().unwrap_or("").trim();
+    let mut references = Vec::new();
+    if !customer_reference.is_empty() && customer_reference != NONREF {
+        references.push(customer_reference.to_string());
+    }
+    references.extend(
+        field.lines[1..]
+            .iter()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+            .map(str::to_string),
+    );
+
+    Ok(StatementEntry {
+        line,
+        direction,
+        amount,
+        currency: None,
+        booking_date: Some(value_date.to_string()),
+        references,
+    })
+}
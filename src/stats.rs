@@ -0,0 +1,277 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Run summary statistics.
+//!
+//! [`RunStats`] collects counters while transactions are ingested and, once
+//! the run is finished, aggregates over the resulting accounts. It renders as a
+//! human-readable summary via [`Display`](fmt::Display) and as JSON via serde.
+
+use crate::account::AccountSnapshot;
+use crate::error::TransactionError;
+use crate::transaction::TransactionType;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+/// Outcome counts for one transaction type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct TypeStats {
+    /// Transactions applied by the engine.
+    pub accepted: u64,
+    /// Transactions the engine rejected.
+    pub rejected: u64,
+}
+
+/// Account counts by locked state.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct AccountStats {
+    /// All accounts.
+    pub total: u64,
+    /// Locked accounts.
+    pub locked: u64,
+    /// Accounts that are not locked.
+    pub open: u64,
+}
+
+/// Summary of a processing run.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RunStats {
+    /// Data rows read (excluding headers).
+    pub rows: u64,
+    /// Transactions applied by the engine.
+    pub accepted: u64,
+    /// Transactions the engine rejected.
+    pub rejected: u64,
+    /// Rows that never reached the engine (malformed or invalid).
+    pub skipped: u64,
+    /// Outcomes per transaction type.
+    pub transactions: BTreeMap<&'static str, TypeStats>,
+    /// Engine rejections per [`TransactionError::code`].
+    pub errors: BTreeMap<&'static str, u64>,
+    /// Skipped rows per reason.
+    pub parse_failures: BTreeMap<&'static str, u64>,
+    /// Account counts by locked state.
+    pub accounts: AccountStats,
+    /// Sum of all account totals; `None` if it overflows the decimal range.
+    pub total_liabilities: Option<Decimal>,
+    /// Sum of all held funds; `None` if it overflows the decimal range.
+    pub total_held: Option<Decimal>,
+    /// Wall-clock processing time in seconds.
+    pub wall_time_secs: f64,
+    /// Rows read per second of wall time.
+    pub rows_per_sec: f64,
+}
+
+impl RunStats {
+    /// Creates empty statistics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a row that failed to parse.
+    pub fn record_parse_failure(&mut self, reason: &'static str) {
+        self.rows += 1;
+        self.skipped += 1;
+        *self.parse_failures.entry(reason).or_default() += 1;
+    }
+
+    /// Records the engine's outcome for a transaction.
+    pub fn record(&mut self, transaction: &TransactionType, result: &Result<(), TransactionError>) {
+        self.rows += 1;
+        let counts = self.transactions.entry(transaction.kind()).or_default();
+        match result {
+            Ok(()) => {
+                self.accepted += 1;
+                counts.accepted += 1;
+            }
+            Err(err) => {
+                self.rejected += 1;
+                counts.rejected += 1;
+                *self.errors.entry(err.code()).or_default() += 1;
+            }
+        }
+    }
+
+    /// Fills in account aggregates and timing once processing is complete.
+    pub fn finish(&mut self, accounts: &[AccountSnapshot], elapsed: Duration) {
+        let locked = accounts.iter().filter(|a| a.locked).count() as u64;
+        self.accounts = AccountStats {
+            total: accounts.len() as u64,
+            locked,
+            open: accounts.len() as u64 - locked,
+        };
+        let sum = |field: fn(&AccountSnapshot) -> Decimal| {
+            accounts
+                .iter()
+                .map(field)
+                .try_fold(Decimal::ZERO, Decimal::checked_add)
+        };
+        self.total_liabilities = sum(|a| a.total);
+        self.total_held = sum(|a| a.held);
+
+        self.wall_time_secs = elapsed.as_secs_f64();
+        self.rows_per_sec = if self.wall_time_secs > 0.0 {
+            self.rows as f64 / self.wall_time_secs
+        } else {
+            0.0
+        };
+    }
+}
+
+impl fmt::Display for RunStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Rows read:         {}", self.rows)?;
+        writeln!(f, "  accepted:        {}", self.accepted)?;
+        writeln!(f, "  rejected:        {}", self.rejected)?;
+        writeln!(f, "  skipped:         {}", self.skipped)?;
+        writeln!(f, "By type:")?;
+        for (kind, counts) in &self.transactions {
+            writeln!(
+                f,
+                "  {kind:<16} {} accepted, {} rejected",
+                counts.accepted, counts.rejected
+            )?;
+        }
+        if !self.errors.is_empty() {
+            writeln!(f, "Rejections:")?;
+            for (code, count) in &self.errors {
+                writeln!(f, "  {code:<24} {count}")?;
+            }
+        }
+        if !self.parse_failures.is_empty() {
+            writeln!(f, "Parse failures:")?;
+            for (reason, count) in &self.parse_failures {
+                writeln!(f, "  {reason:<24} {count}")?;
+            }
+        }
+        writeln!(
+            f,
+            "Accounts:          {} ({} locked, {} open)",
+            self.accounts.total, self.accounts.locked, self.accounts.open
+        )?;
+        let sum = |total: Option<Decimal>| {
+            total.map_or_else(
+                || "overflow (exceeds decimal range)".to_string(),
+                |t| t.to_string(),
+            )
+        };
+        writeln!(f, "Total liabilities: {}", sum(self.total_liabilities))?;
+        writeln!(f, "Total held:        {}", sum(self.total_held))?;
+        write!(
+            f,
+            "Wall time:         {:.3}s ({:.0} rows/s)",
+            self.wall_time_secs, self.rows_per_sec
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{ClientId, TransactionId};
+    use rust_decimal_macros::dec;
+
+    fn deposit() -> TransactionType {
+        TransactionType::Deposit {
+            client_id: ClientId(1),
+            transaction_id: TransactionId(1),
+            amount: dec!(1),
+        }
+    }
+
+    #[test]
+    fn counts_outcomes_by_type_and_error() {
+        let mut stats = RunStats::new();
+        stats.record(&deposit(), &Ok(()));
+        stats.record(&deposit(), &Err(TransactionError::DuplicateTransaction));
+        stats.record_parse_failure("MALFORMED_ROW");
+
+        assert_eq!(stats.rows, 3);
+        assert_eq!((stats.accepted, stats.rejected, stats.skipped), (1, 1, 1));
+        assert_eq!(
+            stats.transactions["deposit"],
+            TypeStats {
+                accepted: 1,
+                rejected: 1
+            }
+        );
+        assert_eq!(stats.errors["DUPLICATE_TRANSACTION"], 1);
+        assert_eq!(stats.parse_failures["MALFORMED_ROW"], 1);
+    }
+
+    #[test]
+    fn finish_aggregates_accounts() {
+        let account = |id, held, locked| AccountSnapshot {
+            client_id: ClientId(id),
+            available: dec!(10),
+            held,
+            total: dec!(10) + held,
+            locked,
+            version: 0,
+        };
+        let mut stats = RunStats::new();
+        stats.rows = 10;
+        stats.finish(
+            &[account(1, dec!(0), false), account(2, dec!(5), true)],
+            Duration::from_millis(500),
+        );
+
+        assert_eq!(
+            stats.accounts,
+            AccountStats {
+                total: 2,
+                locked: 1,
+                open: 1
+            }
+        );
+        assert_eq!(stats.total_liabilities, Some(dec!(25)));
+        assert_eq!(stats.total_held, Some(dec!(5)));
+        assert_eq!(stats.rows_per_sec, 20.0);
+
+        let json = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["accounts"]["locked"], 1);
+        assert_eq!(json["total_liabilities"], "25");
+    }
+
+    #[test]
+    fn finish_reports_overflowing_sums() {
+        let huge = dec!(50000000000000000000000000000);
+        let account = |id| AccountSnapshot {
+            client_id: ClientId(id),
+            available: huge,
+            held: dec!(1),
+            total: huge + dec!(1),
+            locked: false,
+            version: 1,
+        };
+        let mut stats = RunStats::new();
+        stats.finish(&[account(1), account(2)], Duration::from_millis(1));
+
+        assert_eq!(stats.total_liabilities, None);
+        assert_eq!(stats.total_held, Some(dec!(2)));
+        assert!(
+            stats
+                .to_string()
+                .contains("Total liabilities: overflow (exceeds decimal range)\n")
+        );
+        let json = serde_json::to_value(&stats).unwrap();
+        assert!(json["total_liabilities"].is_null());
+    }
+}
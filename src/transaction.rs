@@ -24,6 +24,8 @@
 use crate::base::{ClientId, TransactionId};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum TransactionType {
@@ -51,14 +53,43 @@ pub enum TransactionType {
     },
 }
 
+/// Dispute status of a deposit or withdrawal.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "http", derive(utoipa::ToSchema))]
 pub enum TransactionStatus {
+    /// Applied and not disputed.
     Applied,
+    /// Under dispute; the amount is held.
     Inflight,
+    /// Dispute resolved; the transaction stands.
     Resolved,
+    /// Charged back.
     Voided,
 }
 
+impl FromStr for TransactionStatus {
+    type Err = String;
+
+    /// Parses a status name, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "applied" => Ok(Self::Applied),
+            "inflight" => Ok(Self::Inflight),
+            "resolved" => Ok(Self::Resolved),
+            "voided" => Ok(Self::Voided),
+            _ => Err(format!(
+                "unknown status '{s}' (expected applied, inflight, resolved or voided)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for TransactionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
 impl TransactionType {
     pub fn id(&self) -> TransactionId {
         match self {
@@ -80,6 +111,17 @@ impl TransactionType {
         }
     }
 
+    /// Lowercase type name as used in CSV input, e.g. `deposit`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Deposit { .. } => "deposit",
+            Self::Withdrawal { .. } => "withdrawal",
+            Self::Dispute { .. } => "dispute",
+            Self::Resolve { .. } => "resolve",
+            Self::Chargeback { .. } => "chargeback",
+        }
+    }
+
     pub fn amount(&self) -> Decimal {
         match self {
             Self::Deposit { amount, .. } => *amount,
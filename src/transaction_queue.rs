@@ -21,7 +21,8 @@
 //! while maintaining insertion order.
 
 use crate::TransactionError;
-use crate::base::TransactionId;
+use crate::base::{ClientId, TransactionId};
+use crate::config::DuplicateScope;
 use crate::transaction::TransactionType;
 use crossbeam::queue::SegQueue;
 use dashmap::DashMap;
@@ -34,6 +35,9 @@ use std::sync::Arc;
 /// to preserve insertion order. All operations are lock-free and safe
 /// for concurrent access.
 ///
+/// Uniqueness is enforced within a [`DuplicateScope`]: globally by default,
+/// or per client when created with [`TransactionQueue::with_scope`].
+///
 /// # Note: Memory Growth
 ///
 /// Transactions are retained indefinitely for duplicate detection and audit.
@@ -41,19 +45,31 @@ use std::sync::Arc;
 /// intentionally deferred as a topic for architectural discussion.
 #[derive(Debug)]
 pub struct TransactionQueue {
-    /// Map of transaction IDs to transactions for O(1) duplicate detection.
-    transactions: DashMap<TransactionId, Arc<TransactionType>>,
+    /// Map of deduplication keys to transactions for O(1) duplicate detection.
+    transactions: DashMap<DedupKey, Arc<TransactionType>>,
+
+    /// Queue of deduplication keys maintaining FIFO order.
+    transaction_ids: SegQueue<DedupKey>,
 
-    /// Queue of transaction IDs maintaining FIFO order.
-    transaction_ids: SegQueue<TransactionId>,
+    /// Scope in which transaction IDs must be unique.
+    scope: DuplicateScope,
 }
 
+/// Transaction ID, qualified by client when uniqueness is per client.
+pub(crate) type DedupKey = (Option<ClientId>, TransactionId);
+
 impl TransactionQueue {
-    /// Creates a new empty transaction queue.
+    /// Creates a new empty transaction queue with globally unique IDs.
     pub fn new() -> Self {
+        Self::with_scope(DuplicateScope::Global)
+    }
+
+    /// Creates a new empty transaction queue enforcing uniqueness in `scope`.
+    pub fn with_scope(scope: DuplicateScope) -> Self {
         Self {
             transactions: DashMap::new(),
             transaction_ids: SegQueue::new(),
+            scope,
         }
     }
 
@@ -62,20 +78,73 @@ impl TransactionQueue {
     /// # Errors
     ///
     /// Returns [`TransactionError::DuplicateTransaction`] if a transaction
-    /// with the same ID already exists in the queue.
+    /// with the same ID already exists in the queue's scope.
     pub fn push(&self, transaction: Arc<TransactionType>) -> Result<(), TransactionError> {
-        let transaction_id = transaction.id();
+        let key = self.reserve(transaction)?;
+        self.transaction_ids.push(key);
+        Ok(())
+    }
+
+    /// Claims a transaction's ID without adding it to the insertion order.
+    ///
+    /// The ID counts as a duplicate from now on. Follow up with
+    /// [`confirm`](Self::confirm) to keep it or [`release`](Self::release) to
+    /// free it again.
+    pub(crate) fn reserve(
+        &self,
+        transaction: Arc<TransactionType>,
+    ) -> Result<DedupKey, TransactionError> {
+        let key = self.key(&transaction);
 
         // Use entry API for atomic check-and-insert to prevent race conditions
-        match self.transactions.entry(transaction_id) {
+        match self.transactions.entry(key) {
             Entry::Occupied(_) => Err(TransactionError::DuplicateTransaction),
             Entry::Vacant(entry) => {
                 entry.insert(transaction);
-                self.transaction_ids.push(transaction_id);
-                Ok(())
+                Ok(key)
             }
         }
     }
+
+    /// Appends a reserved transaction to the insertion order.
+    pub(crate) fn confirm(&self, key: DedupKey) {
+        self.transaction_ids.push(key);
+    }
+
+    /// Frees a reserved transaction ID.
+    pub(crate) fn release(&self, key: DedupKey) {
+        self.transactions.remove(&key);
+    }
+
+    fn key(&self, transaction: &TransactionType) -> DedupKey {
+        let client_id = match self.scope {
+            DuplicateScope::Global => None,
+            DuplicateScope::PerClient => Some(transaction.client_id()),
+        };
+        (client_id, transaction.id())
+    }
+
+    /// Returns the clients that used transaction ID `transaction_id`, in no
+    /// particular order.
+    ///
+    /// At most one client can hold an ID under [`DuplicateScope::Global`];
+    /// under [`DuplicateScope::PerClient`] this scans every stored transaction.
+    pub fn clients_of(&self, transaction_id: TransactionId) -> Vec<ClientId> {
+        match self.scope {
+            DuplicateScope::Global => self
+                .transactions
+                .get(&(None, transaction_id))
+                .map(|tx| tx.client_id())
+                .into_iter()
+                .collect(),
+            DuplicateScope::PerClient => self
+                .transactions
+                .iter()
+                .filter(|entry| entry.key().1 == transaction_id)
+                .map(|entry| entry.value().client_id())
+                .collect(),
+        }
+    }
 }
 
 impl Default for TransactionQueue {
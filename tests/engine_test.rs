@@ -17,7 +17,11 @@
 
 //! Engine public API integration tests.
 
-use ledger_demo_rs::{ClientId, Engine, TransactionError, TransactionId, TransactionType};
+use ledger_demo_rs::{
+    AmountPolicy, BatchMode, ChargebackPolicy, ClientId, DisputePolicy, DuplicateScope, Engine,
+    EngineConfig, HistoryQuery, PrecisionMode, RoundingMode, TransactionError, TransactionId,
+    TransactionStatus, TransactionType,
+};
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 
@@ -479,3 +483,549 @@ fn chargeback_with_remaining_balance_locks_account() {
     let result = engine.process(make_withdrawal(1, 4, dec!(50.00)));
     assert_eq!(result, Err(TransactionError::AccountLocked));
 }
+
+// === Amount Precision Tests ===
+
+#[cfg(not(feature = "fixed-point"))]
+#[test]
+fn default_config_rounds_excess_precision_at_ingestion() {
+    let engine = Engine::new();
+
+    // 0.00005 rounds to 0.0000 (banker's), which is not a valid deposit amount.
+    assert_eq!(
+        engine.process(make_deposit(1, 1, dec!(0.00005))),
+        Err(TransactionError::InvalidAmount)
+    );
+    engine.process(make_deposit(1, 2, dec!(0.00015))).unwrap();
+    engine.process(make_dispute(1, 2)).unwrap();
+
+    // Held carries the rounded amount, with no hidden sub-precision dust.
+    let account = engine.get_account(&ClientId(1)).unwrap();
+    assert_eq!(account.held, dec!(0.0002));
+    assert_eq!(account.held.scale(), 4);
+}
+
+#[cfg(feature = "fixed-point")]
+#[test]
+fn fixed_point_default_config_rejects_excess_precision() {
+    let engine = Engine::new();
+
+    assert_eq!(
+        engine.process(make_deposit(1, 1, dec!(1.00005))),
+        Err(TransactionError::ExcessPrecision)
+    );
+    assert!(engine.get_account(&ClientId(1)).is_none());
+    // Trailing zeros beyond four places are not excess precision.
+    engine.process(make_deposit(1, 1, dec!(1.00050))).unwrap();
+    assert_eq!(
+        engine.get_account(&ClientId(1)).unwrap().available,
+        dec!(1.0005)
+    );
+}
+
+#[test]
+fn reject_mode_does_not_consume_transaction_id() {
+    let engine = Engine::with_config(EngineConfig {
+        amounts: AmountPolicy::new(2, PrecisionMode::Reject),
+        ..EngineConfig::default()
+    });
+
+    assert_eq!(
+        engine.process(make_deposit(1, 1, dec!(10.001))),
+        Err(TransactionError::ExcessPrecision)
+    );
+    engine.process(make_deposit(1, 1, dec!(10.00))).unwrap();
+    assert_eq!(
+        engine.process(make_withdrawal(1, 2, dec!(1.999))),
+        Err(TransactionError::ExcessPrecision)
+    );
+
+    let account = engine.get_account(&ClientId(1)).unwrap();
+    assert_eq!(account.available, dec!(10.00));
+}
+
+#[test]
+fn configured_rounding_mode_is_applied() {
+    let engine = Engine::with_config(EngineConfig {
+        amounts: AmountPolicy::new(2, PrecisionMode::Round(RoundingMode::Truncate)),
+        ..EngineConfig::default()
+    });
+
+    engine.process(make_deposit(1, 1, dec!(10.009))).unwrap();
+    engine.process(make_withdrawal(1, 2, dec!(0.019))).unwrap();
+
+    let account = engine.get_account(&ClientId(1)).unwrap();
+    assert_eq!(account.available, dec!(9.99));
+}
+
+// === Policy Configuration Tests ===
+
+#[test]
+fn keep_open_chargeback_leaves_account_usable() {
+    let engine = Engine::builder()
+        .chargeback_policy(ChargebackPolicy::KeepOpen)
+        .build();
+
+    engine.process(make_deposit(1, 1, dec!(100.00))).unwrap();
+    engine.process(make_deposit(1, 2, dec!(40.00))).unwrap();
+    engine.process(make_dispute(1, 1)).unwrap();
+    engine.process(make_chargeback(1, 1)).unwrap();
+
+    let account = engine.get_account(&ClientId(1)).unwrap();
+    assert_eq!(account.available, dec!(40.00));
+    assert_eq!(account.held, dec!(0));
+    assert!(!account.locked);
+
+    engine.process(make_withdrawal(1, 3, dec!(15.00))).unwrap();
+    assert_eq!(
+        engine.get_account(&ClientId(1)).unwrap().available,
+        dec!(25.00)
+    );
+}
+
+#[test]
+fn withdrawal_dispute_resolve_lets_withdrawal_stand() {
+    let engine = Engine::builder()
+        .dispute_policy(DisputePolicy::DepositsAndWithdrawals)
+        .build();
+
+    engine.process(make_deposit(1, 1, dec!(100.00))).unwrap();
+    engine.process(make_withdrawal(1, 2, dec!(30.00))).unwrap();
+    engine.process(make_dispute(1, 2)).unwrap();
+
+    let account = engine.get_account(&ClientId(1)).unwrap();
+    assert_eq!(account.available, dec!(70.00));
+    assert_eq!(account.held, dec!(30.00));
+    assert_eq!(account.total, dec!(100.00));
+
+    engine.process(make_resolve(1, 2)).unwrap();
+    let account = engine.get_account(&ClientId(1)).unwrap();
+    assert_eq!(account.available, dec!(70.00));
+    assert_eq!(account.held, dec!(0));
+    assert_eq!(account.total, dec!(70.00));
+}
+
+#[test]
+fn withdrawal_chargeback_reverses_withdrawal() {
+    let engine = Engine::builder()
+        .dispute_policy(DisputePolicy::DepositsAndWithdrawals)
+        .build();
+
+    engine.process(make_deposit(1, 1, dec!(100.00))).unwrap();
+    engine.process(make_withdrawal(1, 2, dec!(30.00))).unwrap();
+    engine.process(make_dispute(1, 2)).unwrap();
+    engine.process(make_chargeback(1, 2)).unwrap();
+
+    let account = engine.get_account(&ClientId(1)).unwrap();
+    assert_eq!(account.available, dec!(100.00));
+    assert_eq!(account.held, dec!(0));
+    assert!(account.locked);
+}
+
+#[test]
+fn withdrawals_not_disputable_by_default() {
+    let engine = Engine::new();
+
+    engine.process(make_deposit(1, 1, dec!(100.00))).unwrap();
+    engine.process(make_withdrawal(1, 2, dec!(30.00))).unwrap();
+    assert_eq!(
+        engine.process(make_dispute(1, 2)),
+        Err(TransactionError::TransactionNotFound)
+    );
+}
+
+#[test]
+fn per_client_duplicate_scope_allows_reused_ids() {
+    let global = Engine::new();
+    global.process(make_deposit(1, 1, dec!(10.00))).unwrap();
+    assert_eq!(
+        global.process(make_deposit(2, 1, dec!(10.00))),
+        Err(TransactionError::DuplicateTransaction)
+    );
+
+    let per_client = Engine::builder()
+        .duplicate_scope(DuplicateScope::PerClient)
+        .build();
+    per_client.process(make_deposit(1, 1, dec!(10.00))).unwrap();
+    per_client.process(make_deposit(2, 1, dec!(20.00))).unwrap();
+    assert_eq!(
+        per_client.process(make_deposit(1, 1, dec!(10.00))),
+        Err(TransactionError::DuplicateTransaction)
+    );
+    assert_eq!(per_client.accounts().len(), 2);
+}
+
+#[test]
+fn config_file_round_trips_through_builder() {
+    let config =
+        EngineConfig::from_toml_str("chargeback = \"keep-open\"\nduplicates = \"per-client\"\n")
+            .unwrap();
+    let engine = Engine::builder().config(config).build();
+
+    assert_eq!(engine.config().chargeback, ChargebackPolicy::KeepOpen);
+    assert_eq!(engine.config().duplicates, DuplicateScope::PerClient);
+    assert_eq!(
+        EngineConfig::from_toml_str(&engine.config().to_toml()).unwrap(),
+        config
+    );
+}
+
+// === Output Ordering Tests ===
+
+#[test]
+fn accounts_are_ordered_by_client_id_regardless_of_processing_order() {
+    let client_ids: Vec<u16> = (0..64).map(|i| (i * 7919 % 65_521) as u16).collect();
+
+    // Serial, in the scrambled order above
+    let serial = Engine::new();
+    for (tx_id, &client_id) in (1u32..).zip(&client_ids) {
+        serial
+            .process(make_deposit(client_id, tx_id, dec!(1)))
+            .unwrap();
+    }
+
+    // Threaded, each thread taking an interleaved slice
+    let threaded = std::sync::Arc::new(Engine::new());
+    let handles: Vec<_> = (0..4)
+        .map(|t| {
+            let engine = std::sync::Arc::clone(&threaded);
+            let client_ids = client_ids.clone();
+            std::thread::spawn(move || {
+                for (i, &client_id) in client_ids.iter().enumerate().skip(t).step_by(4) {
+                    engine
+                        .process(make_deposit(client_id, i as u32 + 1, dec!(1)))
+                        .unwrap();
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let ids =
+        |engine: &Engine| -> Vec<u16> { engine.accounts().iter().map(|a| a.client_id.0).collect() };
+    let mut expected = client_ids.clone();
+    expected.sort_unstable();
+    assert_eq!(ids(&serial), expected);
+    assert_eq!(ids(&threaded), expected);
+}
+
+#[test]
+fn transaction_status_follows_dispute_lifecycle() {
+    let engine = Engine::new();
+    engine.process(make_deposit(1, 1, dec!(10))).unwrap();
+    engine.process(make_deposit(1, 2, dec!(5))).unwrap();
+    engine.process(make_withdrawal(1, 3, dec!(1))).unwrap();
+    let status = |id| {
+        engine
+            .transaction(ClientId(1), TransactionId(id))
+            .unwrap()
+            .status
+    };
+    assert_eq!(status(1), TransactionStatus::Applied);
+    assert_eq!(status(3), TransactionStatus::Applied);
+
+    engine.process(make_dispute(1, 1)).unwrap();
+    assert_eq!(status(1), TransactionStatus::Inflight);
+    engine.process(make_resolve(1, 1)).unwrap();
+    assert_eq!(status(1), TransactionStatus::Resolved);
+    engine.process(make_dispute(1, 2)).unwrap();
+    engine.process(make_chargeback(1, 2)).unwrap();
+    assert_eq!(status(2), TransactionStatus::Voided);
+
+    // Rejected transactions are not stored
+    assert!(engine.process(make_withdrawal(1, 4, dec!(100))).is_err());
+    assert!(engine.transaction(ClientId(1), TransactionId(4)).is_none());
+    assert!(engine.transaction(ClientId(2), TransactionId(1)).is_none());
+
+    let found = engine.find_transaction(TransactionId(3));
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].transaction, make_withdrawal(1, 3, dec!(1)));
+}
+
+#[test]
+fn transaction_history_pages_and_filters() {
+    let engine = Engine::new();
+    for id in 1..=5 {
+        engine.process(make_deposit(1, id, dec!(10))).unwrap();
+    }
+    engine.process(make_withdrawal(1, 6, dec!(1))).unwrap();
+    engine.process(make_dispute(1, 2)).unwrap();
+    engine.process(make_deposit(2, 7, dec!(1))).unwrap();
+
+    let ids = |query: &HistoryQuery| {
+        let page = engine.transaction_history(ClientId(1), query).unwrap();
+        let ids: Vec<u32> = page
+            .transactions
+            .iter()
+            .map(|stored| stored.transaction.id().0)
+            .collect();
+        (ids, page.next_cursor)
+    };
+
+    let mut query = HistoryQuery {
+        limit: 4,
+        ..HistoryQuery::default()
+    };
+    let (first, cursor) = ids(&query);
+    assert_eq!(first, vec![1, 2, 3, 4]);
+    query.cursor = cursor.unwrap();
+    assert_eq!(ids(&query), (vec![5, 6], None));
+
+    let withdrawals = HistoryQuery {
+        kind: Some("withdrawal".to_string()),
+        ..HistoryQuery::default()
+    };
+    assert_eq!(ids(&withdrawals), (vec![6], None));
+    let inflight = HistoryQuery {
+        status: Some(TransactionStatus::Inflight),
+        ..HistoryQuery::default()
+    };
+    assert_eq!(ids(&inflight), (vec![2], None));
+
+    assert!(
+        engine
+            .transaction_history(ClientId(3), &HistoryQuery::default())
+            .is_none()
+    );
+}
+
+#[test]
+fn independent_batch_reports_each_transaction() {
+    let engine = Engine::new();
+    let report = engine.process_batch(
+        [
+            make_deposit(1, 1, dec!(10)),
+            make_withdrawal(1, 2, dec!(50)),
+            make_withdrawal(1, 3, dec!(4)),
+            make_deposit(2, 1, dec!(1)),
+        ],
+        BatchMode::Independent,
+    );
+
+    assert!(report.committed);
+    assert_eq!(
+        report.results,
+        vec![
+            Ok(()),
+            Err(TransactionError::InsufficientFunds),
+            Ok(()),
+            Err(TransactionError::DuplicateTransaction),
+        ]
+    );
+    assert_eq!(report.rejected(), 2);
+    assert_eq!(engine.get_account(&ClientId(1)).unwrap().available, dec!(6));
+    assert!(engine.get_account(&ClientId(2)).is_none());
+}
+
+#[test]
+fn atomic_batch_commits_when_every_transaction_succeeds() {
+    let engine = Engine::new();
+    engine.process(make_deposit(1, 1, dec!(10))).unwrap();
+
+    let report = engine.process_batch(
+        [
+            make_deposit(2, 2, dec!(5)),
+            make_withdrawal(1, 3, dec!(10)),
+            make_dispute(2, 2),
+        ],
+        BatchMode::Atomic,
+    );
+
+    assert!(report.committed);
+    assert_eq!(report.rejected(), 0);
+    assert_eq!(engine.get_account(&ClientId(1)).unwrap().available, dec!(0));
+    assert_eq!(engine.get_account(&ClientId(2)).unwrap().held, dec!(5));
+    // Committed IDs stay taken
+    assert_eq!(
+        engine.process(make_deposit(3, 3, dec!(1))),
+        Err(TransactionError::DuplicateTransaction)
+    );
+}
+
+#[test]
+fn atomic_batch_rolls_back_on_any_rejection() {
+    let engine = Engine::new();
+    engine.process(make_deposit(1, 1, dec!(10))).unwrap();
+
+    let report = engine.process_batch(
+        [
+            make_deposit(1, 2, dec!(5)),
+            make_deposit(2, 3, dec!(5)),
+            make_withdrawal(1, 4, dec!(100)),
+            make_dispute(1, 1),
+        ],
+        BatchMode::Atomic,
+    );
+
+    assert!(!report.committed);
+    assert_eq!(
+        report.results,
+        vec![
+            Ok(()),
+            Ok(()),
+            Err(TransactionError::InsufficientFunds),
+            Ok(())
+        ]
+    );
+    let account = engine.get_account(&ClientId(1)).unwrap();
+    assert_eq!((account.available, account.held), (dec!(10), dec!(0)));
+    assert!(engine.get_account(&ClientId(2)).is_none());
+    assert!(engine.transaction(ClientId(1), TransactionId(2)).is_none());
+
+    // The batch's IDs are free again
+    engine.process(make_deposit(2, 2, dec!(1))).unwrap();
+    engine.process(make_deposit(2, 4, dec!(1))).unwrap();
+}
+
+#[test]
+fn atomic_batches_are_isolated_from_concurrent_writes() {
+    let engine = std::sync::Arc::new(Engine::new());
+    engine.process(make_deposit(1, 1, dec!(1000))).unwrap();
+
+    let handles: Vec<_> = (0..4u32)
+        .map(|t| {
+            let engine = std::sync::Arc::clone(&engine);
+            std::thread::spawn(move || {
+                for i in 0..50 {
+                    let id = 1000 + t * 1000 + i * 2;
+                    // Each batch moves 1 from client 1 to client 2 and back
+                    engine.process_batch(
+                        [
+                            make_withdrawal(1, id, dec!(1)),
+                            make_deposit(2, id + 1, dec!(1)),
+                        ],
+                        BatchMode::Atomic,
+                    );
+                    engine
+                        .process(make_deposit(1, 100_000 + t * 1000 + i, dec!(1)))
+                        .unwrap();
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let one = engine.get_account(&ClientId(1)).unwrap();
+    let two = engine.get_account(&ClientId(2)).unwrap();
+    assert_eq!(two.available, dec!(200));
+    assert_eq!(one.available, dec!(1000));
+}
+
+#[test]
+fn metrics_count_outcomes_and_report_account_gauges() {
+    let engine = Engine::new();
+    engine.process(make_deposit(1, 1, dec!(10))).unwrap();
+    engine.process(make_deposit(2, 2, dec!(4))).unwrap();
+    engine.process(make_dispute(1, 1)).unwrap();
+    engine.process(make_chargeback(1, 1)).unwrap();
+    let _ = engine.process(make_deposit(2, 2, dec!(1)));
+    let _ = engine.process(make_withdrawal(2, 3, dec!(100)));
+    engine.process_batch(
+        [
+            make_deposit(2, 4, dec!(1)),
+            make_withdrawal(2, 5, dec!(100)),
+        ],
+        BatchMode::Atomic,
+    );
+
+    let metrics = engine.metrics();
+    assert_eq!(metrics.transactions("deposit", "applied"), 2);
+    assert_eq!(metrics.transactions("deposit", "rejected"), 1);
+    assert_eq!(metrics.transactions("deposit", "rolled_back"), 1);
+    assert_eq!(metrics.transactions("withdrawal", "rejected"), 2);
+    assert_eq!(metrics.transactions("chargeback", "applied"), 1);
+    assert_eq!(metrics.errors(&TransactionError::InsufficientFunds), 2);
+    assert_eq!(metrics.errors(&TransactionError::DuplicateTransaction), 1);
+    // Atomic batches are not timed as `process` calls
+    assert_eq!(metrics.processed(), 6);
+
+    let text = engine.metrics_text();
+    for line in [
+        "ledger_transactions_total{type=\"dispute\",result=\"applied\"} 1",
+        "ledger_transaction_errors_total{code=\"INSUFFICIENT_FUNDS\"} 2",
+        "ledger_process_duration_seconds_bucket{le=\"+Inf\"} 6",
+        "ledger_process_duration_seconds_count 6",
+        "ledger_accounts 2",
+        "ledger_accounts_locked 1",
+        "ledger_held_funds 0",
+        "ledger_liabilities 4",
+    ] {
+        assert!(
+            text.lines().any(|l| l == line),
+            "missing '{line}' in:\n{text}"
+        );
+    }
+}
+
+// === Optimistic Concurrency Tests ===
+
+#[test]
+fn account_version_counts_applied_transactions() {
+    let engine = Engine::new();
+    engine.process(make_deposit(1, 1, dec!(10))).unwrap();
+    engine.process(make_dispute(1, 1)).unwrap();
+    let _ = engine.process(make_withdrawal(1, 2, dec!(100)));
+    engine.process(make_resolve(1, 1)).unwrap();
+    assert_eq!(engine.get_account(&ClientId(1)).unwrap().version, 3);
+
+    engine.process_batch(
+        [make_deposit(1, 3, dec!(1)), make_withdrawal(1, 4, dec!(1))],
+        BatchMode::Atomic,
+    );
+    engine.process_batch(
+        [
+            make_deposit(1, 5, dec!(1)),
+            make_withdrawal(1, 6, dec!(100)),
+        ],
+        BatchMode::Atomic,
+    );
+    assert_eq!(engine.get_account(&ClientId(1)).unwrap().version, 5);
+}
+
+#[test]
+fn expected_version_guards_transactions() {
+    let engine = Engine::new();
+    engine
+        .process_expecting(make_deposit(1, 1, dec!(10)), 0)
+        .unwrap();
+    engine
+        .process_expecting(make_deposit(1, 2, dec!(5)), 1)
+        .unwrap();
+    assert_eq!(
+        engine.process_expecting(make_dispute(1, 1), 1),
+        Err(TransactionError::VersionConflict)
+    );
+    engine.process_expecting(make_dispute(1, 1), 2).unwrap();
+
+    let account = engine.get_account(&ClientId(1)).unwrap();
+    assert_eq!((account.available, account.held), (dec!(5), dec!(10)));
+    assert_eq!(account.version, 3);
+    assert_eq!(
+        engine.metrics().errors(&TransactionError::VersionConflict),
+        1
+    );
+}
+
+#[test]
+fn version_conflict_does_not_consume_id_or_create_account() {
+    let engine = Engine::new();
+    assert_eq!(
+        engine.process_expecting(make_deposit(1, 1, dec!(10)), 3),
+        Err(TransactionError::VersionConflict)
+    );
+    assert!(engine.get_account(&ClientId(1)).is_none());
+
+    engine.process(make_deposit(1, 1, dec!(10))).unwrap();
+    assert_eq!(
+        engine.process_expecting(make_withdrawal(1, 2, dec!(1)), 0),
+        Err(TransactionError::VersionConflict)
+    );
+    engine
+        .process_expecting(make_withdrawal(1, 2, dec!(1)), 1)
+        .unwrap();
+    assert_eq!(engine.get_account(&ClientId(1)).unwrap().available, dec!(9));
+}
@@ -0,0 +1,264 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Journal export integration tests. The generated Beancount and ledger-cli
+//! files are checked the way `bean-check` and `ledger` would: every entry
+//! balances, accounts are opened before use, and the closing balance
+//! assertions hold against the postings.
+
+use ledger_demo_rs::{
+    AccountSnapshot, AmountPolicy, ChargebackPolicy, ClientId, DisputePolicy, Engine,
+    JournalFormat, JournalOptions, JournalRecorder, PrecisionMode, RoundingMode, TransactionId,
+    TransactionType,
+};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+
+fn deposit(client: u16, tx: u32, amount: Decimal) -> TransactionType {
+    TransactionType::Deposit {
+        client_id: ClientId(client),
+        transaction_id: TransactionId(tx),
+        amount,
+    }
+}
+
+fn withdrawal(client: u16, tx: u32, amount: Decimal) -> TransactionType {
+    TransactionType::Withdrawal {
+        client_id: ClientId(client),
+        transaction_id: TransactionId(tx),
+        amount,
+    }
+}
+
+fn dispute(client: u16, tx: u32) -> TransactionType {
+    TransactionType::Dispute {
+        client_id: ClientId(client),
+        transaction_id: TransactionId(tx),
+    }
+}
+
+fn resolve(client: u16, tx: u32) -> TransactionType {
+    TransactionType::Resolve {
+        client_id: ClientId(client),
+        transaction_id: TransactionId(tx),
+    }
+}
+
+fn chargeback(client: u16, tx: u32) -> TransactionType {
+    TransactionType::Chargeback {
+        client_id: ClientId(client),
+        transaction_id: TransactionId(tx),
+    }
+}
+
+/// Runs every kind of movement, including rejected ones, and returns the
+/// recorder with the final account snapshots.
+fn run() -> (JournalRecorder, Vec<AccountSnapshot>) {
+    let engine = Engine::builder()
+        .amount_policy(AmountPolicy::new(
+            2,
+            PrecisionMode::Round(RoundingMode::HalfUp),
+        ))
+        .dispute_policy(DisputePolicy::DepositsAndWithdrawals)
+        .chargeback_policy(ChargebackPolicy::KeepOpen)
+        .build();
+    let mut recorder = JournalRecorder::new();
+    for tx in [
+        deposit(1, 1, dec!(100.005)),
+        deposit(2, 2, dec!(50)),
+        withdrawal(1, 3, dec!(30)),
+        // Rejected: insufficient funds.
+        withdrawal(2, 4, dec!(80)),
+        dispute(2, 2),
+        // Rejected: the deposit is held.
+        withdrawal(2, 5, dec!(10)),
+        resolve(2, 2),
+        deposit(2, 8, dec!(10)),
+        dispute(2, 8),
+        chargeback(2, 8),
+        dispute(1, 3),
+        chargeback(1, 3),
+        deposit(3, 6, dec!(20)),
+        withdrawal(3, 7, dec!(5)),
+        dispute(3, 7),
+        resolve(3, 7),
+        dispute(3, 6),
+        // Rejected: unknown transaction.
+        dispute(3, 99),
+    ] {
+        let result = engine.process(tx);
+        let applied = engine.config().amounts.apply_to(tx).unwrap_or(tx);
+        recorder.record(&applied, &result);
+    }
+    (recorder, engine.accounts())
+}
+
+fn write(format: JournalFormat) -> String {
+    let (recorder, accounts) = run();
+    let mut out = Vec::new();
+    recorder
+        .write(
+            format,
+            &JournalOptions::new("2025-03-07"),
+            &accounts,
+            &mut out,
+        )
+        .unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+/// A parsed posting: account, amount and an optional ledger-cli assertion.
+fn parse_posting(line: &str) -> (String, Decimal, Option<Decimal>) {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    match tokens.as_slice() {
+        [account, amount, "USD"] => (account.to_string(), amount.parse().unwrap(), None),
+        [account, amount, "USD", "=", expected, "USD"] => (
+            account.to_string(),
+            amount.parse().unwrap(),
+            Some(expected.parse().unwrap()),
+        ),
+        _ => panic!("malformed posting: {line}"),
+    }
+}
+
+/// Checks the entries of a journal, returning (entries, assertions) checked.
+fn check_entries(
+    lines: &[&str],
+    opened: &HashMap<String, String>,
+    balances: &mut HashMap<String, Decimal>,
+) -> (usize, usize) {
+    let (mut entries, mut assertions) = (0, 0);
+    let mut sum: Option<Decimal> = None;
+    for line in lines {
+        let indented = line.starts_with(' ');
+        let body = line.trim();
+        if !indented || body.is_empty() {
+            if let Some(total) = sum.take() {
+                assert_eq!(total, Decimal::ZERO, "unbalanced entry before '{line}'");
+                entries += 1;
+            }
+            if line.contains(" * ") {
+                sum = Some(Decimal::ZERO);
+            }
+            continue;
+        }
+        // Metadata and comments.
+        if body.starts_with(';') || body.starts_with(|c: char| c.is_ascii_lowercase()) {
+            continue;
+        }
+        let (account, amount, expected) = parse_posting(body);
+        assert!(opened.contains_key(&account), "{account} is not opened");
+        *sum.as_mut().expect("posting outside an entry") += amount;
+        let balance = balances.entry(account.clone()).or_default();
+        *balance += amount;
+        if let Some(expected) = expected {
+            assert_eq!(*balance, expected, "assertion failed for {account}");
+            assertions += 1;
+        }
+    }
+    if let Some(total) = sum {
+        assert_eq!(total, Decimal::ZERO, "unbalanced last entry");
+        entries += 1;
+    }
+    (entries, assertions)
+}
+
+/// A minimal `bean-check`: open directives, balanced entries and `balance`
+/// assertions evaluated at the start of their day.
+fn bean_check(journal: &str) -> (usize, usize) {
+    let mut opened = HashMap::new();
+    let mut dated = Vec::new();
+    for line in journal.lines() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            [_, "open", account, currency] => {
+                opened.insert(account.to_string(), currency.to_string());
+            }
+            [date, "balance", ..] => dated.push((date.to_string(), line)),
+            _ => {}
+        }
+    }
+    assert!(opened.values().all(|currency| currency == "USD"));
+
+    let mut balances = HashMap::new();
+    let lines: Vec<&str> = journal
+        .lines()
+        .filter(|l| !l.contains(" balance "))
+        .collect();
+    let (entries, _) = check_entries(&lines, &opened, &mut balances);
+    let entry_dates: Vec<&str> = journal
+        .lines()
+        .filter(|l| l.contains(" * "))
+        .map(|l| &l[..10])
+        .collect();
+
+    for (date, line) in &dated {
+        // Every entry precedes the assertion's day, so the balance is final.
+        assert!(entry_dates.iter().all(|d| *d < date.as_str()), "{line}");
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let [_, _, account, amount, "USD"] = tokens.as_slice() else {
+            panic!("malformed balance directive: {line}");
+        };
+        assert!(opened.contains_key(*account), "{account} is not opened");
+        let balance = balances.get(*account).copied().unwrap_or_default();
+        assert_eq!(balance, amount.parse::<Decimal>().unwrap(), "{line}");
+    }
+    (entries, dated.len())
+}
+
+/// A minimal `ledger --strict` check: declared accounts, balanced entries and
+/// `= AMOUNT` assertions evaluated in file order.
+fn ledger_check(journal: &str) -> (usize, usize) {
+    let opened: HashMap<String, String> = journal
+        .lines()
+        .filter_map(|l| l.strip_prefix("account "))
+        .map(|account| (account.to_string(), "USD".to_string()))
+        .collect();
+    let lines: Vec<&str> = journal.lines().collect();
+    check_entries(&lines, &opened, &mut HashMap::new())
+}
+
+#[test]
+fn beancount_journal_passes_balance_checks() {
+    let journal = write(JournalFormat::Beancount);
+    // 14 accepted transactions; two assertions per client.
+    assert_eq!(bean_check(&journal), (14, 6));
+    assert!(journal.contains("2025-03-08 balance Liabilities:Clients:1:Available"));
+}
+
+#[test]
+fn ledger_journal_passes_balance_checks() {
+    let journal = write(JournalFormat::Ledger);
+    // The closing-balances entry carries the assertions.
+    assert_eq!(ledger_check(&journal), (15, 6));
+}
+
+#[test]
+fn journal_amounts_are_the_applied_amounts() {
+    let (recorder, accounts) = run();
+    assert_eq!(recorder.entries()[0].amount, dec!(100.01));
+    assert_eq!(accounts[0].available, dec!(100.01));
+
+    // The charged-back withdrawal is back in client 1's available funds.
+    let charged_back = recorder
+        .entries()
+        .iter()
+        .find(|e| matches!(e.transaction, TransactionType::Chargeback { .. }) && e.of_withdrawal)
+        .unwrap();
+    assert_eq!(charged_back.amount, dec!(30));
+}
@@ -0,0 +1,501 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Checks the OpenAPI document against the running server.
+//!
+//! Every documented operation must be routed, and every response the tests
+//! provoke must have a documented status and match its schema, so changing a
+//! handler or DTO without its annotation fails here.
+
+use futures::StreamExt;
+use ledger_demo_rs::Engine;
+use ledger_demo_rs::server::{
+    ApiKeys, RateLimit, RateLimiter, RateLimits, ServerOptions, create_router_with, error_codes,
+    openapi,
+};
+use reqwest::{Client, Method, RequestBuilder, StatusCode, header};
+use serde_json::{Value, json};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+
+/// The API on an ephemeral port, with the document it serves.
+struct TestServer {
+    base_url: String,
+    spec: Value,
+    client: Client,
+}
+
+impl TestServer {
+    async fn new(options: ServerOptions) -> Self {
+        let app = create_router_with(Arc::new(Engine::new()), options);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        TestServer {
+            base_url,
+            spec: serde_json::to_value(openapi()).unwrap(),
+            client: Client::new(),
+        }
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.client
+            .request(method, format!("{}{}", self.base_url, path))
+    }
+
+    /// Sends `request` and checks the response against the operation at
+    /// `template`, returning the JSON body if there is one.
+    async fn check(&self, method: Method, template: &str, request: RequestBuilder) -> Value {
+        let response = request.send().await.unwrap();
+        let status = response.status();
+        let operation = &self.spec["paths"][template][method.as_str().to_lowercase()];
+        assert!(
+            operation.is_object(),
+            "{method} {template} is not documented"
+        );
+        let documented = &operation["responses"][status.as_str()];
+        assert!(
+            documented.is_object(),
+            "{method} {template} answered an undocumented {status}"
+        );
+        let Some(content) = documented["content"].as_object() else {
+            assert!(
+                response.bytes().await.unwrap().is_empty(),
+                "{method} {template} {status} has an undocumented body"
+            );
+            return Value::Null;
+        };
+        let content_type = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        let (media_type, media) = content
+            .iter()
+            .find(|(media_type, _)| content_type.starts_with(media_type.as_str()))
+            .unwrap_or_else(|| {
+                panic!("{method} {template} {status} answered undocumented {content_type:?}")
+            });
+        if media_type == "text/event-stream" {
+            // Event bodies are checked by `stream_events_match_event_schema`.
+            return Value::Null;
+        }
+        let body = match media_type.as_str() {
+            "application/json" => response.json().await.unwrap(),
+            _ => Value::String(response.text().await.unwrap()),
+        };
+        if let Some(schema) = media.get("schema") {
+            validate(&self.spec, schema, &body)
+                .unwrap_or_else(|e| panic!("{method} {template} {status}: {e}\n{body:#}"));
+        }
+        body
+    }
+}
+
+/// Validates `value` against `schema`, rejecting undeclared object fields.
+fn validate(spec: &Value, schema: &Value, value: &Value) -> Result<(), String> {
+    check_schema(spec, schema, value)?;
+    let (Some(object), Some(declared)) = (value.as_object(), declared(spec, schema, value)) else {
+        return Ok(());
+    };
+    match object.keys().find(|key| !declared.contains(key.as_str())) {
+        Some(key) => Err(format!("undeclared field '{key}' in {value}")),
+        None => Ok(()),
+    }
+}
+
+/// Checks everything but undeclared fields of the top-level object, which
+/// `allOf` and `oneOf` spread over several schemas.
+fn check_schema(spec: &Value, schema: &Value, value: &Value) -> Result<(), String> {
+    if let Some(reference) = schema["$ref"].as_str() {
+        return check_schema(spec, resolve(spec, reference), value);
+    }
+    if let Some(types) = schema.get("type") {
+        let types: Vec<&str> = match types {
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            other => other.as_str().into_iter().collect(),
+        };
+        let matches = |kind: &str| match kind {
+            "null" => value.is_null(),
+            "boolean" => value.is_boolean(),
+            "integer" => value.is_u64() || value.is_i64(),
+            "number" => value.is_number(),
+            "string" => value.is_string(),
+            "array" => value.is_array(),
+            "object" => value.is_object(),
+            _ => false,
+        };
+        if !types.iter().any(|kind| matches(kind)) {
+            return Err(format!("{value} is not of type {types:?}"));
+        }
+    }
+    if let Some(allowed) = schema["enum"].as_array()
+        && !allowed.contains(value)
+    {
+        return Err(format!("{value} is not one of {allowed:?}"));
+    }
+    if let (Some(minimum), Some(number)) = (schema["minimum"].as_f64(), value.as_f64())
+        && number < minimum
+    {
+        return Err(format!("{value} is below {minimum}"));
+    }
+    if let (Some(maximum), Some(number)) = (schema["maximum"].as_f64(), value.as_f64())
+        && number > maximum
+    {
+        return Err(format!("{value} is above {maximum}"));
+    }
+    if let Some(object) = value.as_object() {
+        for required in schema["required"].as_array().into_iter().flatten() {
+            let required = required.as_str().unwrap();
+            if !object.contains_key(required) {
+                return Err(format!("missing required field '{required}' in {value}"));
+            }
+        }
+        if let Some(properties) = schema["properties"].as_object() {
+            for (name, property) in properties {
+                if let Some(field) = object.get(name) {
+                    validate(spec, property, field).map_err(|e| format!("{name}: {e}"))?;
+                }
+            }
+        }
+    }
+    if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+        for item in array {
+            validate(spec, items, item)?;
+        }
+    }
+    for part in schema["allOf"].as_array().into_iter().flatten() {
+        check_schema(spec, part, value)?;
+    }
+    if let Some(branches) = schema["oneOf"].as_array() {
+        let errors: Vec<String> = branches
+            .iter()
+            .filter_map(|branch| check_schema(spec, branch, value).err())
+            .collect();
+        if errors.len() + 1 != branches.len() {
+            return Err(format!("{value} does not match exactly one of {errors:?}"));
+        }
+    }
+    if let Some(branches) = schema["anyOf"].as_array()
+        && branches
+            .iter()
+            .all(|branch| check_schema(spec, branch, value).is_err())
+    {
+        return Err(format!("{value} matches none of anyOf"));
+    }
+    Ok(())
+}
+
+/// Field names `schema` declares for `value`, merged through `allOf` and the
+/// matching `oneOf` or `anyOf` branch; `None` if it does not list them.
+fn declared<'a>(spec: &'a Value, schema: &'a Value, value: &Value) -> Option<BTreeSet<&'a str>> {
+    if let Some(reference) = schema["$ref"].as_str() {
+        return declared(spec, resolve(spec, reference), value);
+    }
+    let mut fields: Option<BTreeSet<&str>> = schema["properties"]
+        .as_object()
+        .map(|properties| properties.keys().map(String::as_str).collect());
+    let branch = ["oneOf", "anyOf"].into_iter().find_map(|keyword| {
+        schema[keyword]
+            .as_array()?
+            .iter()
+            .find(|branch| check_schema(spec, branch, value).is_ok())
+    });
+    for part in schema["allOf"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .chain(branch)
+    {
+        fields
+            .get_or_insert_with(BTreeSet::new)
+            .extend(declared(spec, part, value)?);
+    }
+    fields
+}
+
+/// Looks up a `#/components/schemas/` reference.
+fn resolve<'a>(spec: &'a Value, reference: &str) -> &'a Value {
+    let name = reference.trim_start_matches("#/components/schemas/");
+    &spec["components"]["schemas"][name]
+}
+
+fn deposit(client_id: u16, transaction_id: u32, amount: &str) -> Value {
+    json!({"type": "deposit", "client_id": client_id, "transaction_id": transaction_id, "amount": amount})
+}
+
+fn withdrawal(client_id: u16, transaction_id: u32, amount: &str) -> Value {
+    json!({"type": "withdrawal", "client_id": client_id, "transaction_id": transaction_id, "amount": amount})
+}
+
+#[tokio::test]
+async fn serves_the_generated_document() {
+    let server = TestServer::new(ServerOptions::default()).await;
+    let served: Value = server
+        .request(Method::GET, "/openapi.json")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(served, server.spec);
+    assert!(served["openapi"].as_str().unwrap().starts_with("3."));
+}
+
+#[tokio::test]
+async fn every_documented_operation_is_routed() {
+    let server = TestServer::new(ServerOptions::default()).await;
+    let paths = server.spec["paths"].as_object().unwrap();
+    assert_eq!(paths.len(), 10);
+    for (template, operations) in paths {
+        let path = template.replace("{id}", "1");
+        for method in operations.as_object().unwrap().keys() {
+            let method: Method = method.to_uppercase().parse().unwrap();
+            let mut request = server.request(method.clone(), &path);
+            if method == Method::POST {
+                request = request.json(&json!([]));
+            }
+            // An unrouted path answers an empty 404 and a wrong method a 405,
+            // neither of which is documented.
+            server.check(method, template, request).await;
+        }
+    }
+}
+
+#[tokio::test]
+async fn responses_match_documented_schemas() {
+    let server = TestServer::new(ServerOptions::default()).await;
+    let post = |path: &'static str, body: Value| {
+        let template = path.split('?').next().unwrap();
+        let request = server.request(Method::POST, path).json(&body);
+        server.check(Method::POST, template, request)
+    };
+    let get = |path: &str, template: &'static str| {
+        server.check(Method::GET, template, server.request(Method::GET, path))
+    };
+
+    post("/transactions", deposit(1, 1, "100")).await;
+    post("/transactions", deposit(1, 2, "5.5")).await;
+    let error = post("/transactions", deposit(1, 1, "1")).await;
+    assert_eq!(error["code"], "DUPLICATE_TRANSACTION");
+    let error = post("/transactions", withdrawal(1, 3, "1000")).await;
+    assert_eq!(error["code"], "INSUFFICIENT_FUNDS");
+    let error = post("/transactions", json!({"type": "refund"})).await;
+    assert_eq!(error["code"], "INVALID_BODY");
+    let request = server
+        .request(Method::POST, "/transactions")
+        .body("{\"type\":");
+    let error = server.check(Method::POST, "/transactions", request).await;
+    assert_eq!(error["code"], "UNSUPPORTED_MEDIA_TYPE");
+    post(
+        "/transactions",
+        json!({"type": "dispute", "client_id": 1, "transaction_id": 2}),
+    )
+    .await;
+    let mut conditional = withdrawal(1, 4, "1");
+    conditional["expected_version"] = json!(2);
+    let error = post("/transactions", conditional).await;
+    assert_eq!(error["code"], "VERSION_CONFLICT");
+
+    let report = post(
+        "/transactions/batch",
+        json!([deposit(2, 10, "3"), withdrawal(2, 11, "9")]),
+    )
+    .await;
+    assert_eq!(report["results"][1]["error"]["code"], "INSUFFICIENT_FUNDS");
+    let report = post(
+        "/transactions/batch?atomic=true",
+        json!([deposit(3, 20, "3"), withdrawal(3, 21, "9")]),
+    )
+    .await;
+    assert_eq!(report["committed"], false);
+    let request = server
+        .request(Method::POST, "/transactions/batch")
+        .header(header::CONTENT_TYPE, "application/x-ndjson")
+        .body(format!("{}\n{{", deposit(4, 30, "1")));
+    let error = server
+        .check(Method::POST, "/transactions/batch", request)
+        .await;
+    assert_eq!(error["code"], "INVALID_BODY");
+    let error = post("/transactions/batch?atomic=maybe", json!([])).await;
+    assert_eq!(error["code"], "INVALID_QUERY");
+
+    get("/accounts", "/accounts").await;
+    get("/accounts/1", "/accounts/{id}").await;
+    let request = server
+        .request(Method::GET, "/accounts/1")
+        .header(header::IF_NONE_MATCH, "\"3\"");
+    let not_modified = server.check(Method::GET, "/accounts/{id}", request).await;
+    assert!(not_modified.is_null(), "expected 304, got {not_modified}");
+    let error = get("/accounts/9", "/accounts/{id}").await;
+    assert_eq!(error["code"], "ACCOUNT_NOT_FOUND");
+    let error = get("/accounts/abc", "/accounts/{id}").await;
+    assert_eq!(error["code"], "INVALID_PATH");
+
+    let page = get(
+        "/accounts/1/transactions?limit=1",
+        "/accounts/{id}/transactions",
+    )
+    .await;
+    assert!(page["next_cursor"].is_string());
+    let page = get(
+        "/accounts/1/transactions?status=inflight",
+        "/accounts/{id}/transactions",
+    )
+    .await;
+    assert_eq!(page["transactions"][0]["status"], "Inflight");
+    let error = get(
+        "/accounts/1/transactions?limit=0",
+        "/accounts/{id}/transactions",
+    )
+    .await;
+    assert_eq!(error["code"], "INVALID_QUERY");
+
+    get("/transactions/1", "/transactions/{id}").await;
+    let error = get("/transactions/99", "/transactions/{id}").await;
+    assert_eq!(error["code"], "TRANSACTION_NOT_FOUND");
+
+    let error = get("/accounts/ws", "/accounts/ws").await;
+    assert_eq!(error["code"], "INVALID_UPGRADE");
+    let url = format!("{}/accounts/ws", server.base_url.replace("http", "ws"));
+    let (_, response) = tokio_tungstenite::connect_async(url).await.unwrap();
+    let documented = &server.spec["paths"]["/accounts/ws"]["get"]["responses"];
+    assert!(documented[response.status().as_str()].is_object());
+}
+
+#[tokio::test]
+async fn stream_events_match_event_schema() {
+    let server = TestServer::new(ServerOptions::default()).await;
+    let response = server
+        .request(Method::GET, "/accounts/stream?last_event_id=5")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let schema = &server.spec["paths"]["/accounts/stream"]["get"]["responses"]["200"]["content"]["text/event-stream"]
+        ["schema"];
+    server
+        .client
+        .post(format!("{}/transactions", server.base_url))
+        .json(&deposit(1, 1, "1"))
+        .send()
+        .await
+        .unwrap();
+
+    // A resync for the unknown ID, then the transaction and account events.
+    let mut body = response.bytes_stream();
+    let mut buffer = String::new();
+    let mut types = Vec::new();
+    while types.len() < 3 {
+        let chunk = tokio::time::timeout(tokio::time::Duration::from_secs(5), body.next())
+            .await
+            .expect("event within 5s")
+            .unwrap()
+            .unwrap();
+        buffer.push_str(std::str::from_utf8(&chunk).unwrap());
+        // Only complete lines; the rest waits for the next chunk.
+        let Some(end) = buffer.rfind('\n') else {
+            continue;
+        };
+        let complete: String = buffer.drain(..=end).collect();
+        for line in complete
+            .lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+        {
+            let event: Value = serde_json::from_str(line.trim_start()).unwrap();
+            validate(&server.spec, schema, &event).unwrap_or_else(|e| panic!("{e}"));
+            types.push(event["type"].as_str().unwrap().to_string());
+        }
+    }
+    assert_eq!(types, ["resync", "transaction", "account"]);
+}
+
+#[tokio::test]
+async fn authentication_errors_are_documented() {
+    let keys_file = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/keys.toml");
+    let server = TestServer::new(ServerOptions {
+        keys: Some(ApiKeys::from_file(keys_file).unwrap()),
+        ..ServerOptions::default()
+    })
+    .await;
+
+    let request = server.request(Method::GET, "/accounts");
+    let error = server.check(Method::GET, "/accounts", request).await;
+    assert_eq!(error["code"], "UNAUTHORIZED");
+    let request = server
+        .request(Method::GET, "/accounts")
+        .bearer_auth("client-1-test-key");
+    let error = server.check(Method::GET, "/accounts", request).await;
+    assert_eq!(error["code"], "FORBIDDEN");
+    let request = server
+        .request(Method::POST, "/transactions")
+        .header("X-API-Key", "client-1-test-key")
+        .json(&deposit(2, 1, "1"));
+    let error = server.check(Method::POST, "/transactions", request).await;
+    assert_eq!(error["code"], "FORBIDDEN");
+
+    // The document itself needs no key.
+    let request = server.request(Method::GET, "/openapi.json");
+    server.check(Method::GET, "/openapi.json", request).await;
+}
+
+#[tokio::test]
+async fn rate_limit_errors_are_documented() {
+    let limits = RateLimits {
+        client: Some(RateLimit::new(0.001, 1)),
+        ..RateLimits::default()
+    };
+    let server = TestServer::new(ServerOptions {
+        limits: Some(Arc::new(RateLimiter::new(limits).unwrap())),
+        ..ServerOptions::default()
+    })
+    .await;
+
+    let request = server
+        .request(Method::POST, "/transactions")
+        .json(&deposit(1, 1, "1"));
+    server.check(Method::POST, "/transactions", request).await;
+    let request = server
+        .request(Method::POST, "/transactions")
+        .json(&deposit(1, 2, "1"));
+    let error = server.check(Method::POST, "/transactions", request).await;
+    assert_eq!(error["code"], "RATE_LIMITED");
+    let request = server
+        .request(Method::POST, "/transactions/batch")
+        .json(&json!([deposit(1, 3, "1")]));
+    let error = server
+        .check(Method::POST, "/transactions/batch", request)
+        .await;
+    assert_eq!(error["code"], "RATE_LIMITED");
+}
+
+#[test]
+fn documents_every_error_code() {
+    let spec = serde_json::to_value(openapi()).unwrap();
+    let documented = &spec["components"]["schemas"]["ErrorResponse"]["properties"]["code"]["enum"];
+    for code in error_codes() {
+        assert!(
+            documented.as_array().unwrap().contains(&json!(code)),
+            "{code} is missing from the document"
+        );
+    }
+}
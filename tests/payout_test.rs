@@ -0,0 +1,207 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Payout export integration tests: withdrawals processed by the engine are
+//! written as pain.001 and NACHA files and parsed back.
+
+use ledger_demo_rs::{
+    Beneficiaries, ClientId, Engine, Payout, PayoutCollector, PayoutConfig, PayoutError,
+    PayoutFile, PayoutFormat, TransactionId, TransactionType,
+};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+const BENEFICIARIES: &str = include_str!("fixtures/beneficiaries.csv");
+const CONFIG: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/payout.toml");
+
+fn deposit(client: u16, tx: u32, amount: Decimal) -> TransactionType {
+    TransactionType::Deposit {
+        client_id: ClientId(client),
+        transaction_id: TransactionId(tx),
+        amount,
+    }
+}
+
+fn withdrawal(client: u16, tx: u32, amount: Decimal) -> TransactionType {
+    TransactionType::Withdrawal {
+        client_id: ClientId(client),
+        transaction_id: TransactionId(tx),
+        amount,
+    }
+}
+
+/// Runs a small ledger with one rejected withdrawal and returns the payouts.
+fn accepted_withdrawals() -> Vec<Payout> {
+    let engine = Engine::new();
+    let mut collector = PayoutCollector::new();
+    for tx in [
+        deposit(1, 1, dec!(500.00)),
+        deposit(2, 2, dec!(80.00)),
+        deposit(3, 3, dec!(40.00)),
+        withdrawal(1, 4, dec!(120.50)),
+        // Insufficient funds: must not be paid out.
+        withdrawal(2, 5, dec!(100.00)),
+        withdrawal(2, 6, dec!(79.99)),
+        withdrawal(3, 7, dec!(0.01)),
+    ] {
+        let result = engine.process(tx);
+        collector.record(&tx, &result);
+    }
+    collector.into_payouts()
+}
+
+fn build(format: PayoutFormat) -> Result<String, PayoutError> {
+    let config = PayoutConfig::from_file(CONFIG).unwrap();
+    let beneficiaries = Beneficiaries::from_csv(BENEFICIARIES.as_bytes()).unwrap();
+    let file = PayoutFile::build(format, &config, &beneficiaries, &accepted_withdrawals())?;
+    assert_eq!(file.len(), 3);
+    assert_eq!(file.control_sum(), dec!(200.50));
+
+    let mut out = Vec::new();
+    file.write(&mut out)?;
+    Ok(String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn rejected_withdrawals_are_not_collected() {
+    let ids: Vec<u32> = accepted_withdrawals()
+        .iter()
+        .map(|p| p.transaction_id.0)
+        .collect();
+    assert_eq!(ids, vec![4, 6, 7]);
+}
+
+#[test]
+fn pain001_totals_match_its_transactions() {
+    let xml = build(PayoutFormat::Pain001).unwrap();
+    let doc = roxmltree::Document::parse(&xml).unwrap();
+    let texts = |name: &str| -> Vec<&str> {
+        doc.descendants()
+            .filter(|n| n.has_tag_name(name))
+            .filter_map(|n| n.text())
+            .collect()
+    };
+
+    let amounts: Vec<Decimal> = texts("InstdAmt")
+        .iter()
+        .map(|a| a.parse().unwrap())
+        .collect();
+    assert_eq!(amounts, vec![dec!(120.50), dec!(79.99), dec!(0.01)]);
+    assert_eq!(texts("NbOfTxs"), vec!["3", "3"]);
+    assert_eq!(texts("CtrlSum"), vec!["200.50", "200.50"]);
+    assert_eq!(texts("EndToEndId"), vec!["TX4", "TX6", "TX7"]);
+    assert_eq!(
+        texts("IBAN"),
+        vec![
+            "NL91ABNA0417164300",
+            "DE89370400440532013000",
+            "GB82WEST12345698765432",
+            "FR1420041010050500013M02606",
+        ]
+    );
+    assert_eq!(texts("ReqdExctnDt"), vec!["2025-03-07"]);
+}
+
+#[test]
+fn nacha_records_are_blocked_and_balanced() {
+    let ach = build(PayoutFormat::Nacha).unwrap();
+    let lines: Vec<&str> = ach.lines().collect();
+
+    assert_eq!(lines.len() % 10, 0);
+    assert!(lines.iter().all(|line| line.len() == 94));
+
+    let entries: Vec<&str> = lines
+        .iter()
+        .copied()
+        .filter(|l| l.starts_with('6'))
+        .collect();
+    assert_eq!(entries.len(), 3);
+    let amounts: Vec<u64> = entries.iter().map(|e| e[29..39].parse().unwrap()).collect();
+    assert_eq!(amounts, vec![12050, 7999, 1]);
+    assert_eq!(&entries[0][1..3], "22");
+    assert_eq!(&entries[1][1..3], "32");
+    let hash: u64 = entries
+        .iter()
+        .map(|e| e[3..11].parse::<u64>().unwrap())
+        .sum();
+
+    let batch = lines.iter().find(|l| l.starts_with('8')).unwrap();
+    assert_eq!(&batch[4..10], "000003");
+    assert_eq!(batch[10..20].parse::<u64>().unwrap(), hash);
+    assert_eq!(batch[32..44].parse::<u64>().unwrap(), 20050);
+
+    let control = lines
+        .iter()
+        .find(|l| l.starts_with('9') && l.as_bytes()[1] != b'9');
+    let control = control.unwrap();
+    assert_eq!(&control[7..13], "000001");
+    assert_eq!(control[13..21].parse::<u64>().unwrap(), 3);
+    assert_eq!(control[31..43].parse::<u64>().unwrap(), 0);
+    assert_eq!(control[43..55].parse::<u64>().unwrap(), 20050);
+}
+
+#[test]
+fn sepa_currency_must_be_eur() {
+    let beneficiaries = Beneficiaries::from_csv(BENEFICIARIES.as_bytes()).unwrap();
+    for (currency, reason) in [
+        ("EU\"R", "is not an ISO 4217 currency code"),
+        ("eur", "is not an ISO 4217 currency code"),
+        ("USD", "SEPA credit transfers are in EUR"),
+    ] {
+        let mut config = PayoutConfig::from_file(CONFIG).unwrap();
+        config.sepa.as_mut().unwrap().currency = currency.to_string();
+        let err = PayoutFile::build(
+            PayoutFormat::Pain001,
+            &config,
+            &beneficiaries,
+            &accepted_withdrawals(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, PayoutError::Config(_)), "{currency}: {err}");
+        assert!(err.to_string().contains(reason), "{currency}: {err}");
+    }
+}
+
+#[test]
+fn missing_bank_details_fail_the_whole_file() {
+    let config = PayoutConfig::from_file(CONFIG).unwrap();
+    let mut beneficiaries = Beneficiaries::from_csv(BENEFICIARIES.as_bytes()).unwrap();
+    let mut claire = beneficiaries.get(ClientId(3)).unwrap().clone();
+    claire.routing_number = Some("091000018".to_string());
+    beneficiaries.insert(claire);
+
+    let err = PayoutFile::build(
+        PayoutFormat::Nacha,
+        &config,
+        &beneficiaries,
+        &accepted_withdrawals(),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        PayoutError::InvalidBeneficiary { client: 3, .. }
+    ));
+
+    let err = PayoutFile::build(
+        PayoutFormat::Pain001,
+        &config,
+        &Beneficiaries::new(),
+        &accepted_withdrawals(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, PayoutError::MissingBeneficiary(1)));
+}
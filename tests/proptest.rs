@@ -20,7 +20,10 @@
 //! These tests verify invariants that should hold for any sequence of
 //! valid transactions.
 
-use ledger_demo_rs::{Account, ClientId, Engine, TransactionError, TransactionId, TransactionType};
+use ledger_demo_rs::{
+    Account, AmountError, ClientId, Engine, FixedAmount, TransactionError, TransactionId,
+    TransactionType,
+};
 use proptest::prelude::*;
 use rust_decimal::Decimal;
 
@@ -33,6 +36,15 @@ fn arb_amount() -> impl Strategy<Value = Decimal> {
     (1i64..=10_000_000i64).prop_map(|cents| Decimal::new(cents, 4))
 }
 
+/// Generate an amount close to the top of the `Decimal` range.
+///
+/// Mantissas are drawn from the top few bits of the 96-bit range at varying
+/// scales, so a handful of deposits is enough to overflow a balance.
+fn arb_extreme_amount() -> impl Strategy<Value = Decimal> {
+    ((1i128 << 90)..(1i128 << 96), 0u32..=4)
+        .prop_map(|(mantissa, scale)| Decimal::from_i128_with_scale(mantissa, scale))
+}
+
 // =============================================================================
 // Account Invariant Tests
 // =============================================================================
@@ -782,3 +794,236 @@ proptest! {
         }
     }
 }
+
+// =============================================================================
+// Fixed-Point Representation Tests
+// =============================================================================
+
+/// Balance-affecting operation applied to both representations.
+#[derive(Debug, Clone, Copy)]
+enum BalanceOp {
+    Credit(i64),
+    Debit(i64),
+    Hold(i64),
+    Release(i64),
+}
+
+fn arb_balance_op() -> impl Strategy<Value = BalanceOp> {
+    (0u8..4, 1i64..=10_000_000i64).prop_map(|(kind, raw)| match kind {
+        0 => BalanceOp::Credit(raw),
+        1 => BalanceOp::Debit(raw),
+        2 => BalanceOp::Hold(raw),
+        _ => BalanceOp::Release(raw),
+    })
+}
+
+/// Applies `op` to an `(available, held)` pair, returning whether it was accepted.
+fn apply_decimal(state: &mut (Decimal, Decimal), op: BalanceOp) -> bool {
+    let (available, held) = state;
+    match op {
+        BalanceOp::Credit(raw) => *available += Decimal::new(raw, 4),
+        BalanceOp::Debit(raw) | BalanceOp::Hold(raw) if *available < Decimal::new(raw, 4) => {
+            return false;
+        }
+        BalanceOp::Debit(raw) => *available -= Decimal::new(raw, 4),
+        BalanceOp::Hold(raw) => {
+            *available -= Decimal::new(raw, 4);
+            *held += Decimal::new(raw, 4);
+        }
+        BalanceOp::Release(raw) if *held < Decimal::new(raw, 4) => return false,
+        BalanceOp::Release(raw) => {
+            *held -= Decimal::new(raw, 4);
+            *available += Decimal::new(raw, 4);
+        }
+    }
+    true
+}
+
+/// Fixed-point counterpart of [`apply_decimal`], using checked arithmetic only.
+fn apply_fixed(state: &mut (FixedAmount, FixedAmount), op: BalanceOp) -> bool {
+    let (available, held) = state;
+    let amount = |raw: i64| FixedAmount::from_raw(i128::from(raw)).unwrap();
+    match op {
+        BalanceOp::Credit(raw) => *available = available.checked_add(amount(raw)).unwrap(),
+        BalanceOp::Debit(raw) | BalanceOp::Hold(raw) if *available < amount(raw) => return false,
+        BalanceOp::Debit(raw) => *available = available.checked_sub(amount(raw)).unwrap(),
+        BalanceOp::Hold(raw) => {
+            *available = available.checked_sub(amount(raw)).unwrap();
+            *held = held.checked_add(amount(raw)).unwrap();
+        }
+        BalanceOp::Release(raw) if *held < amount(raw) => return false,
+        BalanceOp::Release(raw) => {
+            *held = held.checked_sub(amount(raw)).unwrap();
+            *available = available.checked_add(amount(raw)).unwrap();
+        }
+    }
+    true
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(1000))]
+
+    /// Any four-decimal string parses to the same value in both representations.
+    #[test]
+    fn fixed_and_decimal_parse_agree(raw in any::<i64>()) {
+        let text = Decimal::new(raw, 4).to_string();
+        let fixed: FixedAmount = text.parse().unwrap();
+        let decimal: Decimal = text.parse().unwrap();
+        prop_assert_eq!(fixed.raw(), i128::from(raw));
+        prop_assert_eq!(fixed.to_decimal(), decimal);
+        prop_assert_eq!(FixedAmount::from_decimal(decimal), Ok(fixed));
+    }
+
+    /// Amounts with more than four significant decimals are rejected, never rounded.
+    #[test]
+    fn fixed_rejects_excess_precision(raw in any::<i64>(), scale in 5u32..=10) {
+        prop_assume!(raw % 10 != 0);
+        let decimal = Decimal::new(raw, scale);
+        prop_assert_eq!(
+            decimal.to_string().parse::<FixedAmount>(),
+            Err(AmountError::TooPrecise)
+        );
+        prop_assert_eq!(FixedAmount::from_decimal(decimal), Err(AmountError::TooPrecise));
+    }
+
+    /// Balance arithmetic produces identical results in both representations.
+    #[test]
+    fn fixed_and_decimal_balances_agree(
+        ops in prop::collection::vec(arb_balance_op(), 1..50),
+    ) {
+        let mut decimal = (Decimal::ZERO, Decimal::ZERO);
+        let mut fixed = (FixedAmount::ZERO, FixedAmount::ZERO);
+
+        for op in ops {
+            prop_assert_eq!(apply_decimal(&mut decimal, op), apply_fixed(&mut fixed, op));
+            prop_assert_eq!(fixed.0.to_decimal(), decimal.0);
+            prop_assert_eq!(fixed.1.to_decimal(), decimal.1);
+        }
+    }
+
+    /// Accounts agree with a `Decimal` reference model regardless of the
+    /// representation selected by the `fixed-point` feature.
+    #[test]
+    fn account_matches_decimal_model(
+        ops in prop::collection::vec((any::<bool>(), arb_amount()), 1..50),
+    ) {
+        let client_id = ClientId(1);
+        let mut account = Account::new(client_id);
+        let mut expected = Decimal::ZERO;
+
+        for (i, (is_deposit, amount)) in ops.into_iter().enumerate() {
+            let transaction_id = TransactionId(i as u32);
+            let tx = if is_deposit {
+                TransactionType::Deposit { client_id, transaction_id, amount }
+            } else {
+                TransactionType::Withdrawal { client_id, transaction_id, amount }
+            };
+            let accepted = account.add_transaction(tx).is_ok();
+
+            if is_deposit {
+                prop_assert!(accepted);
+                expected += amount;
+            } else if expected >= amount {
+                prop_assert!(accepted);
+                expected -= amount;
+            } else {
+                prop_assert!(!accepted);
+            }
+            prop_assert_eq!(account.available(), expected);
+        }
+    }
+}
+
+// =============================================================================
+// Overflow Tests
+// =============================================================================
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(500))]
+
+    /// Deposits near the top of the range either apply or fail with
+    /// `Overflow`, and a rejected deposit never changes the account.
+    #[test]
+    fn extreme_deposits_overflow_safely(
+        deposits in prop::collection::vec(arb_extreme_amount(), 1..20),
+    ) {
+        let client_id = ClientId(1);
+        let mut account = Account::new(client_id);
+
+        for (i, amount) in deposits.into_iter().enumerate() {
+            let before = (account.available(), account.held());
+            let result = account.add_transaction(TransactionType::Deposit {
+                client_id,
+                transaction_id: TransactionId(i as u32),
+                amount,
+            });
+
+            match result {
+                Ok(()) => prop_assert!(account.available() >= before.0),
+                Err(e) => {
+                    prop_assert_eq!(e, TransactionError::Overflow);
+                    prop_assert_eq!((account.available(), account.held()), before);
+                }
+            }
+            prop_assert_eq!(account.total(), account.available() + account.held());
+        }
+    }
+
+    /// Dispute lifecycle operations on extreme balances never panic and keep
+    /// `total == available + held`.
+    #[test]
+    fn extreme_dispute_lifecycle_overflows_safely(
+        deposits in prop::collection::vec(arb_extreme_amount(), 2..10),
+        ops in prop::collection::vec((0u8..3, 0usize..10), 1..20),
+    ) {
+        let client_id = ClientId(1);
+        let mut account = Account::new(client_id);
+
+        for (i, amount) in deposits.iter().enumerate() {
+            let _ = account.add_transaction(TransactionType::Deposit {
+                client_id,
+                transaction_id: TransactionId(i as u32),
+                amount: *amount,
+            });
+        }
+
+        for (kind, idx) in ops {
+            let transaction_id = TransactionId((idx % deposits.len()) as u32);
+            let tx = match kind {
+                0 => TransactionType::Dispute { client_id, transaction_id },
+                1 => TransactionType::Resolve { client_id, transaction_id },
+                _ => TransactionType::Chargeback { client_id, transaction_id },
+            };
+            let _ = account.add_transaction(tx);
+
+            prop_assert!(account.available() >= Decimal::ZERO);
+            prop_assert!(account.held() >= Decimal::ZERO);
+            prop_assert_eq!(account.total(), account.available() + account.held());
+        }
+    }
+
+    /// The engine reports overflow instead of panicking, and snapshots of
+    /// extreme balances remain consistent.
+    #[test]
+    fn engine_extreme_deposits_overflow_safely(
+        deposits in prop::collection::vec((1u16..=3, arb_extreme_amount()), 1..30),
+    ) {
+        let engine = Engine::new();
+
+        for (i, (client, amount)) in deposits.into_iter().enumerate() {
+            let result = engine.process(TransactionType::Deposit {
+                client_id: ClientId(client),
+                transaction_id: TransactionId(i as u32),
+                amount,
+            });
+            prop_assert!(matches!(result, Ok(()) | Err(TransactionError::Overflow)));
+        }
+
+        for snapshot in engine.accounts() {
+            prop_assert_eq!(
+                snapshot.total,
+                (snapshot.available + snapshot.held).round_dp(4)
+            );
+        }
+    }
+}
@@ -17,266 +17,911 @@
 
 //! Integration tests for the REST API server with concurrent requests.
 //!
-//! These tests verify that the server correctly handles thousands of
+//! The smoke tests run the library router and the `serve` subcommand; the
+//! load tests verify that the server correctly handles thousands of
 //! concurrent requests while maintaining data consistency.
 
-use axum::{
-    Json, Router,
-    extract::{Path, State},
-    http::StatusCode,
-    response::{IntoResponse, Response},
-    routing::{get, post},
+// The load tests number their transactions with explicit counters.
+#![allow(clippy::explicit_counter_loop)]
+
+use futures::StreamExt;
+use ledger_demo_rs::server::{
+    AccountResponse, ApiKeys, BatchResponse, BatchStatus, ErrorResponse, Event, EventData,
+    HistoryResponse, RateLimit, RateLimiter, RateLimits, ServerOptions, TransactionResponse,
+    TransactionSubmission, create_router_with,
 };
-use ledger_demo_rs::{ClientId, Engine, TransactionError, TransactionId, TransactionType};
-use reqwest::Client;
+use ledger_demo_rs::{ClientId, Engine, TransactionRequest, TransactionStatus};
+use reqwest::{Client, StatusCode};
 use rust_decimal::Decimal;
-use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU32, Ordering};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tokio::net::TcpListener;
 
-// === DTOs (duplicated from example for test isolation) ===
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "lowercase")]
-pub enum TransactionRequest {
-    Deposit {
-        client_id: u16,
-        transaction_id: u32,
-        amount: Decimal,
-    },
-    Withdrawal {
-        client_id: u16,
-        transaction_id: u32,
-        amount: Decimal,
-    },
-    Dispute {
-        client_id: u16,
-        transaction_id: u32,
-    },
-    Resolve {
-        client_id: u16,
-        transaction_id: u32,
-    },
-    Chargeback {
-        client_id: u16,
-        transaction_id: u32,
-    },
-}
-
-impl TransactionRequest {
-    fn into_transaction_type(self) -> TransactionType {
-        match self {
-            Self::Deposit {
-                client_id,
-                transaction_id,
-                amount,
-            } => TransactionType::Deposit {
-                client_id: ClientId(client_id),
-                transaction_id: TransactionId(transaction_id),
-                amount,
-            },
-            Self::Withdrawal {
-                client_id,
-                transaction_id,
-                amount,
-            } => TransactionType::Withdrawal {
-                client_id: ClientId(client_id),
-                transaction_id: TransactionId(transaction_id),
-                amount,
-            },
-            Self::Dispute {
-                client_id,
-                transaction_id,
-            } => TransactionType::Dispute {
-                client_id: ClientId(client_id),
-                transaction_id: TransactionId(transaction_id),
-            },
-            Self::Resolve {
-                client_id,
-                transaction_id,
-            } => TransactionType::Resolve {
-                client_id: ClientId(client_id),
-                transaction_id: TransactionId(transaction_id),
-            },
-            Self::Chargeback {
-                client_id,
-                transaction_id,
-            } => TransactionType::Chargeback {
-                client_id: ClientId(client_id),
-                transaction_id: TransactionId(transaction_id),
-            },
+/// Test server that binds to an ephemeral port.
+struct TestServer {
+    base_url: String,
+    engine: Arc<Engine>,
+}
+
+impl TestServer {
+    async fn new() -> Self {
+        Self::with_options(ServerOptions::default()).await
+    }
+
+    async fn with_options(options: ServerOptions) -> Self {
+        let engine = Arc::new(Engine::new());
+        let app = create_router_with(Arc::clone(&engine), options);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let base_url = format!("http://{}", addr);
+
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        // Wait for server to be ready by polling with retries
+        let client = Client::new();
+        let health_url = format!("{}/accounts", base_url);
+        for _ in 0..50 {
+            match client.get(&health_url).send().await {
+                Ok(_) => break,
+                Err(_) => tokio::time::sleep(tokio::time::Duration::from_millis(50)).await,
+            }
         }
+
+        TestServer { base_url, engine }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AccountResponse {
-    pub client: u16,
-    pub available: Decimal,
-    pub held: Decimal,
-    pub total: Decimal,
-    pub locked: bool,
+/// A `ledger-demo-rs serve` process on an ephemeral port, killed on drop.
+struct ServeProcess {
+    child: Child,
+    base_url: String,
+}
+
+impl ServeProcess {
+    fn spawn() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_main"))
+            .args(["serve", "--bind", "127.0.0.1:0"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(child.stdout.take().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let base_url = line
+            .trim()
+            .strip_prefix("Listening on ")
+            .unwrap_or_else(|| panic!("unexpected output: {line}"))
+            .to_string();
+        ServeProcess { child, base_url }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ErrorResponse {
-    pub error: String,
-    pub code: String,
+impl Drop for ServeProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
 }
 
-// === Server Setup ===
+// === Smoke tests ===
 
-#[derive(Clone)]
-pub struct AppState {
-    pub engine: Arc<Engine>,
+#[tokio::test]
+async fn serve_subcommand_processes_and_reports() {
+    let server = ServeProcess::spawn();
+    let client = Client::new();
+    let url = |path: &str| format!("{}{}", server.base_url, path);
+
+    let deposit = TransactionRequest::Deposit {
+        client_id: 1,
+        transaction_id: 1,
+        amount: "100.00".parse().unwrap(),
+    };
+    let response = client
+        .post(url("/transactions"))
+        .json(&deposit)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let account: AccountResponse = client
+        .get(url("/accounts/1"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(account.client, 1);
+    assert_eq!(account.available, "100".parse::<Decimal>().unwrap());
+    assert!(!account.locked);
 }
 
-pub struct AppError(TransactionError);
+#[tokio::test]
+async fn errors_carry_status_and_code() {
+    let server = TestServer::new().await;
+    let client = Client::new();
 
-impl From<TransactionError> for AppError {
-    fn from(err: TransactionError) -> Self {
-        AppError(err)
+    let withdrawal = TransactionRequest::Withdrawal {
+        client_id: 1,
+        transaction_id: 1,
+        amount: "5".parse().unwrap(),
+    };
+    let deposit = TransactionRequest::Deposit {
+        client_id: 1,
+        transaction_id: 2,
+        amount: "1".parse().unwrap(),
+    };
+    for (request, status, code) in [
+        (&deposit, StatusCode::CREATED, None),
+        (
+            &deposit,
+            StatusCode::CONFLICT,
+            Some("DUPLICATE_TRANSACTION"),
+        ),
+        (
+            &withdrawal,
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Some("INSUFFICIENT_FUNDS"),
+        ),
+    ] {
+        let response = client
+            .post(server.url("/transactions"))
+            .json(request)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), status);
+        if let Some(code) = code {
+            let body: ErrorResponse = response.json().await.unwrap();
+            assert_eq!(body.code, code);
+        }
     }
+
+    let response = client.get(server.url("/accounts/9")).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(body.code, "ACCOUNT_NOT_FOUND");
 }
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let (status, code) = match &self.0 {
-            TransactionError::MissingAmount => (StatusCode::BAD_REQUEST, "MISSING_AMOUNT"),
-            TransactionError::InvalidAmount => (StatusCode::BAD_REQUEST, "INVALID_AMOUNT"),
-            TransactionError::InsufficientFunds => {
-                (StatusCode::UNPROCESSABLE_ENTITY, "INSUFFICIENT_FUNDS")
-            }
-            TransactionError::TransactionNotFound => {
-                (StatusCode::NOT_FOUND, "TRANSACTION_NOT_FOUND")
-            }
-            TransactionError::ClientMismatch => (StatusCode::BAD_REQUEST, "CLIENT_MISMATCH"),
-            TransactionError::AlreadyDisputed => (StatusCode::CONFLICT, "ALREADY_DISPUTED"),
-            TransactionError::NotDisputed => (StatusCode::CONFLICT, "NOT_DISPUTED"),
-            TransactionError::NotDisputable => (StatusCode::BAD_REQUEST, "NOT_DISPUTABLE"),
-            TransactionError::DuplicateTransaction => {
-                (StatusCode::CONFLICT, "DUPLICATE_TRANSACTION")
-            }
-            TransactionError::AccountLocked => (StatusCode::FORBIDDEN, "ACCOUNT_LOCKED"),
+#[tokio::test]
+async fn transaction_and_history_endpoints() {
+    let server = TestServer::new().await;
+    let client = Client::new();
+
+    for transaction_id in 1..=3 {
+        let deposit = TransactionRequest::Deposit {
+            client_id: 1,
+            transaction_id,
+            amount: "2".parse().unwrap(),
         };
+        let response = client
+            .post(server.url("/transactions"))
+            .json(&deposit)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+    let dispute = TransactionRequest::Dispute {
+        client_id: 1,
+        transaction_id: 2,
+    };
+    client
+        .post(server.url("/transactions"))
+        .json(&dispute)
+        .send()
+        .await
+        .unwrap();
+
+    let response = client
+        .get(server.url("/transactions/2"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: TransactionResponse = response.json().await.unwrap();
+    assert_eq!((body.transaction_id, body.client), (2, 1));
+    assert_eq!(body.status, TransactionStatus::Inflight);
+    assert!(matches!(body.request, TransactionRequest::Deposit { .. }));
+
+    let response = client
+        .get(server.url("/accounts/1/transactions?limit=2"))
+        .send()
+        .await
+        .unwrap();
+    let page: HistoryResponse = response.json().await.unwrap();
+    assert_eq!(page.transactions.len(), 2);
+    let cursor = page.next_cursor.unwrap();
+    let response = client
+        .get(server.url(&format!("/accounts/1/transactions?limit=2&cursor={cursor}")))
+        .send()
+        .await
+        .unwrap();
+    let page: HistoryResponse = response.json().await.unwrap();
+    assert_eq!(page.transactions[0].transaction_id, 3);
+    assert_eq!(page.next_cursor, None);
+
+    let response = client
+        .get(server.url("/accounts/1/transactions?status=inflight&type=deposit"))
+        .send()
+        .await
+        .unwrap();
+    let page: HistoryResponse = response.json().await.unwrap();
+    let ids: Vec<u32> = page.transactions.iter().map(|t| t.transaction_id).collect();
+    assert_eq!(ids, vec![2]);
 
+    for (path, status, code) in [
         (
-            status,
-            Json(ErrorResponse {
-                error: self.0.to_string(),
-                code: code.to_string(),
-            }),
-        )
-            .into_response()
-    }
-}
-
-async fn create_transaction(
-    State(state): State<AppState>,
-    Json(request): Json<TransactionRequest>,
-) -> Result<StatusCode, AppError> {
-    let tx = request.into_transaction_type();
-    state.engine.process(tx)?;
-    Ok(StatusCode::CREATED)
-}
-
-async fn get_account(
-    State(state): State<AppState>,
-    Path(id): Path<u16>,
-) -> Result<Json<AccountResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let client_id = ClientId(id);
-
-    state
-        .engine
-        .get_account(&client_id)
-        .map(|account| {
-            Json(AccountResponse {
-                client: client_id.0,
-                available: account.available,
-                held: account.held,
-                total: account.total,
-                locked: account.locked,
-            })
-        })
-        .ok_or_else(|| {
-            (
-                StatusCode::NOT_FOUND,
-                Json(ErrorResponse {
-                    error: "Account not found".to_string(),
-                    code: "ACCOUNT_NOT_FOUND".to_string(),
-                }),
-            )
-        })
+            "/transactions/9",
+            StatusCode::NOT_FOUND,
+            "TRANSACTION_NOT_FOUND",
+        ),
+        (
+            "/transactions/1?client=2",
+            StatusCode::NOT_FOUND,
+            "TRANSACTION_NOT_FOUND",
+        ),
+        (
+            "/accounts/9/transactions",
+            StatusCode::NOT_FOUND,
+            "ACCOUNT_NOT_FOUND",
+        ),
+        (
+            "/accounts/1/transactions?limit=0",
+            StatusCode::BAD_REQUEST,
+            "INVALID_QUERY",
+        ),
+        (
+            "/accounts/1/transactions?type=dispute",
+            StatusCode::BAD_REQUEST,
+            "INVALID_QUERY",
+        ),
+        (
+            "/accounts/1/transactions?status=lost",
+            StatusCode::BAD_REQUEST,
+            "INVALID_QUERY",
+        ),
+        (
+            "/accounts/1/transactions?cursor=x",
+            StatusCode::BAD_REQUEST,
+            "INVALID_QUERY",
+        ),
+    ] {
+        let response = client.get(server.url(path)).send().await.unwrap();
+        assert_eq!(response.status(), status, "{path}");
+        let body: ErrorResponse = response.json().await.unwrap();
+        assert_eq!(body.code, code, "{path}");
+    }
+}
+
+#[tokio::test]
+async fn batch_endpoint_accepts_arrays_and_json_lines() {
+    let server = TestServer::new().await;
+    let client = Client::new();
+
+    let batch = serde_json::json!([
+        {"type": "deposit", "client_id": 1, "transaction_id": 1, "amount": "10"},
+        {"type": "withdrawal", "client_id": 1, "transaction_id": 2, "amount": "50"},
+        {"type": "withdrawal", "client_id": 1, "transaction_id": 3, "amount": "4"},
+    ]);
+    let response = client
+        .post(server.url("/transactions/batch"))
+        .json(&batch)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: BatchResponse = response.json().await.unwrap();
+    assert!(body.committed);
+    let statuses: Vec<_> = body.results.iter().map(|r| r.status).collect();
+    assert_eq!(
+        statuses,
+        [
+            BatchStatus::Applied,
+            BatchStatus::Rejected,
+            BatchStatus::Applied
+        ]
+    );
+    assert_eq!(
+        body.results[1].error.as_ref().unwrap().code,
+        "INSUFFICIENT_FUNDS"
+    );
+
+    // All-or-nothing: the deposit is rolled back with the failed withdrawal
+    let lines = concat!(
+        r#"{"type": "deposit", "client_id": 2, "transaction_id": 4, "amount": "1"}"#,
+        "\n\n",
+        r#"{"type": "withdrawal", "client_id": 1, "transaction_id": 5, "amount": "99"}"#,
+        "\n",
+    );
+    let response = client
+        .post(server.url("/transactions/batch?atomic=true"))
+        .header("Content-Type", "application/x-ndjson")
+        .body(lines)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: BatchResponse = response.json().await.unwrap();
+    assert!(!body.committed);
+    assert_eq!(body.results[0].status, BatchStatus::RolledBack);
+    assert_eq!(body.results[1].status, BatchStatus::Rejected);
+    assert!(server.engine.get_account(&ClientId(2)).is_none());
+    assert_eq!(
+        server.engine.get_account(&ClientId(1)).unwrap().available,
+        Decimal::from(6)
+    );
+
+    let response = client
+        .post(server.url("/transactions/batch"))
+        .header("Content-Type", "application/x-ndjson")
+        .body("{\"type\": \"deposit\"}\n")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(body.code, "INVALID_BODY");
+    assert!(body.error.starts_with("line 1:"), "{}", body.error);
+}
+
+/// POSTs a one-unit deposit.
+async fn post_deposit(server: &TestServer, client: &Client, client_id: u16, transaction_id: u32) {
+    let deposit = TransactionRequest::Deposit {
+        client_id,
+        transaction_id,
+        amount: "1".parse().unwrap(),
+    };
+    client
+        .post(server.url("/transactions"))
+        .json(&deposit)
+        .send()
+        .await
+        .unwrap();
+}
+
+/// Reads `count` events from a Server-Sent Events response.
+async fn read_sse(response: reqwest::Response, count: usize) -> Vec<(Option<String>, Event)> {
+    let mut body = response.bytes_stream();
+    let mut buffer = String::new();
+    let mut events = Vec::new();
+    while events.len() < count {
+        let chunk = tokio::time::timeout(tokio::time::Duration::from_secs(5), body.next())
+            .await
+            .expect("event within 5s")
+            .unwrap()
+            .unwrap();
+        buffer.push_str(std::str::from_utf8(&chunk).unwrap());
+        while let Some(end) = buffer.find("\n\n") {
+            let block: String = buffer.drain(..end + 2).collect();
+            let field = |name: &str| {
+                block
+                    .lines()
+                    .find_map(|line| line.strip_prefix(name))
+                    .map(|value| value.strip_prefix(' ').unwrap_or(value).to_string())
+            };
+            if let Some(data) = field("data:") {
+                events.push((field("id:"), serde_json::from_str(&data).unwrap()));
+            }
+        }
+    }
+    events.truncate(count);
+    events
 }
 
-async fn list_accounts(State(state): State<AppState>) -> Json<Vec<AccountResponse>> {
-    let accounts: Vec<AccountResponse> = state
-        .engine
-        .accounts()
+#[tokio::test]
+async fn event_stream_pushes_and_resumes() {
+    let server = TestServer::new().await;
+    let client = Client::new();
+
+    let response = client
+        .get(server.url("/accounts/stream?client=2"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    post_deposit(&server, &client, 1, 1).await;
+    post_deposit(&server, &client, 2, 2).await;
+
+    let events = read_sse(response, 2).await;
+    assert_eq!(events[0].0.as_deref(), Some("3"));
+    match &events[0].1.data {
+        EventData::Transaction { result, .. } => assert_eq!(result.status, BatchStatus::Applied),
+        other => panic!("expected a transaction event, got {other:?}"),
+    }
+    match &events[1].1.data {
+        EventData::Account(account) => assert_eq!((account.client, events[1].1.id), (2, Some(4))),
+        other => panic!("expected an account event, got {other:?}"),
+    }
+
+    // Reconnecting with the last seen ID replays what was missed
+    post_deposit(&server, &client, 2, 3).await;
+    let response = client
+        .get(server.url("/accounts/stream"))
+        .header("Last-Event-ID", "4")
+        .send()
+        .await
+        .unwrap();
+    let ids: Vec<_> = read_sse(response, 2)
+        .await
         .into_iter()
-        .map(|account| AccountResponse {
-            client: account.client_id.0,
-            available: account.available,
-            held: account.held,
-            total: account.total,
-            locked: account.locked,
-        })
+        .map(|(_, event)| event.id)
         .collect();
+    assert_eq!(ids, [Some(5), Some(6)]);
 
-    Json(accounts)
+    let response = client
+        .get(server.url("/accounts/stream"))
+        .header("Last-Event-ID", "soon")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 }
 
-fn create_router(state: AppState) -> Router {
-    Router::new()
-        .route("/transactions", post(create_transaction))
-        .route("/accounts", get(list_accounts))
-        .route("/accounts/{id}", get(get_account))
-        .with_state(state)
+#[tokio::test]
+async fn websocket_pushes_events() {
+    use tokio_tungstenite::tungstenite::Message;
+
+    let server = TestServer::new().await;
+    let client = Client::new();
+    post_deposit(&server, &client, 1, 1).await;
+
+    let url = server
+        .url("/accounts/ws?last_event_id=0")
+        .replacen("http", "ws", 1);
+    let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+    post_deposit(&server, &client, 1, 1).await;
+
+    let mut events = Vec::new();
+    while events.len() < 3 {
+        let message = tokio::time::timeout(tokio::time::Duration::from_secs(5), socket.next())
+            .await
+            .expect("message within 5s")
+            .unwrap()
+            .unwrap();
+        if let Message::Text(text) = message {
+            events.push(serde_json::from_str::<Event>(&text).unwrap());
+        }
+    }
+    let names: Vec<_> = events.iter().map(Event::name).collect();
+    assert_eq!(names, ["transaction", "account", "transaction"]);
+    match &events[2].data {
+        EventData::Transaction { result, .. } => {
+            assert_eq!(result.error.as_ref().unwrap().code, "DUPLICATE_TRANSACTION");
+        }
+        other => panic!("expected a transaction event, got {other:?}"),
+    }
 }
 
-/// Test server that binds to an ephemeral port.
-struct TestServer {
-    base_url: String,
-    engine: Arc<Engine>,
+#[tokio::test]
+async fn api_keys_authenticate_and_scope_requests() {
+    let keys_file = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/keys.toml");
+    let server = TestServer::with_options(ServerOptions {
+        keys: Some(ApiKeys::from_file(keys_file).unwrap()),
+        ..ServerOptions::default()
+    })
+    .await;
+    let client = Client::new();
+    let deposit = |client_id, transaction_id| TransactionRequest::Deposit {
+        client_id,
+        transaction_id,
+        amount: "5".parse().unwrap(),
+    };
+    let dispute = TransactionRequest::Dispute {
+        client_id: 1,
+        transaction_id: 1,
+    };
+    let submit = |key: Option<&'static str>, request: TransactionRequest| {
+        let mut builder = client.post(server.url("/transactions")).json(&request);
+        if let Some(key) = key {
+            builder = builder.bearer_auth(key);
+        }
+        builder.send()
+    };
+    let expect = |response: reqwest::Response, status: StatusCode, code: &'static str| async move {
+        assert_eq!(response.status(), status);
+        let body: ErrorResponse = response.json().await.unwrap();
+        assert_eq!(body.code, code);
+    };
+
+    let response = submit(None, deposit(1, 1)).await.unwrap();
+    assert_eq!(response.headers()["www-authenticate"], "Bearer");
+    expect(response, StatusCode::UNAUTHORIZED, "UNAUTHORIZED").await;
+    let response = submit(Some("guess"), deposit(1, 1)).await.unwrap();
+    expect(response, StatusCode::UNAUTHORIZED, "UNAUTHORIZED").await;
+
+    // Client keys: own deposits and withdrawals only
+    let client_key = Some("client-1-test-key");
+    let response = submit(client_key, deposit(1, 1)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = submit(client_key, deposit(2, 2)).await.unwrap();
+    expect(response, StatusCode::FORBIDDEN, "FORBIDDEN").await;
+    let response = submit(client_key, dispute.clone()).await.unwrap();
+    expect(response, StatusCode::FORBIDDEN, "FORBIDDEN").await;
+    let response = submit(Some("operator-test-key"), dispute).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // A batch with one foreign transaction is refused as a whole
+    let response = client
+        .post(server.url("/transactions/batch"))
+        .header("X-API-Key", "client-2-test-key")
+        .json(&[deposit(2, 3), deposit(1, 4)])
+        .send()
+        .await
+        .unwrap();
+    expect(response, StatusCode::FORBIDDEN, "FORBIDDEN").await;
+    assert!(server.engine.get_account(&ClientId(2)).is_none());
+
+    // Reads: own account only; the list is for operators
+    for (path, key, status) in [
+        ("/accounts/1", "client-1-test-key", StatusCode::OK),
+        (
+            "/accounts/1/transactions",
+            "client-1-test-key",
+            StatusCode::OK,
+        ),
+        ("/transactions/1", "client-1-test-key", StatusCode::OK),
+        (
+            "/transactions/1",
+            "client-2-test-key",
+            StatusCode::NOT_FOUND,
+        ),
+        ("/accounts/1", "client-2-test-key", StatusCode::FORBIDDEN),
+        (
+            "/accounts/stream?client=1",
+            "client-2-test-key",
+            StatusCode::FORBIDDEN,
+        ),
+        ("/accounts", "client-1-test-key", StatusCode::FORBIDDEN),
+        ("/accounts", "operator-test-key", StatusCode::OK),
+        ("/metrics", "client-1-test-key", StatusCode::FORBIDDEN),
+        ("/metrics", "operator-test-key", StatusCode::OK),
+    ] {
+        let response = client
+            .get(server.url(path))
+            .bearer_auth(key)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), status, "{path} with {key}");
+    }
 }
 
-impl TestServer {
-    async fn new() -> Self {
-        let engine = Arc::new(Engine::new());
-        let state = AppState {
-            engine: engine.clone(),
-        };
+#[tokio::test]
+async fn metrics_endpoint_reports_engine_and_requests() {
+    let server = TestServer::new().await;
+    let client = Client::new();
+    post_deposit(&server, &client, 1, 1).await;
+    post_deposit(&server, &client, 1, 1).await;
+    client.get(server.url("/accounts/1")).send().await.unwrap();
 
-        let app = create_router(state);
-        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
-        let addr = listener.local_addr().unwrap();
-        let base_url = format!("http://{}", addr);
+    let response = client.get(server.url("/metrics")).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        response.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain; version=0.0.4")
+    );
+    let text = response.text().await.unwrap();
+    for line in [
+        "ledger_transactions_total{type=\"deposit\",result=\"applied\"} 1",
+        "ledger_transactions_total{type=\"deposit\",result=\"rejected\"} 1",
+        "ledger_transaction_errors_total{code=\"DUPLICATE_TRANSACTION\"} 1",
+        "ledger_accounts 1",
+        "ledger_liabilities 1",
+        "ledger_http_requests_total{method=\"POST\",route=\"/transactions\",status=\"201\"} 1",
+        "ledger_http_requests_total{method=\"POST\",route=\"/transactions\",status=\"409\"} 1",
+        "ledger_http_requests_total{method=\"GET\",route=\"/accounts/{id}\",status=\"200\"} 1",
+    ] {
+        assert!(
+            text.lines().any(|l| l == line),
+            "missing '{line}' in:\n{text}"
+        );
+    }
+}
 
-        tokio::spawn(async move {
-            axum::serve(listener, app).await.unwrap();
-        });
+#[tokio::test]
+async fn metrics_lump_extension_methods_together() {
+    let server = TestServer::new().await;
+    let client = Client::new();
+    for method in ["PURGE", "FROBNICATE"] {
+        let method = reqwest::Method::from_bytes(method.as_bytes()).unwrap();
+        client
+            .request(method, server.url("/accounts/1"))
+            .send()
+            .await
+            .unwrap();
+    }
 
-        // Wait for server to be ready by polling with retries
-        let client = Client::new();
-        let health_url = format!("{}/accounts", base_url);
-        for _ in 0..50 {
-            match client.get(&health_url).send().await {
-                Ok(_) => break,
-                Err(_) => tokio::time::sleep(tokio::time::Duration::from_millis(50)).await,
+    let text = client
+        .get(server.url("/metrics"))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    let series: Vec<_> = text
+        .lines()
+        .filter(|l| l.starts_with("ledger_http_requests_total{") && l.contains("/accounts/{id}"))
+        .collect();
+    assert_eq!(series.len(), 1, "{text}");
+    assert!(
+        series[0].starts_with("ledger_http_requests_total{method=\"other\""),
+        "{text}"
+    );
+    assert!(series[0].ends_with(" 2"), "{text}");
+}
+
+#[tokio::test]
+async fn account_versions_drive_etags_and_conditional_transactions() {
+    let server = TestServer::new().await;
+    let client = Client::new();
+    let submit = |transaction_id, expected_version| TransactionSubmission {
+        transaction: TransactionRequest::Deposit {
+            client_id: 1,
+            transaction_id,
+            amount: "5".parse().unwrap(),
+        },
+        expected_version,
+    };
+
+    for (submission, status) in [
+        (submit(1, Some(0)), StatusCode::CREATED),
+        (submit(2, None), StatusCode::CREATED),
+        (submit(3, Some(1)), StatusCode::CONFLICT),
+        (submit(3, Some(2)), StatusCode::CREATED),
+    ] {
+        let response = client
+            .post(server.url("/transactions"))
+            .json(&submission)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), status, "{submission:?}");
+        if status == StatusCode::CONFLICT {
+            let body: ErrorResponse = response.json().await.unwrap();
+            assert_eq!(body.code, "VERSION_CONFLICT");
+        }
+    }
+
+    let response = client.get(server.url("/accounts/1")).send().await.unwrap();
+    assert_eq!(response.headers()["etag"], "\"3\"");
+    let account: AccountResponse = response.json().await.unwrap();
+    assert_eq!(account.version, 3);
+    assert_eq!(account.total, Decimal::from(15));
+
+    for (tag, status) in [
+        ("\"3\"", StatusCode::NOT_MODIFIED),
+        ("\"1\", W/\"3\"", StatusCode::NOT_MODIFIED),
+        ("*", StatusCode::NOT_MODIFIED),
+        ("\"2\"", StatusCode::OK),
+    ] {
+        let response = client
+            .get(server.url("/accounts/1"))
+            .header("if-none-match", tag)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), status, "If-None-Match: {tag}");
+        assert_eq!(response.headers()["etag"], "\"3\"");
+    }
+
+    // The version field does not loosen the transaction's own field checks
+    let response = client
+        .post(server.url("/transactions"))
+        .json(&serde_json::json!({
+            "type": "deposit", "client_id": 1, "transaction_id": 4,
+            "amount": "1", "expected_version": 3, "memo": "x",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(body.code, "INVALID_BODY");
+}
+
+/// Submits `count` deposits for each of `clients` at once, returning the
+/// statuses per client.
+async fn deposit_burst(
+    server: &TestServer,
+    key: Option<&str>,
+    clients: &[u16],
+    count: u32,
+    tx_counter: &AtomicU32,
+) -> HashMap<u16, Vec<reqwest::Response>> {
+    let client = Client::new();
+    let mut handles = Vec::new();
+    for &client_id in clients {
+        for _ in 0..count {
+            let mut request =
+                client
+                    .post(server.url("/transactions"))
+                    .json(&TransactionRequest::Deposit {
+                        client_id,
+                        transaction_id: tx_counter.fetch_add(1, Ordering::SeqCst),
+                        amount: "10.00".parse().unwrap(),
+                    });
+            if let Some(key) = key {
+                request = request.bearer_auth(key);
             }
+            handles.push(tokio::spawn(async move {
+                (client_id, request.send().await.unwrap())
+            }));
         }
+    }
+    let mut responses: HashMap<u16, Vec<_>> = HashMap::new();
+    for handle in handles {
+        let (client_id, response) = handle.await.unwrap();
+        responses.entry(client_id).or_default().push(response);
+    }
+    responses
+}
 
-        TestServer { base_url, engine }
+/// Counts the `201`s, checking that every other response is a well-formed
+/// `429`.
+async fn admitted(responses: Vec<reqwest::Response>) -> usize {
+    let mut created = 0;
+    for response in responses {
+        if response.status() == StatusCode::CREATED {
+            created += 1;
+            continue;
+        }
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = response.headers()["retry-after"]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(retry_after >= 1);
+        let body: ErrorResponse = response.json().await.unwrap();
+        assert_eq!(body.code, "RATE_LIMITED");
     }
+    created
+}
 
-    fn url(&self, path: &str) -> String {
-        format!("{}{}", self.base_url, path)
+/// A limit that does not refill during a test.
+fn no_refill(burst: u32) -> RateLimit {
+    RateLimit::new(0.001, burst)
+}
+
+#[tokio::test]
+async fn rate_limits_cap_bursts_per_client() {
+    let limiter = Arc::new(
+        RateLimiter::new(RateLimits {
+            client: Some(no_refill(10)),
+            clients: HashMap::from([(3, no_refill(2))]),
+            ..RateLimits::default()
+        })
+        .unwrap(),
+    );
+    let server = TestServer::with_options(ServerOptions {
+        limits: Some(limiter),
+        ..ServerOptions::default()
+    })
+    .await;
+    let tx_counter = AtomicU32::new(1);
+
+    let clients: Vec<u16> = (1..=8).collect();
+    let responses = deposit_burst(&server, None, &clients, 25, &tx_counter).await;
+    for (client_id, responses) in responses {
+        let expected = if client_id == 3 { 2 } else { 10 };
+        assert_eq!(admitted(responses).await, expected, "client {client_id}");
+        let account = server.engine.get_account(&ClientId(client_id)).unwrap();
+        assert_eq!(account.total, Decimal::from(expected * 10));
+    }
+
+    // A batch draws one token per transaction and is refused as a whole
+    let client = Client::new();
+    let batch = serde_json::json!([
+        {"type": "withdrawal", "client_id": 9, "transaction_id": 900, "amount": "1"},
+        {"type": "deposit", "client_id": 1, "transaction_id": 901, "amount": "1"},
+    ]);
+    let response = client
+        .post(server.url("/transactions/batch"))
+        .json(&batch)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(server.engine.get_account(&ClientId(9)).is_none());
+    // Refused submissions never reach the engine
+    assert_eq!(server.engine.metrics().processed(), 7 * 10 + 2);
+}
+
+#[tokio::test]
+async fn rate_limits_cap_bursts_per_key() {
+    let keys_file = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/keys.toml");
+    let limiter = Arc::new(
+        RateLimiter::new(RateLimits {
+            key: Some(no_refill(4)),
+            keys: HashMap::from([("back office".to_string(), no_refill(100))]),
+            ..RateLimits::default()
+        })
+        .unwrap(),
+    );
+    let server = TestServer::with_options(ServerOptions {
+        keys: Some(ApiKeys::from_file(keys_file).unwrap()),
+        limits: Some(limiter),
+    })
+    .await;
+    let tx_counter = AtomicU32::new(1);
+
+    let responses = deposit_burst(&server, Some("client-1-test-key"), &[1], 12, &tx_counter).await;
+    assert_eq!(
+        admitted(responses.into_values().flatten().collect()).await,
+        4
+    );
+    let responses = deposit_burst(
+        &server,
+        Some("operator-test-key"),
+        &[1, 2, 3, 4],
+        12,
+        &tx_counter,
+    )
+    .await;
+    assert_eq!(
+        admitted(responses.into_values().flatten().collect()).await,
+        48
+    );
+    // Every key has a bucket of its own
+    let responses = deposit_burst(&server, Some("client-2-test-key"), &[2], 6, &tx_counter).await;
+    assert_eq!(
+        admitted(responses.into_values().flatten().collect()).await,
+        4
+    );
+}
+
+#[tokio::test]
+async fn rate_limits_reload_when_the_file_changes() {
+    let path = std::env::temp_dir().join(format!("ledger-limits-{}.toml", std::process::id()));
+    std::fs::write(&path, "[client]\nrate = 0.001\nburst = 1\n").unwrap();
+    let limiter = Arc::new(RateLimiter::new(RateLimits::from_file(&path).unwrap()).unwrap());
+    tokio::spawn(Arc::clone(&limiter).watch(path.clone(), Duration::from_millis(20)));
+    let server = TestServer::with_options(ServerOptions {
+        limits: Some(Arc::clone(&limiter)),
+        ..ServerOptions::default()
+    })
+    .await;
+    let tx_counter = AtomicU32::new(1);
+    let burst = |count| deposit_burst(&server, None, &[1], count, &tx_counter);
+
+    let responses = burst(3).await.remove(&1).unwrap();
+    assert_eq!(admitted(responses).await, 1);
+
+    // An invalid file keeps the previous limits
+    std::fs::write(&path, "[client]\nrate = -1\nburst = 1\n").unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(limiter.limits().client, Some(RateLimit::new(0.001, 1)));
+
+    std::fs::write(&path, "[client]\nrate = 1000\nburst = 5\n").unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while limiter.limits().client != Some(RateLimit::new(1000.0, 5)) {
+        assert!(Instant::now() < deadline, "limits were not reloaded");
+        tokio::time::sleep(Duration::from_millis(20)).await;
     }
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let responses = burst(5).await.remove(&1).unwrap();
+    assert_eq!(admitted(responses).await, 5);
+    std::fs::remove_file(&path).unwrap();
 }
 
-// === Tests ===
+// === Load tests ===
 // These tests are ignored in CI due to connection issues on some platforms.
 // Run manually with: cargo test --test server_test -- --ignored
 
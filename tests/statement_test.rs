@@ -0,0 +1,156 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2025 Daniel Negri
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Bank statement import integration tests against the fixtures in
+//! `tests/fixtures`.
+
+use ledger_demo_rs::{
+    ClientId, ClientLookup, Direction, Engine, StatementError, StatementFormat, StatementImporter,
+    TransactionError,
+};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+const CAMT053: &str = include_str!("fixtures/statement.camt053.xml");
+const MT940: &str = include_str!("fixtures/statement.mt940");
+const CLIENTS: &str = include_str!("fixtures/clients.csv");
+
+fn lookup() -> ClientLookup {
+    ClientLookup::from_csv(CLIENTS.as_bytes()).unwrap()
+}
+
+fn available(engine: &Engine, client: u16) -> Decimal {
+    engine.get_account(&ClientId(client)).unwrap().available
+}
+
+#[test]
+fn camt053_entries_are_parsed_in_order() {
+    let entries = StatementFormat::Camt053.parse(CAMT053).unwrap();
+
+    // The pending entry is skipped and the batch entry is split in two.
+    let summary: Vec<_> = entries
+        .iter()
+        .map(|e| (e.direction, e.amount, e.booking_date.as_deref()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (Direction::Credit, dec!(250.00), Some("2025-01-02")),
+            (Direction::Debit, dec!(75.50), Some("2025-01-03")),
+            (Direction::Credit, dec!(30.00), Some("2025-01-05")),
+            (Direction::Credit, dec!(10.00), Some("2025-01-05")),
+        ]
+    );
+    assert_eq!(entries[0].currency.as_deref(), Some("EUR"));
+    assert_eq!(entries[0].references, vec!["CUST-0001"]);
+    assert_eq!(entries[1].references, vec!["Payout CUST-0002", "PAYOUT-9"]);
+}
+
+#[test]
+fn camt053_import_reports_unmatched_entries() {
+    let import = StatementImporter::new(lookup(), 1000)
+        .import(StatementFormat::Camt053.parse(CAMT053).unwrap());
+
+    assert_eq!(import.transactions.len(), 3);
+    assert_eq!(import.unmatched.len(), 1);
+    assert_eq!(import.unmatched[0].amount, dec!(10.00));
+    assert_eq!(import.unmatched[0].references, vec!["Unknown sender"]);
+
+    // The payout precedes any deposit for client 2 and is rejected.
+    let engine = Engine::new();
+    let results: Vec<_> = import
+        .transactions
+        .into_iter()
+        .map(|tx| engine.process(tx))
+        .collect();
+    assert_eq!(
+        results,
+        vec![Ok(()), Err(TransactionError::InsufficientFunds), Ok(())]
+    );
+    assert_eq!(available(&engine, 1), dec!(250.00));
+    assert_eq!(available(&engine, 3), dec!(30.00));
+}
+
+#[test]
+fn mt940_entries_are_parsed_with_references() {
+    let entries = StatementFormat::Mt940.parse(MT940).unwrap();
+
+    let summary: Vec<_> = entries
+        .iter()
+        .map(|e| (e.line, e.direction, e.amount))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (6, Direction::Credit, dec!(250.00)),
+            (9, Direction::Debit, dec!(75.50)),
+            (11, Direction::Credit, dec!(10.00)),
+            // RC: reversal of a credit
+            (13, Direction::Debit, dec!(250.00)),
+        ]
+    );
+    assert!(entries.iter().all(|e| e.currency.as_deref() == Some("EUR")));
+    assert_eq!(entries[0].booking_date.as_deref(), Some("250102"));
+    assert_eq!(
+        entries[0].references,
+        vec!["Top-up via online banking", "/REMI/CUST-0001 top-up"]
+    );
+    assert_eq!(entries[1].references[0], "PAYOUT-9");
+    assert_eq!(
+        entries[1].references[1],
+        "EREF+PAYOUT-9 SVWZ+CUST-0002 payout"
+    );
+}
+
+#[test]
+fn mt940_import_maps_clients_and_reports_unmatched_lines() {
+    let mut importer = StatementImporter::new(lookup(), 1);
+    let import = importer.import(StatementFormat::Mt940.parse(MT940).unwrap());
+
+    assert_eq!(
+        import.unmatched.iter().map(|e| e.line).collect::<Vec<_>>(),
+        vec![11]
+    );
+    assert_eq!(import.transactions.len(), 3);
+    assert_eq!(importer.next_id(), 4);
+
+    let engine = Engine::new();
+    engine.process(import.transactions[0]).unwrap();
+    engine.process(import.transactions[2]).unwrap();
+    assert_eq!(available(&engine, 1), dec!(0.00));
+    assert_eq!(import.transactions[1].client_id(), ClientId(2));
+}
+
+#[test]
+fn malformed_statements_are_rejected_with_a_line() {
+    let err = StatementFormat::Mt940
+        .parse(":20:X\n:60F:C250101EUR0,00\n:61:250102X1,00NTRFNONREF\n-")
+        .unwrap_err();
+    assert!(
+        matches!(err, StatementError::Invalid { line: 3, .. }),
+        "{err}"
+    );
+
+    let err = StatementFormat::Camt053
+        .parse("<Document><Other/></Document>")
+        .unwrap_err();
+    assert!(err.to_string().contains("BkToCstmrStmt"), "{err}");
+    assert!(matches!(
+        StatementFormat::Camt053.parse("<Document>"),
+        Err(StatementError::Xml(_))
+    ));
+}